@@ -0,0 +1,162 @@
+//! Reproducibility attestations (`--attest`): the inputs of a run (repository HEAD, command
+//! line, tool version, and hashes of the files it read options from) and a hash of its output,
+//! optionally signed with an Ed25519 key, so that published datasets can be audited and
+//! reproduced.
+//!
+//! The attestation file is JSON with the statement as a string, so that the signed bytes are
+//! exactly what is stored:
+//!
+//! ```json
+//! {
+//!   "payload": "{\"tool\":{\"name\":\"delorean\",\"version\":\"0.1.0\"},...}",
+//!   "public_key": "<hex>",
+//!   "signature": "<hex Ed25519 signature of payload>"
+//! }
+//! ```
+//!
+//! Hashes are git object ids (SHA-1), as `git hash-object` computes them. A directory's hash is
+//! that of a blob listing `<hash> <relative path>` for each file in path order.
+
+use crate::extract::ExtractArgs;
+use crate::fixtures::list_files;
+use ed25519_dalek::{Signer, SigningKey};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize)]
+pub(crate) struct Tool {
+    name: &'static str,
+    version: &'static str,
+}
+
+impl Tool {
+    pub(crate) const CURRENT: Tool = Tool {
+        name: env!("CARGO_PKG_NAME"),
+        version: env!("CARGO_PKG_VERSION"),
+    };
+}
+
+/// The commit at HEAD of the repository at `repo_path`.
+pub(crate) fn head_commit(repo_path: &str) -> io::Result<String> {
+    let repo = git2::Repository::open(repo_path).map_err(io::Error::other)?;
+    let head = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(io::Error::other)?;
+    Ok(head.id().to_string())
+}
+
+#[derive(Serialize)]
+struct Statement {
+    tool: Tool,
+    /// The commit at HEAD of the walked repository
+    head: String,
+    /// The command line of the run, including the program name
+    arguments: Vec<String>,
+    /// Hashes of the files the run read options from (rules, templates, ignore-revs, baselines)
+    config_files: BTreeMap<PathBuf, String>,
+    /// Hash of `<OUTPUT_PATH>`, unless it is stdout
+    output: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Attestation {
+    payload: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    public_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut s, byte| {
+        write!(s, "{byte:02x}").unwrap();
+        s
+    })
+}
+
+pub(crate) fn blob_hash(content: &[u8]) -> String {
+    git2::Oid::hash_object(git2::ObjectType::Blob, content)
+        .expect("Failed to hash content")
+        .to_string()
+}
+
+/// The hash of a file, or of every file in a directory.
+fn path_hash(path: &Path) -> io::Result<String> {
+    if !path.is_dir() {
+        return Ok(blob_hash(&fs::read(path)?));
+    }
+    let mut files = BTreeSet::new();
+    list_files(path, path, &mut files);
+    let mut listing = String::new();
+    for file in files {
+        let hash = blob_hash(&fs::read(path.join(&file))?);
+        writeln!(listing, "{hash} {}", file.display()).unwrap();
+    }
+    Ok(blob_hash(listing.as_bytes()))
+}
+
+/// Reads an Ed25519 secret key: 32 bytes as 64 hex digits, e.g. from `openssl rand -hex 32`.
+fn read_signing_key(path: &Path) -> io::Result<SigningKey> {
+    let content = fs::read_to_string(path)?;
+    let content = content.trim();
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not 64 hex digits", path.display()),
+        )
+    };
+    if content.len() != 64 || !content.is_ascii() {
+        return Err(invalid());
+    }
+    let mut secret_key = [0; 32];
+    for (i, byte) in secret_key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&content[2 * i..2 * i + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(SigningKey::from_bytes(&secret_key))
+}
+
+/// Writes the attestation of a finished run with `args`, invoked as `arguments`, to `--attest`.
+pub fn write(args: &ExtractArgs, arguments: Vec<String>) -> io::Result<()> {
+    let Some(attest_path) = &args.attest else {
+        return Ok(());
+    };
+    let config_files = [
+        args.rules.as_ref(),
+        args.template.as_ref(),
+        args.ignore_revs_file.as_ref(),
+        args.baseline.as_ref(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|path| Ok((path.clone(), path_hash(path)?)))
+    .collect::<io::Result<_>>()?;
+    let statement = Statement {
+        tool: Tool::CURRENT,
+        head: head_commit(&args.repo_path)?,
+        arguments,
+        config_files,
+        output: args
+            .output_path
+            .as_deref()
+            .filter(|path| *path != "-")
+            .map(|path| path_hash(Path::new(path)))
+            .transpose()?,
+    };
+    let payload = serde_json::to_string(&statement)?;
+    let mut attestation = Attestation {
+        payload,
+        public_key: None,
+        signature: None,
+    };
+    if let Some(key_path) = &args.attest_key {
+        let key = read_signing_key(key_path)?;
+        attestation.signature = Some(hex(&key.sign(attestation.payload.as_bytes()).to_bytes()));
+        attestation.public_key = Some(hex(key.verifying_key().as_bytes()));
+    }
+    fs::write(attest_path, serde_json::to_vec_pretty(&attestation)?)
+}
@@ -0,0 +1,136 @@
+//! BigQuery output (`--output-format bigquery`): every change as a flat row of newline-delimited
+//! JSON that `bq load --source_format=NEWLINE_DELIMITED_JSON` accepts as is, with the table
+//! schema written next to it as `<OUTPUT_PATH>.schema.json`:
+//!
+//! ```sh
+//! bq load --source_format=NEWLINE_DELIMITED_JSON dataset.changes changes.ndjson changes.ndjson.schema.json
+//! ```
+//!
+//! Timestamps are always RFC 3339, whatever the `--timestamp-format`. `--trailer`s are a repeated
+//! record of `key` and `value`, the only nesting, and `--capture`d values are a JSON object in a
+//! string, since their types vary from record to record.
+
+use crate::extract::Extraction;
+use crate::output;
+use crate::record::ChangeType;
+use chrono::{DateTime, SecondsFormat};
+use serde::Serialize;
+use serde_json::json;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize)]
+struct Row<'a> {
+    path: &'a Path,
+    primary_key: &'a str,
+    change_type: ChangeType,
+    commit: &'a str,
+    timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<&'a str>,
+    trailers: Vec<Trailer<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    captured: Option<String>,
+    changed_fields: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author_name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author_email: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    committer_name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    committer_email: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct Trailer<'a> {
+    key: &'a str,
+    value: &'a str,
+}
+
+fn field(name: &str, field_type: &str, mode: &str) -> serde_json::Value {
+    json!({
+        "name": name,
+        "type": field_type,
+        "mode": mode,
+    })
+}
+
+/// The table schema of the rows, in the JSON form `bq` takes.
+pub fn schema() -> serde_json::Value {
+    json!([
+        field("path", "STRING", "REQUIRED"),
+        field("primary_key", "STRING", "REQUIRED"),
+        field("change_type", "STRING", "REQUIRED"),
+        field("commit", "STRING", "REQUIRED"),
+        field("timestamp", "TIMESTAMP", "REQUIRED"),
+        field("id", "STRING", "NULLABLE"),
+        {
+            "name": "trailers",
+            "type": "RECORD",
+            "mode": "REPEATED",
+            "fields": [
+                field("key", "STRING", "REQUIRED"),
+                field("value", "STRING", "REQUIRED"),
+            ],
+        },
+        field("captured", "STRING", "NULLABLE"),
+        field("changed_fields", "STRING", "REPEATED"),
+        field("author_name", "STRING", "NULLABLE"),
+        field("author_email", "STRING", "NULLABLE"),
+        field("committer_name", "STRING", "NULLABLE"),
+        field("committer_email", "STRING", "NULLABLE"),
+        field("summary", "STRING", "NULLABLE"),
+    ])
+}
+
+/// Where the schema of the rows written to `path` goes.
+pub fn schema_path(path: &Path) -> PathBuf {
+    let mut schema_path = OsString::from(path);
+    schema_path.push(".schema.json");
+    PathBuf::from(schema_path)
+}
+
+/// Writes every change in `extraction` to `path` in the order of [`output::change_rows`], and
+/// the schema to [`schema_path`].
+pub fn write(path: &Path, extraction: &Extraction) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for row in output::change_rows(extraction) {
+        let change = row.change;
+        let meta = change.instant.meta.as_ref();
+        let row = Row {
+            path: row.path,
+            primary_key: row.primary_key,
+            change_type: row.change_type,
+            commit: row.commit,
+            timestamp: DateTime::from_timestamp(row.timestamp, 0)
+                .unwrap()
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+            id: row.id,
+            trailers: change
+                .instant
+                .trailers
+                .iter()
+                .map(|(key, value)| Trailer { key, value })
+                .collect(),
+            captured: change
+                .captured
+                .as_ref()
+                .map(|captured| serde_json::to_string(captured).unwrap()),
+            changed_fields: change.changed_fields.as_deref().unwrap_or_default(),
+            author_name: meta.map(|meta| meta.author.name.as_str()),
+            author_email: meta.map(|meta| meta.author.email.as_str()),
+            committer_name: meta.map(|meta| meta.committer.name.as_str()),
+            committer_email: meta.map(|meta| meta.committer.email.as_str()),
+            summary: meta.map(|meta| meta.summary.as_str()),
+        };
+        serde_json::to_writer(&mut writer, &row)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    fs::write(schema_path(path), serde_json::to_vec_pretty(&schema())?)
+}
@@ -0,0 +1,104 @@
+//! Elasticsearch and OpenSearch bulk output (`--output-format elasticsearch`): every change as an
+//! `index` action line followed by a document line, which can be sent to the `_bulk` API as is
+//! (e.g. `curl -H 'Content-Type: application/x-ndjson' --data-binary @changes.ndjson
+//! localhost:9200/_bulk`) to search over the history.
+//!
+//! ```json
+//! {"index":{"_index":"delorean-changes","_id":"<id>"}}
+//! {"path":"courses.json","primary_key":"1","change_type":"added","commit":"<hash>","timestamp":"2023-01-01T00:00:00+00:00"}
+//! ```
+//!
+//! The document is the change as in the JSON output, plus its `path`, `primary_key`, and
+//! `change_type`. `--bulk-index` (`delorean-changes` by default) and `--bulk-id` are Handlebars
+//! templates rendered with the document; without `--bulk-id`, `_id` is the change's `id` under
+//! `--change-ids`, and left to the cluster otherwise.
+
+use crate::extract::{ExtractArgs, Extraction};
+use crate::output;
+use crate::record::{Change, ChangeType};
+use handlebars::Handlebars;
+use serde::Serialize;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// The index of every change without `--bulk-index`.
+pub const DEFAULT_INDEX: &str = "delorean-changes";
+
+const INDEX_TEMPLATE: &str = "index";
+const ID_TEMPLATE: &str = "id";
+
+#[derive(Serialize)]
+struct Document<'a> {
+    path: &'a Path,
+    primary_key: &'a str,
+    change_type: ChangeType,
+    #[serde(flatten)]
+    change: &'a Change,
+}
+
+#[derive(Serialize)]
+struct Action {
+    index: IndexAction,
+}
+
+#[derive(Serialize)]
+struct IndexAction {
+    #[serde(rename = "_index")]
+    index: String,
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+}
+
+/// Writes every change in `extraction` to `writer` as bulk actions, in the order of
+/// [`output::change_rows`].
+pub fn write<W: Write>(
+    mut writer: W,
+    extraction: &Extraction,
+    args: &ExtractArgs,
+) -> io::Result<()> {
+    let mut handlebars = Handlebars::new();
+    // Index names and IDs are not HTML.
+    handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars.set_strict_mode(true);
+    handlebars
+        .register_template_string(
+            INDEX_TEMPLATE,
+            args.bulk_index.as_deref().unwrap_or(DEFAULT_INDEX),
+        )
+        .map_err(io::Error::other)?;
+    if let Some(id_template) = &args.bulk_id {
+        handlebars
+            .register_template_string(ID_TEMPLATE, id_template)
+            .map_err(io::Error::other)?;
+    }
+    for row in output::change_rows(extraction) {
+        let document = Document {
+            path: row.path,
+            primary_key: row.primary_key,
+            change_type: row.change_type,
+            change: row.change,
+        };
+        let id = if handlebars.has_template(ID_TEMPLATE) {
+            Some(
+                handlebars
+                    .render(ID_TEMPLATE, &document)
+                    .map_err(io::Error::other)?,
+            )
+        } else {
+            row.id.map(str::to_string)
+        };
+        let action = Action {
+            index: IndexAction {
+                index: handlebars
+                    .render(INDEX_TEMPLATE, &document)
+                    .map_err(io::Error::other)?,
+                id,
+            },
+        };
+        serde_json::to_writer(&mut writer, &action)?;
+        writer.write_all(b"\n")?;
+        serde_json::to_writer(&mut writer, &document)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
@@ -0,0 +1,189 @@
+//! Sync calendar checks (`--calendar`): a YAML file of the windows syncs are expected in and the
+//! holidays on which they are not, checked against the walked commits to report the windows that
+//! had no sync and the changes made outside every window or on a holiday
+//! (`--calendar-report`).
+//!
+//! A calendar file lists weekly windows, in the syntax of `--flag-outside-schedule`, and blackout
+//! days or inclusive ranges of days, in UTC:
+//!
+//! ```yaml
+//! windows:
+//!   - Mon-Fri 02:00-04:00
+//! blackouts:
+//!   - 2024-12-23..2025-01-01
+//!   - 2025-07-04
+//! ```
+
+use crate::extract::Extraction;
+use crate::predicate::Schedule;
+use crate::record::ChangeInstant;
+use chrono::{DateTime, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+struct CalendarFile {
+    windows: Vec<String>,
+    #[serde(default)]
+    blackouts: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Calendar {
+    pub windows: Vec<Schedule>,
+    /// Inclusive ranges of days without syncs
+    pub blackouts: Vec<(NaiveDate, NaiveDate)>,
+}
+
+fn parse_day(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("Expected a day like 2024-12-25, got {s}"))
+}
+
+pub fn load(path: &Path) -> io::Result<Calendar> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let content = fs::read_to_string(path)?;
+    let file: CalendarFile = serde_yaml::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+    let windows = file
+        .windows
+        .iter()
+        .map(|window| window.parse())
+        .collect::<Result<_, _>>()
+        .map_err(invalid)?;
+    let blackouts = file
+        .blackouts
+        .iter()
+        .map(|blackout| {
+            let (first, last) = blackout.split_once("..").unwrap_or((blackout, blackout));
+            Ok((parse_day(first)?, parse_day(last)?))
+        })
+        .collect::<Result<_, String>>()
+        .map_err(invalid)?;
+    Ok(Calendar { windows, blackouts })
+}
+
+impl Calendar {
+    fn is_blackout(&self, day: NaiveDate) -> bool {
+        self.blackouts
+            .iter()
+            .any(|(first, last)| *first <= day && day <= *last)
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct Window {
+    pub start: i64,
+    pub end: i64,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnexpectedReason {
+    /// On a blackout day
+    Blackout,
+    /// Outside every window
+    OutsideWindows,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnexpectedChange {
+    #[serde(flatten)]
+    pub instant: Arc<ChangeInstant>,
+    pub reason: UnexpectedReason,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CalendarReport {
+    /// Windows between the first and last walked sync in which no commit touched a tracked
+    /// file, oldest first
+    pub missing_syncs: Vec<Window>,
+    /// Commits that changed records outside every window or on a blackout day, oldest first
+    pub unexpected_changes: Vec<UnexpectedChange>,
+}
+
+fn day(timestamp: i64) -> NaiveDate {
+    DateTime::from_timestamp(timestamp, 0).unwrap().date_naive()
+}
+
+/// Checks the commits of `extraction` against `calendar`. A sync is any walked commit that
+/// touched a tracked file, whether or not it changed records.
+pub fn report(calendar: &Calendar, extraction: &Extraction) -> CalendarReport {
+    let mut changes: BTreeMap<(i64, &str), &Arc<ChangeInstant>> = BTreeMap::new();
+    for record in extraction
+        .change_records
+        .values()
+        .flat_map(|records| records.values())
+    {
+        for change in record
+            .added
+            .iter()
+            .chain(&record.removed)
+            .chain(&record.modified)
+        {
+            changes.insert(
+                (change.instant.timestamp, &change.instant.commit),
+                &change.instant,
+            );
+        }
+    }
+    let mut syncs = changes
+        .keys()
+        .map(|(timestamp, _)| *timestamp)
+        .chain(extraction.sync_times.iter().copied())
+        .collect::<Vec<_>>();
+    syncs.sort_unstable();
+
+    let mut missing_syncs = vec![];
+    if let (Some(&first), Some(&last)) = (syncs.first(), syncs.last()) {
+        // The window of the first sync may have started the day before
+        for day in day(first)
+            .pred_opt()
+            .unwrap()
+            .iter_days()
+            .take_while(|d| *d <= day(last))
+        {
+            if calendar.is_blackout(day) {
+                continue;
+            }
+            for (start, end) in calendar.windows.iter().filter_map(|w| w.window_on(day)) {
+                let synced = syncs[syncs.partition_point(|sync| *sync < start)..]
+                    .first()
+                    .is_some_and(|sync| *sync < end);
+                if end > first && start <= last && !synced {
+                    missing_syncs.push(Window { start, end });
+                }
+            }
+        }
+        missing_syncs.sort_by_key(|window| window.start);
+        missing_syncs.dedup();
+    }
+
+    let unexpected_changes = changes
+        .into_values()
+        .filter_map(|instant| {
+            let reason = if calendar.is_blackout(day(instant.timestamp)) {
+                UnexpectedReason::Blackout
+            } else if !calendar
+                .windows
+                .iter()
+                .any(|w| w.contains(instant.timestamp))
+            {
+                UnexpectedReason::OutsideWindows
+            } else {
+                return None;
+            };
+            Some(UnexpectedChange {
+                instant: instant.clone(),
+                reason,
+            })
+        })
+        .collect();
+    CalendarReport {
+        missing_syncs,
+        unexpected_changes,
+    }
+}
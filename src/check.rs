@@ -0,0 +1,188 @@
+//! `delorean check`: compares uncommitted versions of the tracked files against HEAD and fails
+//! if too many records would be removed or modified, for use as a pre-commit hook in the data
+//! repository.
+
+use crate::diff::diff_records;
+use crate::errors::{ErrorBudget, ErrorReport, RecoverableError};
+use crate::extract::{self, ExtractArgs};
+use glob_match::glob_match;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+/// Options of `delorean check` on top of the extraction options.
+#[derive(clap::Args, Debug, Clone)]
+pub struct CheckArgs {
+    /// Check the staged versions of the tracked files instead of the working tree
+    #[arg(long)]
+    pub staged: bool,
+
+    /// Fail if more than this percentage of a file's records at HEAD would be removed
+    #[arg(long, value_name = "PERCENT", default_value_t = 30.0)]
+    pub max_removed_percent: f64,
+
+    /// Fail if more than this percentage of a file's records at HEAD would be modified
+    #[arg(long, value_name = "PERCENT")]
+    pub max_modified_percent: Option<f64>,
+}
+
+/// How the records of one tracked file would change.
+#[derive(Debug, Clone)]
+pub struct FileCheck {
+    pub path: PathBuf,
+    /// Number of records at HEAD
+    pub records: usize,
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+    /// Thresholds this file exceeds, as human-readable descriptions
+    pub failures: Vec<String>,
+}
+
+fn percent(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 * 100.0 / total as f64
+    }
+}
+
+impl fmt::Display for FileCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} records, +{} -{} ~{}",
+            self.path.display(),
+            self.records,
+            self.added,
+            self.removed,
+            self.modified
+        )?;
+        for failure in &self.failures {
+            write!(f, "\n  FAIL: {failure}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of `delorean check`.
+#[derive(Debug)]
+pub struct Check {
+    pub files: Vec<FileCheck>,
+    pub errors: Vec<RecoverableError>,
+    pub warnings: Vec<RecoverableError>,
+}
+
+impl Check {
+    pub fn passed(&self) -> bool {
+        self.files.iter().all(|file| file.failures.is_empty())
+    }
+}
+
+/// Compares the staged or working tree version of every changed tracked file against HEAD.
+pub fn run(args: &ExtractArgs, check_args: &CheckArgs) -> Result<Check, ErrorReport> {
+    let repo = git2::Repository::open(&args.repo_path).expect("Failed to open repository");
+    let head = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .expect("Failed to find HEAD commit");
+    let head_id = head.id().to_string();
+    let head_tree = head.tree().expect("Failed to get HEAD tree");
+    let diff = if check_args.staged {
+        repo.diff_tree_to_index(Some(&head_tree), None, None)
+    } else {
+        repo.diff_tree_to_workdir_with_index(Some(&head_tree), None)
+    }
+    .expect("Failed to diff against HEAD");
+    let new_label = if check_args.staged {
+        "index"
+    } else {
+        "working tree"
+    };
+    let diff_options = extract::diff_options(args);
+    let mut errors = ErrorBudget::new(args.max_errors);
+    let mut files = vec![];
+    for delta in diff.deltas() {
+        let old_path = delta.old_file().path().unwrap();
+        let new_path = delta.new_file().path().unwrap();
+        if !glob_match(&args.include, old_path.to_str().unwrap())
+            && !glob_match(&args.include, new_path.to_str().unwrap())
+        {
+            continue;
+        }
+        let old_content = match delta.status() {
+            git2::Delta::Added | git2::Delta::Untracked => Some(HashMap::new()),
+            _ => extract::get_json_data(
+                &repo,
+                &head_tree,
+                old_path,
+                args,
+                None,
+                &head_id,
+                &mut errors,
+            )?,
+        };
+        let new_content = match delta.status() {
+            git2::Delta::Deleted => Some(HashMap::new()),
+            _ => {
+                let content = if check_args.staged {
+                    repo.find_blob(delta.new_file().id())
+                        .expect("Failed to read staged file")
+                        .content()
+                        .to_vec()
+                } else {
+                    let workdir = repo.workdir().expect("Repository has no working tree");
+                    fs::read(workdir.join(new_path)).expect("Failed to read file")
+                };
+                extract::parse_records(&content, new_path, args, None, new_label, &mut errors)?
+            }
+        };
+        let (Some(old_content), Some(new_content)) = (old_content, new_content) else {
+            continue;
+        };
+        let mut file = FileCheck {
+            path: new_path.to_path_buf(),
+            records: old_content.len(),
+            added: new_content
+                .keys()
+                .filter(|pk| !old_content.contains_key(*pk))
+                .count(),
+            removed: 0,
+            modified: 0,
+            failures: vec![],
+        };
+        for (pk, old_val) in &old_content {
+            match new_content.get(pk) {
+                None => file.removed += 1,
+                Some(new_val) if diff_records(old_val, new_val, &diff_options).changed => {
+                    file.modified += 1
+                }
+                Some(_) => {}
+            }
+        }
+        let removed_percent = percent(file.removed, file.records);
+        if removed_percent > check_args.max_removed_percent {
+            file.failures.push(format!(
+                "{removed_percent:.1}% of records removed (--max-removed-percent {})",
+                check_args.max_removed_percent
+            ));
+        }
+        let modified_percent = percent(file.modified, file.records);
+        if let Some(max_modified_percent) = check_args.max_modified_percent {
+            if modified_percent > max_modified_percent {
+                file.failures.push(format!(
+                    "{modified_percent:.1}% of records modified (--max-modified-percent \
+                     {max_modified_percent})"
+                ));
+            }
+        }
+        files.push(file);
+    }
+    let (errors, warnings) = errors.finish();
+    Ok(Check {
+        files,
+        errors,
+        warnings,
+    })
+}
@@ -0,0 +1,221 @@
+//! Comparison of two output directories, e.g. from runs before and after changing diff options,
+//! by the change instants each record gained or lost.
+
+use crate::fixtures::list_files;
+use crate::record::ChangeType;
+use crate::{index, manifest, meta};
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A change instant as read back from an output file.
+#[derive(Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct InstantEntry {
+    pub(crate) commit: String,
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub(crate) timestamp: String,
+}
+
+/// Reads a timestamp written with any `--timestamp-format`, keeping numbers as their digits.
+fn deserialize_timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::String(timestamp) => timestamp,
+        timestamp => timestamp.to_string(),
+    })
+}
+
+/// A change record as read back from an output file.
+#[derive(Deserialize)]
+pub(crate) struct RecordEntry {
+    #[serde(default)]
+    added: BTreeSet<InstantEntry>,
+    #[serde(default)]
+    removed: BTreeSet<InstantEntry>,
+    #[serde(default)]
+    modified: BTreeSet<InstantEntry>,
+}
+
+impl RecordEntry {
+    pub(crate) fn instants(&self, change_type: ChangeType) -> &BTreeSet<InstantEntry> {
+        match change_type {
+            ChangeType::Added => &self.added,
+            ChangeType::Removed => &self.removed,
+            ChangeType::Modified => &self.modified,
+        }
+    }
+}
+
+/// A change instant present in only one of the two runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstantDelta {
+    pub change_type: ChangeType,
+    pub commit: String,
+    pub timestamp: String,
+}
+
+/// How a record present in both runs differs between them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordDelta {
+    pub path: PathBuf,
+    pub primary_key: String,
+    /// Instants only in the second run
+    pub gained: Vec<InstantDelta>,
+    /// Instants only in the first run
+    pub lost: Vec<InstantDelta>,
+}
+
+/// The differences between two output directories. Paths are relative to the directories.
+#[derive(Debug, Default)]
+pub struct Comparison {
+    pub files_only_in_a: Vec<PathBuf>,
+    pub files_only_in_b: Vec<PathBuf>,
+    pub records_only_in_a: Vec<(PathBuf, String)>,
+    pub records_only_in_b: Vec<(PathBuf, String)>,
+    pub changed: Vec<RecordDelta>,
+}
+
+impl Comparison {
+    pub fn is_empty(&self) -> bool {
+        self.files_only_in_a.is_empty()
+            && self.files_only_in_b.is_empty()
+            && self.records_only_in_a.is_empty()
+            && self.records_only_in_b.is_empty()
+            && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for path in &self.files_only_in_a {
+            writeln!(f, "Only in A: {}", path.display())?;
+        }
+        for path in &self.files_only_in_b {
+            writeln!(f, "Only in B: {}", path.display())?;
+        }
+        for (path, primary_key) in &self.records_only_in_a {
+            writeln!(f, "Only in A: {} {primary_key}", path.display())?;
+        }
+        for (path, primary_key) in &self.records_only_in_b {
+            writeln!(f, "Only in B: {} {primary_key}", path.display())?;
+        }
+        for delta in &self.changed {
+            writeln!(f, "{} {}:", delta.path.display(), delta.primary_key)?;
+            for (sign, instants) in [('+', &delta.gained), ('-', &delta.lost)] {
+                for instant in instants {
+                    writeln!(
+                        f,
+                        "  {sign}{} {} {}",
+                        instant.change_type, instant.commit, instant.timestamp
+                    )?;
+                }
+            }
+        }
+        let gained = self.changed.iter().filter(|d| !d.gained.is_empty()).count();
+        let lost = self.changed.iter().filter(|d| !d.lost.is_empty()).count();
+        writeln!(
+            f,
+            "{gained} records gained instants, {lost} records lost instants, {} records only in A, \
+             {} records only in B",
+            self.records_only_in_a.len(),
+            self.records_only_in_b.len()
+        )
+    }
+}
+
+/// Reads the change records of an output file. Only uncompressed JSON files of a directory split
+/// by path, without `--compact-commits` or `--chunk-size`, can be read; any other file is an error
+/// naming it.
+pub(crate) fn read_records(path: &Path) -> Result<HashMap<String, RecordEntry>, String> {
+    let content = fs::read(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    serde_json::from_slice(&content).map_err(|e| {
+        format!(
+            "{} is not a file of change records ({e}); only uncompressed JSON output split by \
+             path, without --compact-commits, --chunk-size, or --cdn-mode, can be read back",
+            path.display()
+        )
+    })
+}
+
+/// The instants of `change_type` in `instants` but not in `other`.
+fn instant_deltas(
+    change_type: ChangeType,
+    instants: &BTreeSet<InstantEntry>,
+    other: &BTreeSet<InstantEntry>,
+) -> Vec<InstantDelta> {
+    instants
+        .difference(other)
+        .map(|instant| InstantDelta {
+            change_type,
+            commit: instant.commit.clone(),
+            timestamp: instant.timestamp.clone(),
+        })
+        .collect()
+}
+
+/// Compares the change records in output directories `a` and `b`. Fails on a file in both that
+/// is not a file of change records, such as one written with `--compress` or `--compact-commits`.
+pub fn compare_outputs(a: &Path, b: &Path) -> Result<Comparison, String> {
+    let mut files_a = BTreeSet::new();
+    list_files(a, a, &mut files_a);
+    let mut files_b = BTreeSet::new();
+    list_files(b, b, &mut files_b);
+    for files in [&mut files_a, &mut files_b] {
+        files.remove(Path::new(meta::FILE_NAME));
+        files.remove(Path::new(manifest::FILE_NAME));
+        files.remove(Path::new(index::FILE_NAME));
+    }
+
+    let mut comparison = Comparison {
+        files_only_in_a: files_a.difference(&files_b).cloned().collect(),
+        files_only_in_b: files_b.difference(&files_a).cloned().collect(),
+        ..Default::default()
+    };
+    for path in files_a.intersection(&files_b) {
+        let records_a = read_records(&a.join(path))?;
+        let records_b = read_records(&b.join(path))?;
+        let keys = records_a
+            .keys()
+            .chain(records_b.keys())
+            .collect::<BTreeSet<_>>();
+        for key in keys {
+            let (record_a, record_b) = match (records_a.get(key), records_b.get(key)) {
+                (Some(record_a), Some(record_b)) => (record_a, record_b),
+                (Some(_), None) => {
+                    comparison
+                        .records_only_in_a
+                        .push((path.clone(), key.clone()));
+                    continue;
+                }
+                _ => {
+                    comparison
+                        .records_only_in_b
+                        .push((path.clone(), key.clone()));
+                    continue;
+                }
+            };
+            let mut delta = RecordDelta {
+                path: path.clone(),
+                primary_key: key.clone(),
+                gained: vec![],
+                lost: vec![],
+            };
+            for change_type in [ChangeType::Added, ChangeType::Removed, ChangeType::Modified] {
+                let instants_a = record_a.instants(change_type);
+                let instants_b = record_b.instants(change_type);
+                delta
+                    .gained
+                    .extend(instant_deltas(change_type, instants_b, instants_a));
+                delta
+                    .lost
+                    .extend(instant_deltas(change_type, instants_a, instants_b));
+            }
+            if !delta.gained.is_empty() || !delta.lost.is_empty() {
+                comparison.changed.push(delta);
+            }
+        }
+    }
+    Ok(comparison)
+}
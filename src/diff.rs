@@ -0,0 +1,286 @@
+//! The record-level differ: decides whether two versions of the same record differ.
+//!
+//! This module has no dependency on git and can be used on its own:
+//!
+//! ```
+//! use delorean::diff::{diff_records, DiffOptions};
+//! use serde_json::json;
+//!
+//! let old = json!({ "crn": "1", "title": "A", "last_updated": "2023-01-01" });
+//! let new = json!({ "crn": "1", "title": "A", "last_updated": "2023-01-02" });
+//! let options = DiffOptions {
+//!     ignore_fields: vec!["/last_updated".to_string()],
+//!     ..Default::default()
+//! };
+//! assert!(diff_records(&old, &new, &DiffOptions::default()).changed);
+//! assert!(!diff_records(&old, &new, &options).changed);
+//! ```
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Options controlling how two versions of a record are compared.
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    /// JSON pointers (RFC 6901, e.g. `/last_updated` or `/professors/0/email`) of values that are
+    /// ignored when comparing. An ignored value may differ, or be missing on either side.
+    pub ignore_fields: Vec<String>,
+    /// Compare arrays as unordered collections, so reordering is not a change.
+    pub ignore_array_order: bool,
+    /// Compare numbers by value, so `1` and `1.0` are equal.
+    pub normalize_numbers: bool,
+    /// Compare objects by their values alone, so renaming a field is not a change.
+    pub ignore_field_names: bool,
+}
+
+/// The outcome of comparing two versions of a record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffResult {
+    pub changed: bool,
+}
+
+/// Compares two versions of a record. The result is symmetric: swapping `old` and `new` never
+/// changes whether the record is considered changed.
+pub fn diff_records(old: &Value, new: &Value, options: &DiffOptions) -> DiffResult {
+    let mut pointer = String::new();
+    DiffResult {
+        changed: deep_diff_json(old, new, &mut pointer, options, None),
+    }
+}
+
+/// One operation of an RFC 6902 JSON Patch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+}
+
+/// A JSON Patch that turns `old` into `new`, empty exactly when [`diff_records`] finds no
+/// change. Ignored fields are left out of the patch, and a value compared under a normalization
+/// (e.g. an array under `ignore_array_order`) is replaced as a whole if it changed.
+pub fn json_patch(old: &Value, new: &Value, options: &DiffOptions) -> Vec<PatchOperation> {
+    let mut pointer = String::new();
+    let mut patch = vec![];
+    deep_diff_json(old, new, &mut pointer, options, Some(&mut patch));
+    patch
+}
+
+/// The top-level fields whose values differ between `old` and `new`, in key order, including
+/// fields present on only one side. Empty unless both are objects.
+pub fn changed_fields(old: &Value, new: &Value, options: &DiffOptions) -> Vec<String> {
+    let (Value::Object(old_obj), Value::Object(new_obj)) = (old, new) else {
+        return vec![];
+    };
+    let mut keys = old_obj
+        .keys()
+        .chain(new_obj.keys().filter(|key| !old_obj.contains_key(*key)))
+        .collect::<Vec<_>>();
+    keys.sort();
+    let mut pointer = String::new();
+    keys.into_iter()
+        .filter(|key| {
+            push_pointer_segment(&mut pointer, key);
+            let changed = match (old_obj.get(*key), new_obj.get(*key)) {
+                (Some(old_val), Some(new_val)) => {
+                    deep_diff_json(old_val, new_val, &mut pointer, options, None)
+                }
+                _ => !is_ignored(&pointer, options),
+            };
+            pointer.clear();
+            changed
+        })
+        .cloned()
+        .collect()
+}
+
+fn push_pointer_segment(pointer: &mut String, segment: &str) {
+    pointer.push('/');
+    pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+}
+
+fn is_ignored(pointer: &str, options: &DiffOptions) -> bool {
+    options.ignore_fields.iter().any(|field| field == pointer)
+}
+
+/// The form of `value` under the normalizations enabled in `options`, with ignored fields left
+/// out: two values are equivalent exactly when their canonical forms are equal.
+fn canonicalize(value: &Value, pointer: &mut String, options: &DiffOptions) -> Value {
+    let pointer_len = pointer.len();
+    let mut canonicalize_child = |segment: &str, child: &Value| {
+        push_pointer_segment(pointer, segment);
+        let canonical =
+            (!is_ignored(pointer, options)).then(|| canonicalize(child, pointer, options));
+        pointer.truncate(pointer_len);
+        canonical
+    };
+    match value {
+        Value::Number(n) if options.normalize_numbers => {
+            n.as_f64().map_or(Value::Null, Value::from)
+        }
+        Value::Array(items) => {
+            let mut items = items
+                .iter()
+                .enumerate()
+                .filter_map(|(i, item)| canonicalize_child(&i.to_string(), item))
+                .collect::<Vec<_>>();
+            if options.ignore_array_order {
+                items.sort_by_cached_key(|item| item.to_string());
+            }
+            Value::Array(items)
+        }
+        Value::Object(obj) if options.ignore_field_names => {
+            let mut values = obj
+                .iter()
+                .filter_map(|(key, child)| canonicalize_child(key, child))
+                .collect::<Vec<_>>();
+            values.sort_by_cached_key(|item| item.to_string());
+            json!({ "values": values })
+        }
+        Value::Object(obj) => Value::Object(
+            obj.iter()
+                .filter_map(|(key, child)| {
+                    canonicalize_child(key, child).map(|canonical| (key.clone(), canonical))
+                })
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Records an operation if a patch is being built. Returns `true`, since every operation is a
+/// change.
+fn push_operation(patch: Option<&mut Vec<PatchOperation>>, operation: PatchOperation) -> bool {
+    if let Some(patch) = patch {
+        patch.push(operation);
+    }
+    true
+}
+
+/// Whether `old_json` and `new_json` differ. With a `patch`, every difference is appended to it
+/// as an operation; without one, the comparison stops at the first difference.
+fn deep_diff_json(
+    old_json: &Value,
+    new_json: &Value,
+    pointer: &mut String,
+    options: &DiffOptions,
+    mut patch: Option<&mut Vec<PatchOperation>>,
+) -> bool {
+    if is_ignored(pointer, options) {
+        return false;
+    }
+    let replace = |patch, pointer: &str| {
+        push_operation(
+            patch,
+            PatchOperation::Replace {
+                path: pointer.to_string(),
+                value: new_json.clone(),
+            },
+        )
+    };
+    let pointer_len = pointer.len();
+    let mut changed = false;
+    match (old_json, new_json) {
+        (Value::Array(_), Value::Array(_)) if options.ignore_array_order => {
+            if canonicalize(old_json, pointer, options) != canonicalize(new_json, pointer, options)
+            {
+                return replace(patch, pointer);
+            }
+        }
+        (Value::Object(_), Value::Object(_)) if options.ignore_field_names => {
+            if canonicalize(old_json, pointer, options) != canonicalize(new_json, pointer, options)
+            {
+                return replace(patch, pointer);
+            }
+        }
+        (Value::Number(old_num), Value::Number(new_num)) if options.normalize_numbers => {
+            if old_num.as_f64() != new_num.as_f64() {
+                return replace(patch, pointer);
+            }
+        }
+        (Value::Object(old_obj), Value::Object(new_obj)) => {
+            let keys = old_obj
+                .keys()
+                .chain(new_obj.keys().filter(|key| !old_obj.contains_key(*key)));
+            for key in keys {
+                push_pointer_segment(pointer, key);
+                changed |= match (old_obj.get(key), new_obj.get(key)) {
+                    (Some(old_val), Some(new_val)) => {
+                        deep_diff_json(old_val, new_val, pointer, options, patch.as_deref_mut())
+                    }
+                    _ if is_ignored(pointer, options) => false,
+                    (_, None) => push_operation(
+                        patch.as_deref_mut(),
+                        PatchOperation::Remove {
+                            path: pointer.clone(),
+                        },
+                    ),
+                    (_, Some(new_val)) => push_operation(
+                        patch.as_deref_mut(),
+                        PatchOperation::Add {
+                            path: pointer.clone(),
+                            value: new_val.clone(),
+                        },
+                    ),
+                };
+                pointer.truncate(pointer_len);
+                if changed && patch.is_none() {
+                    return true;
+                }
+            }
+        }
+        (Value::Array(old_arr), Value::Array(new_arr)) => {
+            if patch.is_none() {
+                for i in old_arr.len().min(new_arr.len())..old_arr.len().max(new_arr.len()) {
+                    push_pointer_segment(pointer, &i.to_string());
+                    let ignored = is_ignored(pointer, options);
+                    pointer.truncate(pointer_len);
+                    if !ignored {
+                        return true;
+                    }
+                }
+            }
+            for (i, (old_val, new_val)) in old_arr.iter().zip(new_arr.iter()).enumerate() {
+                push_pointer_segment(pointer, &i.to_string());
+                changed |= deep_diff_json(old_val, new_val, pointer, options, patch.as_deref_mut());
+                pointer.truncate(pointer_len);
+                if changed && patch.is_none() {
+                    return true;
+                }
+            }
+            // Trailing elements are removed from the back, so earlier indices stay valid.
+            for i in (new_arr.len()..old_arr.len()).rev() {
+                push_pointer_segment(pointer, &i.to_string());
+                if !is_ignored(pointer, options) {
+                    changed = push_operation(
+                        patch.as_deref_mut(),
+                        PatchOperation::Remove {
+                            path: pointer.clone(),
+                        },
+                    );
+                }
+                pointer.truncate(pointer_len);
+            }
+            for (i, new_val) in new_arr.iter().enumerate().skip(old_arr.len()) {
+                push_pointer_segment(pointer, &i.to_string());
+                if !is_ignored(pointer, options) {
+                    changed = push_operation(
+                        patch.as_deref_mut(),
+                        PatchOperation::Add {
+                            path: pointer.clone(),
+                            value: new_val.clone(),
+                        },
+                    );
+                }
+                pointer.truncate(pointer_len);
+            }
+        }
+        (old_val, new_val) => {
+            if old_val != new_val {
+                return replace(patch, pointer);
+            }
+        }
+    }
+    changed
+}
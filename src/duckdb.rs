@@ -0,0 +1,69 @@
+//! DuckDB output (`--output-format duckdb`): every change as a row of a single database file, so
+//! the history can be analyzed in DuckDB without importing the JSON output. Built with the
+//! `duckdb` feature.
+//!
+//! ```sql
+//! CREATE TABLE changes (
+//!     path VARCHAR NOT NULL,
+//!     primary_key VARCHAR NOT NULL,
+//!     change_type VARCHAR NOT NULL, -- added, removed, or modified
+//!     commit_hash VARCHAR NOT NULL,
+//!     timestamp TIMESTAMP NOT NULL, -- UTC
+//!     id VARCHAR                     -- under `--change-ids`
+//! );
+//! CREATE INDEX changes_record ON changes (path, primary_key);
+//! CREATE INDEX changes_timestamp ON changes (timestamp);
+//! ```
+
+use crate::extract::Extraction;
+use crate::output;
+use ::duckdb::types::{TimeUnit, Value};
+use ::duckdb::{params, Connection};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE changes (
+    path VARCHAR NOT NULL,
+    primary_key VARCHAR NOT NULL,
+    change_type VARCHAR NOT NULL,
+    commit_hash VARCHAR NOT NULL,
+    timestamp TIMESTAMP NOT NULL,
+    id VARCHAR
+);
+";
+
+/// Created after the rows are appended, which DuckDB does faster than maintaining them per row.
+const INDEXES: &str = "
+CREATE INDEX changes_record ON changes (path, primary_key);
+CREATE INDEX changes_timestamp ON changes (timestamp);
+";
+
+/// Writes every change in `extraction` to a new database at `path`, replacing any existing file,
+/// in the order of [`output::change_rows`].
+pub fn write(path: &Path, extraction: &Extraction) -> ::duckdb::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+            panic!("Failed to remove {}: {e}", path.display())
+        }
+        _ => {}
+    }
+    let connection = Connection::open(path)?;
+    connection.execute_batch(SCHEMA)?;
+    {
+        let mut appender = connection.appender("changes")?;
+        for row in output::change_rows(extraction) {
+            appender.append_row(params![
+                row.path.to_string_lossy().into_owned(),
+                row.primary_key,
+                row.change_type.to_string(),
+                row.commit,
+                Value::Timestamp(TimeUnit::Second, row.timestamp),
+                row.id,
+            ])?;
+        }
+        appender.flush()?;
+    }
+    connection.execute_batch(INDEXES)
+}
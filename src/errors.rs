@@ -0,0 +1,145 @@
+//! Recoverable errors (unparseable files, records without a usable primary key) and the
+//! `--max-errors` budget that decides when to stop tolerating them, plus warnings for data that
+//! is skipped by design and never counts against the budget.
+
+use serde::Serialize;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// A problem that made extraction skip part of a commit instead of aborting. Also used for
+/// warnings. `path` is empty for problems with the commit itself.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoverableError {
+    pub commit: String,
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl fmt::Display for RecoverableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.as_os_str().is_empty() {
+            return write!(f, "{}: {}", self.commit, self.message);
+        }
+        write!(
+            f,
+            "{} in {}: {}",
+            self.path.display(),
+            self.commit,
+            self.message
+        )
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Serialize)]
+struct WarningLine<'a> {
+    severity: Severity,
+    #[serde(flatten)]
+    problem: &'a RecoverableError,
+}
+
+/// Writes warnings and skipped errors to `path` as NDJSON, one object per line with `severity`
+/// (`warning` or `error`), `commit`, `path`, and `message`.
+pub fn write_ndjson(
+    path: &Path,
+    warnings: &[RecoverableError],
+    errors: &[RecoverableError],
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let lines = warnings
+        .iter()
+        .map(|problem| (Severity::Warning, problem))
+        .chain(errors.iter().map(|problem| (Severity::Error, problem)));
+    for (severity, problem) in lines {
+        serde_json::to_writer(&mut writer, &WarningLine { severity, problem })?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+/// Returned by an extraction that hit more recoverable errors than `--max-errors` allows.
+#[derive(Debug)]
+pub struct ErrorReport {
+    pub max_errors: usize,
+    pub errors: Vec<RecoverableError>,
+    /// Warnings collected before the extraction aborted
+    pub warnings: Vec<RecoverableError>,
+}
+
+impl fmt::Display for ErrorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Aborting after {} errors (--max-errors {}):",
+            self.errors.len(),
+            self.max_errors
+        )?;
+        for error in &self.errors {
+            writeln!(f, "  {error}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ErrorReport {}
+
+/// Collects recoverable errors until more than `max_errors` have occurred, and warnings.
+pub struct ErrorBudget {
+    max_errors: usize,
+    errors: Vec<RecoverableError>,
+    warnings: Vec<RecoverableError>,
+}
+
+impl ErrorBudget {
+    pub fn new(max_errors: usize) -> Self {
+        ErrorBudget {
+            max_errors,
+            errors: vec![],
+            warnings: vec![],
+        }
+    }
+
+    /// Records an error, failing with the consolidated report once the budget is exceeded.
+    pub fn record(
+        &mut self,
+        commit: &str,
+        path: &Path,
+        message: impl Into<String>,
+    ) -> Result<(), ErrorReport> {
+        self.errors.push(RecoverableError {
+            commit: commit.to_string(),
+            path: path.to_path_buf(),
+            message: message.into(),
+        });
+        if self.errors.len() > self.max_errors {
+            return Err(ErrorReport {
+                max_errors: self.max_errors,
+                errors: std::mem::take(&mut self.errors),
+                warnings: std::mem::take(&mut self.warnings),
+            });
+        }
+        Ok(())
+    }
+
+    /// Records a warning, which does not count against the budget.
+    pub fn warn(&mut self, commit: &str, path: &Path, message: impl Into<String>) {
+        self.warnings.push(RecoverableError {
+            commit: commit.to_string(),
+            path: path.to_path_buf(),
+            message: message.into(),
+        });
+    }
+
+    /// Returns the errors and warnings collected so far.
+    pub fn finish(self) -> (Vec<RecoverableError>, Vec<RecoverableError>) {
+        (self.errors, self.warnings)
+    }
+}
@@ -0,0 +1,232 @@
+use crate::plugin;
+use crate::record::{ChangeInstant, ChangeType, ChangeValues};
+use handlebars::Handlebars;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Stdio};
+use std::str::FromStr;
+
+/// Where `--output` sends the stream of change events.
+#[derive(Debug, Clone)]
+pub enum OutputTarget {
+    Stdout,
+    /// A Unix domain socket that some other process is already listening on.
+    Unix(PathBuf),
+    /// The stdin of the [`plugin`] of this name, which is started for the stream.
+    Plugin(String),
+}
+
+impl FromStr for OutputTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "-" {
+            return Ok(OutputTarget::Stdout);
+        }
+        if let Some(socket_path) = s.strip_prefix("unix:") {
+            return Ok(OutputTarget::Unix(PathBuf::from(socket_path)));
+        }
+        if let Some(name) = s.strip_prefix("plugin:") {
+            return Ok(OutputTarget::Plugin(name.to_string()));
+        }
+        Err(format!(
+            "Unsupported output target {s} (expected '-', 'unix:<path>', or 'plugin:<name>')"
+        ))
+    }
+}
+
+/// How each event is written to the `--output` stream.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default)]
+pub enum EventFormat {
+    /// One JSON object per line
+    #[default]
+    Ndjson,
+    /// Rendered through the Handlebars template given by `--template`
+    Template,
+}
+
+enum Encoder {
+    Ndjson,
+    Template(Box<Handlebars<'static>>),
+}
+
+const TEMPLATE_NAME: &str = "event";
+
+impl Encoder {
+    fn new(format: EventFormat, template_path: Option<&Path>) -> io::Result<Self> {
+        match format {
+            EventFormat::Ndjson => Ok(Encoder::Ndjson),
+            EventFormat::Template => {
+                let template_path = template_path.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "--template is required")
+                })?;
+                let mut handlebars = Handlebars::new();
+                // The output is arbitrary text (CSV, wiki markup, ...), not HTML.
+                handlebars.register_escape_fn(handlebars::no_escape);
+                handlebars
+                    .register_template_string(TEMPLATE_NAME, fs::read_to_string(template_path)?)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                Ok(Encoder::Template(Box::new(handlebars)))
+            }
+        }
+    }
+}
+
+/// A single change to a single record. This is the NDJSON line, and the context a template
+/// is rendered with.
+#[derive(Serialize)]
+struct ChangeEvent<'a> {
+    /// The `--rules` rule the change matched, in the `--rules-output` stream
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule: Option<&'a str>,
+    path: &'a Path,
+    primary_key: &'a str,
+    /// `added`, `removed`, `modified`, or the `promote_to` of a `--rules` rule
+    change_type: &'a str,
+    /// The change's ID, under `--change-ids`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<&'a str>,
+    #[serde(flatten)]
+    instant: &'a ChangeInstant,
+    /// Versions of the record around the change, under `--include-values`
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    values: Option<&'a ChangeValues>,
+}
+
+pub struct EventStream {
+    writer: Box<dyn Write>,
+    encoder: Encoder,
+    /// The plugin reading the stream, waited for when the stream is dropped
+    plugin: Option<Child>,
+}
+
+impl EventStream {
+    pub fn open(
+        target: &OutputTarget,
+        format: EventFormat,
+        template_path: Option<&Path>,
+    ) -> io::Result<Self> {
+        let encoder = Encoder::new(format, template_path)?;
+        let mut plugin = None;
+        let writer: Box<dyn Write> = match target {
+            OutputTarget::Stdout => Box::new(BufWriter::new(io::stdout())),
+            #[cfg(unix)]
+            OutputTarget::Unix(socket_path) => {
+                Box::new(BufWriter::new(UnixStream::connect(socket_path)?))
+            }
+            #[cfg(not(unix))]
+            OutputTarget::Unix(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Unix sockets are not supported on this platform",
+                ))
+            }
+            OutputTarget::Plugin(name) => {
+                let mut child = plugin::command(name)?.stdin(Stdio::piped()).spawn()?;
+                let stdin = child.stdin.take().expect("Plugin stdin is piped");
+                plugin = Some(child);
+                Box::new(BufWriter::new(stdin))
+            }
+        };
+        Ok(EventStream {
+            writer,
+            encoder,
+            plugin,
+        })
+    }
+
+    /// Emits a change of type `change_type`, which is a [`ChangeType`] or a promoted one.
+    pub fn emit(
+        &mut self,
+        path: &Path,
+        primary_key: &str,
+        change_type: &str,
+        instant: &ChangeInstant,
+        id: Option<&str>,
+        values: Option<&ChangeValues>,
+    ) -> io::Result<()> {
+        self.emit_event(None, path, primary_key, change_type, instant, id, values)
+    }
+
+    /// Emits a change that matched the rule named `rule`.
+    pub fn emit_match(
+        &mut self,
+        rule: &str,
+        path: &Path,
+        primary_key: &str,
+        change_type: ChangeType,
+        instant: &ChangeInstant,
+        id: Option<&str>,
+    ) -> io::Result<()> {
+        self.emit_event(
+            Some(rule),
+            path,
+            primary_key,
+            change_type.as_str(),
+            instant,
+            id,
+            None,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn emit_event(
+        &mut self,
+        rule: Option<&str>,
+        path: &Path,
+        primary_key: &str,
+        change_type: &str,
+        instant: &ChangeInstant,
+        id: Option<&str>,
+        values: Option<&ChangeValues>,
+    ) -> io::Result<()> {
+        let event = ChangeEvent {
+            rule,
+            path,
+            primary_key,
+            change_type,
+            id,
+            instant,
+            values,
+        };
+        match &self.encoder {
+            Encoder::Ndjson => {
+                serde_json::to_writer(&mut self.writer, &event)?;
+                self.writer.write_all(b"\n")
+            }
+            Encoder::Template(handlebars) => handlebars
+                .render_to_write(TEMPLATE_NAME, &event, &mut self.writer)
+                .map_err(io::Error::other),
+        }
+    }
+
+    /// Pushes buffered events downstream. Called once per commit so consumers see events as
+    /// the walk progresses rather than at exit.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl Drop for EventStream {
+    /// Closes a plugin's stdin and waits for it to finish, so that whatever it writes is complete
+    /// by the time DeLorean exits.
+    fn drop(&mut self) {
+        let Some(mut child) = self.plugin.take() else {
+            return;
+        };
+        let mut writer = std::mem::replace(&mut self.writer, Box::new(io::sink()));
+        if let Err(e) = writer.flush() {
+            eprintln!("Failed to write to plugin: {e}");
+        }
+        drop(writer);
+        match child.wait() {
+            Ok(status) if !status.success() => eprintln!("Plugin exited with {status}"),
+            Ok(_) => {}
+            Err(e) => eprintln!("Failed to wait for plugin: {e}"),
+        }
+    }
+}
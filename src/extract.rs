@@ -0,0 +1,2256 @@
+use crate::diff::{changed_fields, diff_records, json_patch, DiffOptions, PatchOperation};
+use crate::errors::{ErrorBudget, ErrorReport, RecoverableError};
+use crate::events::{EventFormat, EventStream, OutputTarget};
+use crate::input::{self, InputFormat};
+use crate::output::{Compression, MergeFormat, OutputFormat, SplitBy, SplitWindow};
+use crate::predicate::{Predicate, Sample, Schedule, Tombstone};
+use crate::record::{
+    change_id, Change, ChangeInstant, ChangeOrder, ChangeRecord, ChangeType, ChangeValues,
+    CommitMeta, IncludeValues, Person, TimestampFormat,
+};
+use crate::rollup::RollupPeriod;
+use crate::rules::{self, Rule};
+use chrono::{DateTime, Months, NaiveDateTime};
+use clap::Parser;
+use glob_match::glob_match;
+use indicatif::ProgressBar;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::vec;
+
+/// Options for a single extraction run. This is the top-level command line of `delorean`, and
+/// can also be built with [`ExtractArgs::parse_from`] when embedding the extractor.
+#[derive(Parser, Debug, Clone)]
+#[command(about = None, long_about = None)]
+pub struct ExtractArgs {
+    pub repo_path: String,
+    /// Directory to write the change records to. Required unless `--output` is given. `-`
+    /// writes them to stdout instead, as a single document in the layout of `--merge-output`
+    /// (`json` unless given), or as CSV or MessagePack with `--output-format`. A URL such as
+    /// `s3://bucket/prefix` uploads the output there (requires the `object_store` feature).
+    pub output_path: Option<String>,
+
+    /// Format of `<OUTPUT_PATH>`. `csv`, `msgpack`, `elasticsearch`, `bigquery`, `arrow`,
+    /// `parquet`, `proto`, `duckdb`, and `sqlite` write a single file instead of a directory (the
+    /// last five require the feature of the same name), and do not support `--rollup`,
+    /// `--cdn-mode`, `--merge-output`, `--compress`, `--chunk-size`, `--split-by`, or
+    /// `--record-manifest`. `--compact-commits` requires `json` or `msgpack`.
+    #[arg(long, value_enum, default_value_t)]
+    pub output_format: OutputFormat,
+
+    /// Stream change events as NDJSON while walking. Use `-` for stdout, which also
+    /// suppresses the progress bar, `unix:<path>` to connect to a listening Unix socket, or
+    /// `plugin:<name>` to start the `delorean-<name>` executable on `PATH` and write to its
+    /// stdin (see `delorean::plugin` for the protocol).
+    /// Without `<OUTPUT_PATH>` or reports that need the full history, changes are not kept in
+    /// memory.
+    #[arg(long)]
+    pub output: Option<OutputTarget>,
+
+    /// URL of a Redis server, e.g. `redis://127.0.0.1/`, to store the change record of every
+    /// record in under `history:<path>:<primary key>` (see `delorean::redis`). Requires the
+    /// `redis` feature
+    #[arg(long, value_name = "URL")]
+    pub redis: Option<String>,
+
+    /// A file to write every change to as a single log, oldest first: one JSON object per line
+    /// with the `timestamp`, `commit`, `path`, `pk`, and `type` of the change, and its `id` under
+    /// `--change-ids`
+    #[arg(long, value_name = "FILE")]
+    pub event_log: Option<PathBuf>,
+
+    /// Format of the events written to `--output`
+    #[arg(long, value_enum, default_value_t, requires = "output")]
+    pub format: EventFormat,
+
+    /// Handlebars template rendered once per event when `--format template` is used.
+    /// Fields: path, primary_key, change_type, commit, timestamp.
+    #[arg(long, required_if_eq("format", "template"))]
+    pub template: Option<PathBuf>,
+
+    /// Identifies records for adding, removing, and modifying
+    #[arg(long)]
+    pub primary_key: String,
+
+    /// Glob pattern to match files to include in the diff.
+    #[arg(short, long, default_value = "**/*")]
+    pub include: String,
+
+    /// List of commit authors (name or email). If empty, all authors are included.
+    /// Otherwise, only commits by the specified authors are included.
+    #[arg(short = 'a', long)]
+    pub include_authors: Vec<String>,
+
+    /// JSON pointer (e.g. `/last_updated`) of a field whose changes are not counted as
+    /// modifications. May be repeated.
+    #[arg(long = "ignore-field")]
+    pub ignore_fields: Vec<String>,
+
+    /// Compare arrays as unordered collections, so reordering elements is not a modification
+    #[arg(long)]
+    pub ignore_array_order: bool,
+
+    /// Compare numbers by value, so reformatting (e.g. `1` to `1.0`) is not a modification
+    #[arg(long)]
+    pub normalize_numbers: bool,
+
+    /// Compare objects by their values alone, so renaming a field is not a modification
+    #[arg(long)]
+    pub ignore_field_names: bool,
+
+    /// Change types to track. Leaving out `modified` skips comparing record contents entirely.
+    /// Without `added`, re-added records cannot be told apart, so the graveyard keeps every
+    /// removed record.
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = [ChangeType::Added, ChangeType::Removed, ChangeType::Modified]
+    )]
+    pub changes: Vec<ChangeType>,
+
+    /// Only track changes to records satisfying a predicate like `/school == "YC"` or
+    /// `/extra_info != null`, checked against the added or modified version of the record, or
+    /// the last version of a removed one. May be repeated; all must hold.
+    #[arg(long = "where", value_name = "PREDICATE")]
+    pub predicates: Vec<Predicate>,
+
+    /// Treat records marked as deleted, e.g. by `status=cancelled` (a field or JSON pointer, and
+    /// a JSON value or string), as absent: marking a record is recorded as its removal, and
+    /// unmarking it as its re-addition
+    #[arg(long, value_name = "FIELD=VALUE")]
+    pub tombstone_field: Option<Tombstone>,
+
+    /// JSON pointers of fields to strip from every record as it is read, e.g.
+    /// `/instructor_email,/notes`, so that no captured value, version, patch, or graveyard entry
+    /// holds them. Changes to only these fields are not recorded as modifications.
+    #[arg(long, value_name = "POINTERS", value_delimiter = ',')]
+    pub redact_fields: Vec<String>,
+
+    /// JSON pointers of fields whose values are replaced, in every record as it is read, by a
+    /// hash of the value salted with `--pseudonymize-salt`, e.g. `/netid`. Equal values get equal
+    /// pseudonyms, so a field can still be followed across records and commits. If the primary
+    /// key is one of them, records are keyed by its pseudonym, as are the keys of `--object-keyed`
+    /// files.
+    #[arg(
+        long,
+        value_name = "POINTERS",
+        value_delimiter = ',',
+        requires = "pseudonymize_salt"
+    )]
+    pub pseudonymize: Vec<String>,
+
+    /// Salt of the `--pseudonymize` hashes. Keep it secret: anyone with it can check a guessed
+    /// value against a pseudonym.
+    #[arg(long, value_name = "SALT", requires = "pseudonymize")]
+    pub pseudonymize_salt: Option<String>,
+
+    /// Size in bytes, as compact JSON, above which a record is kept as a stand-in holding its
+    /// primary key and `"$oversized": {"bytes": ..., "hash": ...}` (its size and git blob hash),
+    /// so that a huge record is compared by hash rather than diffed field by field. Each
+    /// oversized record is reported as a warning.
+    #[arg(long, value_name = "BYTES")]
+    pub max_record_size: Option<usize>,
+
+    /// JSON pointer (e.g. `/title`) of a field whose value is recorded with each change, taken
+    /// from the record's new version (or last version, for removals). May be repeated.
+    #[arg(long = "capture", value_name = "POINTER")]
+    pub captures: Vec<String>,
+
+    /// JSON pointer (e.g. `/last_updated`) of a timestamp in each record to use as the time of
+    /// its additions and modifications instead of the commit time, when present and parseable
+    /// (Unix seconds, RFC 3339, or `YYYY-MM-DD HH:MM:SS` in UTC). Removals keep the commit time.
+    #[arg(long, value_name = "POINTER")]
+    pub timestamp_field: Option<String>,
+
+    /// How the timestamps of changes are written: `rfc3339` (e.g. `2023-01-02T00:00:00+00:00`),
+    /// `unix` or `unix-millis` for seconds or milliseconds since the epoch as numbers, or a
+    /// strftime pattern such as `%Y-%m-%d %H:%M:%S`, in UTC. The `iso_timestamp` column of CSV
+    /// and the typed timestamps of the other tabular formats are unaffected
+    #[arg(long, value_name = "FORMAT", default_value = "rfc3339")]
+    pub timestamp_format: TimestampFormat,
+
+    /// Order of the `added`, `removed`, and `modified` lists of each record: `asc` for oldest
+    /// first, or `desc` for newest first. Recorded in the `--write-meta` metadata.
+    #[arg(long, value_enum, default_value_t)]
+    pub order: ChangeOrder,
+
+    /// Include in each modification a JSON Patch (RFC 6902) under `patch` that turns the
+    /// record's previous version into its new one, leaving out `--ignore-field`s
+    #[arg(long)]
+    pub patches: bool,
+
+    /// Include in each modification the top-level fields whose values changed, under
+    /// `changed_fields`, leaving out `--ignore-field`s
+    #[arg(long)]
+    pub changed_fields: bool,
+
+    /// Embed the versions of the record before and after each change, under `old` and `new`, in
+    /// the change and its `--output` event: `full` for whole records, or `fields` for only the
+    /// top-level fields that a modification changed
+    #[arg(long, value_name = "VALUES")]
+    pub include_values: Option<IncludeValues>,
+
+    /// Key of a commit trailer (e.g. `Sync-Batch`) to attach to the change instants of each
+    /// commit that has it, under `trailers`. May be repeated.
+    #[arg(long = "trailer", value_name = "KEY")]
+    pub trailers: Vec<String>,
+
+    /// Attach the author, committer, and first line of the message of its commit to each change,
+    /// under `author` and `committer` (each with `name` and `email`) and `summary`
+    #[arg(long)]
+    pub with_commit_meta: bool,
+
+    /// A weekly window in UTC when the data is expected to change, e.g. `Mon-Fri 02:00-04:00`
+    /// (see `delorean::predicate::Schedule`). Changes made by commits outside every window get
+    /// `"outside_schedule": true`, to flag manual fixes. May be repeated.
+    #[arg(long, value_name = "SCHEDULE")]
+    pub flag_outside_schedule: Vec<Schedule>,
+
+    /// Only track records with this primary key. May be repeated. Each key's history is walked
+    /// back to its most recent addition, and the walk stops once all keys have been added.
+    #[arg(long = "only-key", value_name = "KEY")]
+    pub only_keys: Vec<String>,
+
+    /// Only track a deterministic sample of the primary keys, e.g. `1%`, to try out options on a
+    /// large repository quickly. Runs with the same share track the same keys.
+    #[arg(long, value_name = "PERCENT")]
+    pub sample: Option<Sample>,
+
+    /// List of revisions (their commit hashes) to ignore
+    #[arg(long)]
+    pub ignore_revs: Vec<String>,
+
+    /// A file of commit hashes to ignore, one per line. Blank lines and lines starting with `#`
+    /// are skipped. `delorean suggest-ignores` writes files in this format.
+    #[arg(long, value_name = "FILE")]
+    pub ignore_revs_file: Option<PathBuf>,
+
+    /// A commit spec (e.g. HEAD~10) to stop at (exclusive). The commit and all of its ancestors
+    /// are left out of the walk. Repeat to bound each lineage of a history with merges.
+    #[arg(long)]
+    pub until: Vec<String>,
+
+    /// Hide a revision and everything reachable from it, like `git log --not`. Accepts anything
+    /// `--until` does, plus ref globs such as `refs/tags/archived/*`. May be repeated.
+    #[arg(long = "not", value_name = "REV")]
+    pub not: Vec<String>,
+
+    /// Write the output once the walk has covered this many months before HEAD's commit time,
+    /// then again when it ends, so that consumers can serve recent history while older history is
+    /// still being walked. The first write only holds changes in those months, and leaves out
+    /// `--record-counts`, `--season-field`, and `--group-by` outputs and the presence index.
+    #[arg(
+        long,
+        value_name = "MONTHS",
+        conflicts_with_all = ["baseline", "append"]
+    )]
+    pub recent_first_flush: Option<u32>,
+
+    /// A directory to output all removed objects
+    #[arg(long)]
+    pub graveyard: Option<String>,
+
+    /// A file to write the commits that touched tracked files but changed no records to, as JSON.
+    /// Changes left out by `--changes` or `--where` still count; without `modified`, so does any
+    /// difference in a record's content.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["only_keys", "sample"])]
+    pub noop_commits: Option<PathBuf>,
+
+    /// Write the output directory for serving from a CDN: minified, brotli-compressed files
+    /// named after a hash of their content, and a `manifest.json` mapping each file's usual
+    /// path to its hashed name. Hashed files of an earlier run that the new manifest no longer
+    /// lists are removed
+    #[arg(long, requires = "output_path", conflicts_with_all = ["chunk_size", "record_manifest"])]
+    pub cdn_mode: bool,
+
+    /// Write all change records to `<OUTPUT_PATH>` as a single file keyed by path instead of a
+    /// directory with a file per tracked path. `--merge-output` alone writes JSON.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "json",
+        requires = "output_path",
+        conflicts_with_all = ["cdn_mode", "chunk_size", "record_manifest"]
+    )]
+    pub merge_output: Option<MergeFormat>,
+
+    /// Compress each file of the output directory, or the `--merge-output` file, appending `.gz`
+    /// or `.zst` to its name. Chunk indexes are left uncompressed.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        requires = "output_path",
+        conflicts_with_all = ["cdn_mode", "record_manifest"]
+    )]
+    pub compress: Option<Compression>,
+
+    /// Store each commit once per output file: files become `{"commits": [...], "timestamps":
+    /// [...], "records": {...}}`, with commits oldest first and timestamps delta-encoded, and
+    /// each change's `commit` an index into both in place of its commit and timestamp. Trailers
+    /// and `--with-commit-meta` metadata move to `trailers` and `meta` lists indexed the same way.
+    /// Not available with `--timestamp-field`, which gives changes of one commit different times
+    #[arg(
+        long,
+        requires = "output_path",
+        conflicts_with_all = [
+            "cdn_mode",
+            "merge_output",
+            "chunk_size",
+            "record_manifest",
+            "timestamp_field",
+        ]
+    )]
+    pub compact_commits: bool,
+
+    /// Write a file per tracked path, or per record at `<path>/<shard>/<primary key>.json`, where
+    /// `<path>` is the tracked path without its extension and `<shard>` the first two digits of
+    /// the git blob hash of the primary key, so that single histories can be served statically
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        conflicts_with_all = ["cdn_mode", "merge_output", "chunk_size", "record_manifest", "compact_commits"]
+    )]
+    pub split_by: SplitBy,
+
+    /// Merge the changes into the files already in `<OUTPUT_PATH>` instead of replacing them,
+    /// skipping changes a record already lists in the same commit, so that a growing repository
+    /// can be extracted incrementally with `--until` the last run's head. The new changes are
+    /// taken to be later than the existing ones, which are expected in the same `--order`. The
+    /// graveyard, `--noop-commits`, and presence index are merged likewise; reports that would
+    /// only cover the run cannot be combined with it.
+    #[arg(
+        long,
+        requires = "output_path",
+        conflicts_with_all = [
+            "cdn_mode",
+            "merge_output",
+            "chunk_size",
+            "record_manifest",
+            "compact_commits",
+            "compress",
+            "rollup",
+            "split_by",
+            "record_counts",
+            "season_summary",
+            "calendar_report",
+            "group_output",
+        ]
+    )]
+    pub append: bool,
+
+    /// Write the changes of each tracked path into a directory at its usual output path, with a
+    /// file per year or month holding the changes made in it (e.g.
+    /// `parsed_courses/202301.json/2019.json`) and an `index.json` listing the windows in order
+    #[arg(
+        long,
+        value_enum,
+        value_name = "WINDOW",
+        requires = "output_path",
+        conflicts_with_all = [
+            "cdn_mode",
+            "merge_output",
+            "chunk_size",
+            "record_manifest",
+            "compact_commits",
+            "rollup",
+            "split_by",
+            "append",
+        ]
+    )]
+    pub split_by_window: Option<SplitWindow>,
+
+    /// Handlebars template of the file of each record under `--split-by record`, relative to
+    /// `<OUTPUT_PATH>`, e.g. `{{path}}/{{primary_key}}.json`. Fields: `path` (the tracked path
+    /// without its extension), `primary_key` (escaped as in the default layout), and `shard`
+    #[arg(long, value_name = "TEMPLATE")]
+    pub path_template: Option<String>,
+
+    /// A Handlebars template file rendered into the content of each record's file under
+    /// `--split-by record`, instead of pretty-printed JSON. Fields: `path`, `primary_key`, and
+    /// `record`, the change record; `{{json value}}` writes a value as JSON
+    #[arg(long, value_name = "FILE")]
+    pub body_template: Option<PathBuf>,
+
+    /// Handlebars template of the index of each change under `--output-format elasticsearch`,
+    /// e.g. `changes-{{change_type}}`, rendered with the change's document [default:
+    /// delorean-changes]
+    #[arg(long, value_name = "TEMPLATE")]
+    pub bulk_index: Option<String>,
+
+    /// Handlebars template of the `_id` of each change under `--output-format elasticsearch`,
+    /// e.g. `{{commit}}-{{primary_key}}`. Defaults to the `--change-ids` ID, if any
+    #[arg(long, value_name = "TEMPLATE")]
+    pub bulk_id: Option<String>,
+
+    /// Split output files larger than this many bytes by key range into chunks `<file>.0`,
+    /// `<file>.1`, ..., listed with their first and last keys in `<file>.index`
+    #[arg(long, value_name = "BYTES")]
+    pub chunk_size: Option<usize>,
+
+    /// A file to write, for each primary key, where its history is in the output directory: the
+    /// file and the byte range of the record's entry, so it can be read without parsing the file
+    #[arg(long, value_name = "FILE", requires = "output_path")]
+    pub record_manifest: Option<PathBuf>,
+
+    /// A directory to output, per file, the number of records after each walked commit that
+    /// changed it, oldest first. With `--only-key`, only the requested keys are counted.
+    #[arg(long, value_name = "DIR")]
+    pub record_counts: Option<String>,
+
+    /// A file to write, per season and ISO week, the number of records added, removed, and
+    /// modified, as JSON
+    #[arg(long, value_name = "FILE")]
+    pub season_summary: Option<PathBuf>,
+
+    /// JSON pointer (e.g. `/season_code`) of the season of each record in `--season-summary`.
+    /// Defaults to the file name without extensions, as in `parsed_courses/202301.json`.
+    #[arg(long, value_name = "POINTER", requires = "season_summary")]
+    pub season_field: Option<String>,
+
+    /// A YAML file of the windows syncs are expected in and blackout days without syncs (see
+    /// `delorean::calendar`), to check the walked commits against in `--calendar-report`
+    #[arg(long, value_name = "FILE", requires = "calendar_report")]
+    pub calendar: Option<PathBuf>,
+
+    /// A file to write the `--calendar` windows that had no sync, and the changes made outside
+    /// every window or on a blackout day, to, as JSON
+    #[arg(long, value_name = "FILE", requires = "calendar")]
+    pub calendar_report: Option<PathBuf>,
+
+    /// JSON pointer (e.g. `/same_course_id`) of a field that groups records, such as the
+    /// cross-listings of a course. Requires `--group-output`.
+    #[arg(long, value_name = "POINTER", requires = "group_output")]
+    pub group_by: Option<String>,
+
+    /// A directory to output, per file, the combined history of each `--group-by` group: every
+    /// change to any of its records, keyed by the group's value. Records without the field are
+    /// left out.
+    #[arg(long, value_name = "DIR", requires = "group_by")]
+    pub group_output: Option<String>,
+
+    /// Also emit, for each record, counts of each change type per calendar period
+    #[arg(long, value_enum)]
+    pub rollup: Option<RollupPeriod>,
+
+    /// A directory to output, per file, the primary keys present at HEAD and the instant each was
+    /// first added (null if it predates the walked history)
+    #[arg(long, value_name = "DIR")]
+    pub emit_presence_index: Option<String>,
+
+    /// Treat files whose top level is a JSON object as a map from primary key to record, instead
+    /// of skipping them. Files with a top-level array still use `--primary-key`.
+    #[arg(long)]
+    pub object_keyed: bool,
+
+    /// Format of the tracked files. YAML files are parsed into the same model as JSON, so they
+    /// hold a sequence of records (or a mapping, with `--object-keyed`) too, and JSON Lines files
+    /// are read as an array of the records on their lines.
+    #[arg(long, value_enum, default_value_t)]
+    pub input_format: InputFormat,
+
+    /// Number of recoverable errors (unparseable files, records without a string primary key)
+    /// to skip over before aborting. Skipped errors are listed at the end of the run.
+    #[arg(long, default_value_t = 0)]
+    pub max_errors: usize,
+
+    /// A YAML file of notification rules (see `delorean::rules`). Changes matching a rule are
+    /// streamed as NDJSON to `--rules-output`, tagged with the rule's name, and have the rule's
+    /// `promote_to` as their change type in `--output`.
+    #[arg(long, value_name = "FILE")]
+    pub rules: Option<PathBuf>,
+
+    /// Where to stream changes matching `--rules`: `-` for stdout, `unix:<path>`, or
+    /// `plugin:<name>`
+    #[arg(long, value_name = "TARGET", requires = "rules")]
+    pub rules_output: Option<OutputTarget>,
+
+    /// Instead of walking history, compare this file (e.g. new scraper output) against
+    /// `--baseline-path` at `--baseline-rev`, as if it were committed on top. Every change gets
+    /// the same instant, with commit `baseline` and the current time.
+    #[arg(long, value_name = "FILE", requires = "baseline_path")]
+    pub baseline: Option<PathBuf>,
+
+    /// Path of the tracked file in the repository that `--baseline` is a new version of
+    #[arg(long, value_name = "PATH", requires = "baseline")]
+    pub baseline_path: Option<PathBuf>,
+
+    /// Revision to compare `--baseline` against
+    #[arg(long, value_name = "REV", default_value = "HEAD")]
+    pub baseline_rev: String,
+
+    /// What to do with commits whose time is before their parent's, which makes histories
+    /// non-monotonic
+    #[arg(long, value_enum, default_value_t)]
+    pub clock_skew: ClockSkew,
+
+    /// A file (e.g. `warnings.ndjson`) to write warnings and skipped errors to as NDJSON, instead
+    /// of listing them at the end of the run
+    #[arg(long, value_name = "FILE")]
+    pub warnings: Option<PathBuf>,
+
+    /// A file to write a reproducibility attestation to: the repository HEAD, the command line,
+    /// the tool version, and hashes of the option files read and of `<OUTPUT_PATH>` (see
+    /// `delorean::attest`)
+    #[arg(long, value_name = "FILE")]
+    pub attest: Option<PathBuf>,
+
+    /// A file holding an Ed25519 secret key as 64 hex digits to sign the `--attest` file with
+    #[arg(long, value_name = "FILE", requires = "attest")]
+    pub attest_key: Option<PathBuf>,
+
+    /// Give each change an `id`, a hash of its path, primary key, commit, and change type, in
+    /// every output format, so that consumers can ingest the changes of repeated runs
+    /// idempotently
+    #[arg(long)]
+    pub change_ids: bool,
+
+    /// Write the tool and output schema versions, the command line, the repository HEAD, and the
+    /// generation time to `<OUTPUT_PATH>/_meta.json`, or `<OUTPUT_PATH>.meta.json` for single-file
+    /// output formats (see `delorean::meta`)
+    #[arg(long, requires = "output_path")]
+    pub write_meta: bool,
+
+    /// Write `<OUTPUT_PATH>/manifest.json`, listing every file of the output directory with its
+    /// record count, the commits of its oldest and newest changes, and a hash of its content
+    /// (see `delorean::manifest`)
+    #[arg(
+        long,
+        requires = "output_path",
+        conflicts_with_all = [
+            "cdn_mode",
+            "merge_output",
+            "chunk_size",
+            "split_by",
+            "append",
+            "split_by_window",
+        ]
+    )]
+    pub write_manifest: bool,
+
+    /// Write `<OUTPUT_PATH>/index.json`, mapping every primary key to the output files its record
+    /// appears in and the commits of its oldest and newest changes in each (see
+    /// `delorean::index`)
+    #[arg(
+        long,
+        requires = "output_path",
+        conflicts_with_all = [
+            "cdn_mode",
+            "merge_output",
+            "chunk_size",
+            "split_by",
+            "append",
+            "split_by_window",
+        ]
+    )]
+    pub write_index: bool,
+
+    /// Never access the network: skip files whose objects are missing from the local repository
+    /// (e.g. a partial clone) as errors, which count against `--max-errors`, instead of fetching
+    /// them, and skip commits with corrupt objects without trying to fetch them again, for
+    /// reproducible runs in hermetic environments
+    #[arg(long)]
+    pub offline: bool,
+
+    /// Report the peak RSS and the allocations of each stage of the run (extraction and output)
+    /// at the end of the run. Requires the `mem_stats` feature
+    #[arg(long)]
+    pub mem_stats: bool,
+
+    /// Hide the progress bar and per-commit log lines
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+/// Handling of commits whose time is before their (first) parent's.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default)]
+pub enum ClockSkew {
+    /// Use the commit time as is
+    #[default]
+    Keep,
+    /// Use the parent's commit time instead
+    Clamp,
+    /// Use the commit time as is, and report a warning
+    Warn,
+}
+
+/// A commit that touched tracked files without changing any record, such as a reformatting.
+/// These are candidates for `--ignore-revs`.
+#[derive(Debug, Serialize)]
+pub struct NoopCommit {
+    #[serde(flatten)]
+    pub instant: ChangeInstant,
+    /// First line of the commit message
+    pub message: String,
+    pub files: Vec<PathBuf>,
+}
+
+/// The number of records in a tracked file just after a commit that changed it.
+#[derive(Debug, Serialize)]
+pub struct RecordCount {
+    #[serde(flatten)]
+    pub instant: ChangeInstant,
+    pub count: usize,
+}
+
+/// Everything an extraction run accumulates, keyed by the path of the tracked file.
+#[derive(Default)]
+pub struct Extraction {
+    /// Empty when changes are only streamed to `--output`.
+    pub change_records: HashMap<PathBuf, HashMap<String, ChangeRecord>>,
+    /// Removed records that were never re-added, when `--graveyard` is set.
+    pub graveyard: HashMap<PathBuf, HashMap<String, serde_json::Value>>,
+    /// Primary keys present at HEAD in each tracked file, when `--emit-presence-index` is set.
+    pub presence: HashMap<PathBuf, Vec<String>>,
+    /// Walked commits that touched tracked files without changing any record, newest first.
+    pub noop_commits: Vec<NoopCommit>,
+    /// Times of the walked commits that touched tracked files, whether or not they changed
+    /// records, newest first.
+    pub sync_times: Vec<i64>,
+    /// Record counts of each tracked file, newest first, when `--record-counts` is set.
+    pub record_counts: HashMap<PathBuf, Vec<RecordCount>>,
+    /// The most recent `--season-field` value of each record, when it is set.
+    pub seasons: HashMap<PathBuf, HashMap<String, String>>,
+    /// The most recent `--group-by` value of each record, when it is set.
+    pub groups: HashMap<PathBuf, HashMap<String, String>>,
+    /// Errors that were skipped over, within the `--max-errors` budget.
+    pub errors: Vec<RecoverableError>,
+    /// Data that was skipped by design, such as files that do not hold a list of records.
+    pub warnings: Vec<RecoverableError>,
+}
+
+const MAX_SYMLINK_DEPTH: usize = 8;
+const SYMLINK_FILEMODE: i32 = 0o120000;
+
+/// Lexically resolves a symlink target relative to the link's directory. Returns `None` if the
+/// target is absolute or climbs out of the repository.
+fn resolve_symlink_target(link: &Path, target: &Path) -> Option<PathBuf> {
+    let mut resolved = link.parent().unwrap_or(Path::new("")).to_path_buf();
+    for component in target.components() {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(resolved)
+}
+
+/// Fetches `id` from the repository's `origin` remote, or its first remote if it has no
+/// `origin`, for an object that is missing (as in a partial clone) or could not be read (as a
+/// corrupt loose object cannot). Objects fetched into a pack are read from it rather than from a
+/// corrupt loose copy.
+fn refetch(repo: &git2::Repository, id: git2::Oid) -> Result<(), git2::Error> {
+    let remotes = repo.remotes()?;
+    let name = match remotes.iter().flatten().find(|name| *name == "origin") {
+        Some(name) => name,
+        None => remotes
+            .iter()
+            .flatten()
+            .next()
+            .ok_or_else(|| git2::Error::from_str("no remote to fetch from"))?,
+    };
+    repo.find_remote(name)?.fetch(&[id.to_string()], None, None)
+}
+
+/// Reads objects with `read`, and if that fails, fetches `id` and reads them again, unless
+/// `offline`.
+fn read_with_refetch<T>(
+    repo: &git2::Repository,
+    id: git2::Oid,
+    offline: bool,
+    read: impl Fn() -> Result<T, git2::Error>,
+) -> Result<T, git2::Error> {
+    read().or_else(|e| {
+        if offline {
+            return Err(e);
+        }
+        refetch(repo, id).map_err(|fetch_error| {
+            git2::Error::from_str(&format!(
+                "{}; failed to fetch it: {}",
+                e.message(),
+                fetch_error.message()
+            ))
+        })?;
+        read()
+    })
+}
+
+/// Why a tracked file could not be loaded.
+enum LoadError {
+    /// The file is skipped with a warning, as for a symlink out of the repository.
+    Skipped(String),
+    /// The file is skipped as an error, which counts against `--max-errors`.
+    Failed(String),
+}
+
+impl From<String> for LoadError {
+    fn from(message: String) -> Self {
+        LoadError::Skipped(message)
+    }
+}
+
+impl LoadError {
+    /// Records the skipped file in `errors`, failing once the budget is exceeded.
+    fn skip(self, commit: &str, path: &Path, errors: &mut ErrorBudget) -> Result<(), ErrorReport> {
+        match self {
+            LoadError::Skipped(message) => {
+                errors.warn(commit, path, format!("Skipped file: {message}"));
+                Ok(())
+            }
+            LoadError::Failed(message) => errors.record(commit, path, message),
+        }
+    }
+}
+
+/// Loads the blob `id` found at `path`, fetching it if it is missing or corrupt. With
+/// `offline`, an object missing from the object store (e.g. in a partial clone) is an error
+/// instead of being fetched.
+fn load_blob<'repo>(
+    repo: &'repo git2::Repository,
+    id: git2::Oid,
+    path: &Path,
+    offline: bool,
+) -> Result<git2::Blob<'repo>, LoadError> {
+    let object = match read_with_refetch(repo, id, offline, || repo.find_object(id, None)) {
+        Ok(object) => object,
+        Err(e) if offline && e.code() == git2::ErrorCode::NotFound => {
+            return Err(LoadError::Failed(format!(
+                "Object {id} for {} is missing, and --offline forbids fetching it",
+                path.display()
+            )))
+        }
+        Err(e) => {
+            return Err(LoadError::Skipped(format!(
+                "Failed to read object {id} for {}: {}",
+                path.display(),
+                e.message()
+            )))
+        }
+    };
+    Ok(object
+        .into_blob()
+        .map_err(|_| format!("{} is not a file", path.display()))?)
+}
+
+/// Finds the id of the blob at `path`, following in-repository symlinks up to
+/// `MAX_SYMLINK_DEPTH` levels. Only symlinks are loaded.
+fn resolve_blob_id(
+    repo: &git2::Repository,
+    tree: &git2::Tree,
+    path: &Path,
+    offline: bool,
+) -> Result<git2::Oid, LoadError> {
+    let mut path = path.to_path_buf();
+    for _ in 0..=MAX_SYMLINK_DEPTH {
+        let tree_entry = tree
+            .get_path(&path)
+            .map_err(|_| format!("{} does not exist", path.display()))?;
+        if tree_entry.kind() != Some(git2::ObjectType::Blob) {
+            return Err(format!("{} is not a file", path.display()).into());
+        }
+        if tree_entry.filemode() != SYMLINK_FILEMODE {
+            return Ok(tree_entry.id());
+        }
+        let blob = load_blob(repo, tree_entry.id(), &path, offline)?;
+        let target = Path::new(
+            std::str::from_utf8(blob.content())
+                .map_err(|_| format!("Symlink {} is not valid UTF-8", path.display()))?,
+        );
+        path = resolve_symlink_target(&path, target)
+            .ok_or_else(|| format!("Symlink {} points outside the repository", path.display()))?;
+    }
+    Err(format!(
+        "More than {MAX_SYMLINK_DEPTH} levels of symlinks at {}",
+        path.display()
+    )
+    .into())
+}
+
+/// A hash of a record's exact content: records with different hashes may or may not differ
+/// under the diff options, but records with equal hashes are (but for a 64-bit collision) equal.
+fn record_hash(value: &serde_json::Value) -> u64 {
+    fn feed(value: &serde_json::Value, hasher: &mut DefaultHasher) {
+        match value {
+            serde_json::Value::Null => hasher.write_u8(0),
+            serde_json::Value::Bool(b) => (1u8, b).hash(hasher),
+            serde_json::Value::Number(n) => (2u8, n.to_string()).hash(hasher),
+            serde_json::Value::String(s) => (3u8, s).hash(hasher),
+            serde_json::Value::Array(items) => {
+                (4u8, items.len()).hash(hasher);
+                for item in items {
+                    feed(item, hasher);
+                }
+            }
+            serde_json::Value::Object(fields) => {
+                (5u8, fields.len()).hash(hasher);
+                for (key, field) in fields {
+                    key.hash(hasher);
+                    feed(field, hasher);
+                }
+            }
+        }
+    }
+    let mut hasher = DefaultHasher::new();
+    feed(value, &mut hasher);
+    hasher.finish()
+}
+
+/// The records of one version of a tracked file, keyed by primary key, with the
+/// [`record_hash`] of each so that records unchanged between two versions are skipped without
+/// being compared.
+#[derive(Default)]
+struct FileRecords {
+    /// Shared so that versions derived from each other's line diffs share unchanged records.
+    records: HashMap<String, Arc<serde_json::Value>>,
+    hashes: HashMap<String, u64>,
+    /// The content these records were read from, if it is an array with one record per line
+    /// (see [`is_line_per_record`]), so that neighbouring versions can be derived from its line
+    /// diff.
+    lines: Option<Vec<u8>>,
+}
+
+impl FileRecords {
+    fn new(records: HashMap<String, serde_json::Value>) -> Self {
+        let hashes = records
+            .iter()
+            .map(|(key, record)| (key.clone(), record_hash(record)))
+            .collect();
+        let records = records
+            .into_iter()
+            .map(|(key, record)| (key, Arc::new(record)))
+            .collect();
+        FileRecords {
+            records,
+            hashes,
+            lines: None,
+        }
+    }
+
+    /// Derives the records of blob `id` from these records and the lines git reports as changed
+    /// between the two versions, so that only changed lines are parsed. Returns `None` if these
+    /// records are not from a blob with one record per line, or a changed line is not a single
+    /// record with a new primary key, in which case the blob has to be parsed as a whole.
+    fn derive(
+        &self,
+        repo: &git2::Repository,
+        id: git2::Oid,
+        primary_key: &str,
+    ) -> Option<FileRecords> {
+        let base = self.lines.as_deref()?;
+        let blob = repo.find_blob(id).ok()?;
+        if !starts_line_per_record(blob.content()) {
+            return None;
+        }
+        let (removed_lines, added_lines) = changed_lines(base, blob.content());
+        let removed = removed_lines
+            .into_iter()
+            .map(|line| parse_record_line(line, primary_key).map(|(key, _)| key))
+            .collect::<Option<Vec<_>>>()?;
+        let added = added_lines
+            .into_iter()
+            .map(|line| parse_record_line(line, primary_key))
+            .collect::<Option<Vec<_>>>()?;
+        let mut records = self.records.clone();
+        let mut hashes = self.hashes.clone();
+        for key in &removed {
+            records.remove(key);
+            hashes.remove(key);
+        }
+        for (key, record) in added {
+            hashes.insert(key.clone(), record_hash(&record));
+            if records.insert(key, Arc::new(record)).is_some() {
+                return None;
+            }
+        }
+        Some(FileRecords {
+            records,
+            hashes,
+            lines: Some(blob.content().to_vec()),
+        })
+    }
+
+    /// Whether the record `key` is known to be identical in `other`.
+    fn unchanged_in(&self, other: &FileRecords, key: &str) -> bool {
+        self.hashes
+            .get(key)
+            .is_some_and(|hash| other.hashes.get(key) == Some(hash))
+    }
+}
+
+/// Whether `content` starts with a line holding only `[`, as in an array with one record per
+/// line. This also rules out compressed and UTF-16 content, whose lines git cannot diff.
+fn starts_line_per_record(content: &[u8]) -> bool {
+    content.starts_with(b"[\n") || content.starts_with(b"[\r\n")
+}
+
+/// Whether `line` holds only the brackets of the top-level array (or nothing).
+fn is_structural_line(line: &[u8]) -> bool {
+    matches!(line.trim_ascii(), b"" | b"[" | b"]")
+}
+
+/// The lines only in `old` and the lines only in `new`, leaving out [`is_structural_line`]s. Lines
+/// are compared as multisets rather than aligned as in `git diff`: the order of records does not
+/// matter, and hashing every line is cheaper than parsing the file, which aligning is not.
+fn changed_lines<'a>(old: &'a [u8], new: &'a [u8]) -> (Vec<&'a [u8]>, Vec<&'a [u8]>) {
+    let lines = |content: &'a [u8]| {
+        content
+            .split(|&b| b == b'\n')
+            .map(<[u8]>::trim_ascii)
+            .filter(|line| !is_structural_line(line))
+    };
+    let mut counts: HashMap<&[u8], isize> = HashMap::new();
+    for line in lines(old) {
+        *counts.entry(line).or_default() += 1;
+    }
+    for line in lines(new) {
+        *counts.entry(line).or_default() -= 1;
+    }
+    let mut removed = vec![];
+    let mut added = vec![];
+    for (line, count) in counts {
+        for _ in 0..count {
+            removed.push(line);
+        }
+        for _ in count..0 {
+            added.push(line);
+        }
+    }
+    (removed, added)
+}
+
+/// Parses a line of an array with one record per line into its primary key and record.
+fn parse_record_line(line: &[u8], primary_key: &str) -> Option<(String, serde_json::Value)> {
+    let line = line.trim_ascii();
+    let line = line.strip_suffix(b",").unwrap_or(line);
+    let record: serde_json::Value = serde_json::from_slice(line).ok()?;
+    let key = record.get(primary_key)?.as_str()?.to_string();
+    record.is_object().then_some((key, record))
+}
+
+/// Whether `content`, which parsed into `records` records, is an array with one record per line:
+/// `[` and `]` on their own lines, and every other line a whole record. Only such files are
+/// diffed line by line.
+fn is_line_per_record(content: &[u8], records: usize) -> bool {
+    if !starts_line_per_record(content) {
+        return false;
+    }
+    let mut record_lines = 0;
+    for line in content.split(|&b| b == b'\n') {
+        if is_structural_line(line) {
+            continue;
+        }
+        let line = line.trim_ascii();
+        if !line.starts_with(b"{") || !(line.ends_with(b"}") || line.ends_with(b"},")) {
+            return false;
+        }
+        record_lines += 1;
+    }
+    record_lines == records
+}
+
+/// Whether parsing keeps every record of a file as it is, which line diffs need: they parse changed
+/// lines alone, and so cannot leave records out or rewrite them.
+fn keeps_every_record(args: &ExtractArgs, keys: Option<&HashSet<String>>) -> bool {
+    keys.is_none()
+        && !args.object_keyed
+        && args.tombstone_field.is_none()
+        && args.redact_fields.is_empty()
+        && args.pseudonymize.is_empty()
+        && args.sample.is_none()
+        && args.max_record_size.is_none()
+}
+
+/// Parsed versions of tracked files keyed by blob id and input format, so that a version is parsed
+/// once even though it is the new side of one commit and the old side of the next. Only the blobs
+/// used by the last commit are kept, and none once the pending `--only-key` keys, which versions
+/// are parsed with, change.
+#[derive(Default)]
+struct BlobCache {
+    previous: HashMap<(git2::Oid, InputFormat), Arc<FileRecords>>,
+    current: HashMap<(git2::Oid, InputFormat), Arc<FileRecords>>,
+}
+
+impl BlobCache {
+    /// Loads the records at `path` in `tree` as [`get_json_data`] does, reusing the parse of the
+    /// same blob in this or the previous commit. If `base` is another version of the file, and
+    /// both have one record per line, only the lines that differ from `base` are parsed.
+    #[allow(clippy::too_many_arguments)]
+    fn load(
+        &mut self,
+        repo: &git2::Repository,
+        tree: &git2::Tree,
+        path: &Path,
+        args: &ExtractArgs,
+        keys: Option<&HashSet<String>>,
+        commit: &str,
+        errors: &mut ErrorBudget,
+        base: Option<&FileRecords>,
+    ) -> Result<Option<Arc<FileRecords>>, ErrorReport> {
+        let id = match resolve_blob_id(repo, tree, path, args.offline) {
+            Ok(id) => id,
+            Err(e) => {
+                e.skip(commit, path, errors)?;
+                return Ok(None);
+            }
+        };
+        let key = (id, args.input_format.of(path));
+        if let Some(records) = self.current.get(&key) {
+            return Ok(Some(records.clone()));
+        }
+        let derive = || {
+            base.filter(|_| keeps_every_record(args, keys))
+                .and_then(|base| base.derive(repo, id, &args.primary_key))
+        };
+        let records = match self.previous.remove(&key) {
+            Some(records) => records,
+            None => match derive() {
+                Some(records) => Arc::new(records),
+                None => {
+                    let blob = match load_blob(repo, id, path, args.offline) {
+                        Ok(blob) => blob,
+                        Err(e) => {
+                            e.skip(commit, path, errors)?;
+                            return Ok(None);
+                        }
+                    };
+                    match parse_records(blob.content(), path, args, keys, commit, errors)? {
+                        Some(records) => {
+                            let line_per_record = keeps_every_record(args, keys)
+                                && is_line_per_record(blob.content(), records.len());
+                            let mut records = FileRecords::new(records);
+                            records.lines = line_per_record.then(|| blob.content().to_vec());
+                            Arc::new(records)
+                        }
+                        None => return Ok(None),
+                    }
+                }
+            },
+        };
+        self.current.insert(key, records.clone());
+        Ok(Some(records))
+    }
+
+    /// Moves on to the next commit, dropping blobs the last one did not use.
+    fn next_commit(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+    }
+}
+
+/// Loads the records of a tracked file, keyed by primary key. Returns `None` if the file could not
+/// be parsed, and leaves out records without a string primary key; both count against `errors`.
+/// Files that are not a list of records (or an object, with `--object-keyed`), and symlinks that
+/// cannot be resolved within the tree, are skipped with a warning. If `keys` is given, other
+/// records are left out, as are records marked by `--tombstone-field` and records outside the
+/// `--sample`. Records over `--max-record-size` are replaced by their stand-ins.
+pub(crate) fn get_json_data(
+    repo: &git2::Repository,
+    tree: &git2::Tree,
+    path: &Path,
+    args: &ExtractArgs,
+    keys: Option<&HashSet<String>>,
+    commit: &str,
+    errors: &mut ErrorBudget,
+) -> Result<Option<HashMap<String, serde_json::Value>>, ErrorReport> {
+    let blob = match resolve_blob_id(repo, tree, path, args.offline)
+        .and_then(|id| load_blob(repo, id, path, args.offline))
+    {
+        Ok(blob) => blob,
+        Err(e) => {
+            e.skip(commit, path, errors)?;
+            return Ok(None);
+        }
+    };
+    parse_records(blob.content(), path, args, keys, commit, errors)
+}
+
+fn is_tombstone(args: &ExtractArgs, record: &serde_json::Value) -> bool {
+    args.tombstone_field
+        .as_ref()
+        .is_some_and(|tombstone| tombstone.matches(record))
+}
+
+fn in_sample(args: &ExtractArgs, primary_key: &str) -> bool {
+    args.sample
+        .is_none_or(|sample| sample.contains(primary_key))
+}
+
+/// Removes the value at `pointer` from `record`, if there is one.
+fn redact(record: &mut serde_json::Value, pointer: &str) {
+    let Some((parent, last)) = pointer.rsplit_once('/') else {
+        return;
+    };
+    let last = last.replace("~1", "/").replace("~0", "~");
+    match record.pointer_mut(parent) {
+        Some(serde_json::Value::Object(object)) => {
+            object.remove(&last);
+        }
+        Some(serde_json::Value::Array(items)) => {
+            if let Some(index) = last.parse().ok().filter(|&index| index < items.len()) {
+                items.remove(index);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The `--pseudonymize` pseudonym of `value`: the hash of its JSON salted with the
+/// `--pseudonymize-salt`.
+fn pseudonym(args: &ExtractArgs, value: &serde_json::Value) -> String {
+    let salt = args.pseudonymize_salt.as_deref().unwrap_or_default();
+    let content = format!("{salt}{value}");
+    git2::Oid::hash_object(git2::ObjectType::Blob, content.as_bytes())
+        .expect("Failed to hash value")
+        .to_string()
+}
+
+/// Whether records are keyed by the `--pseudonymize` pseudonym of their primary key.
+fn pseudonymizes_primary_key(args: &ExtractArgs) -> bool {
+    args.pseudonymize
+        .iter()
+        .any(|pointer| pointer.strip_prefix('/') == Some(&args.primary_key))
+}
+
+/// The record as it is kept: with the `--redact-fields` removed and the `--pseudonymize` fields
+/// replaced by their pseudonyms.
+fn redacted(args: &ExtractArgs, mut record: serde_json::Value) -> serde_json::Value {
+    for pointer in &args.redact_fields {
+        redact(&mut record, pointer);
+    }
+    for pointer in &args.pseudonymize {
+        if let Some(value) = record.pointer_mut(pointer) {
+            *value = pseudonym(args, value).into();
+        }
+    }
+    record
+}
+
+/// The record, or under `--max-record-size` its stand-in if it is larger, with a warning.
+fn bounded(
+    args: &ExtractArgs,
+    record: serde_json::Value,
+    key: &str,
+    commit: &str,
+    path: &Path,
+    errors: &mut ErrorBudget,
+) -> serde_json::Value {
+    let Some(max_record_size) = args.max_record_size else {
+        return record;
+    };
+    let content = serde_json::to_vec(&record).expect("Failed to write json");
+    if content.len() <= max_record_size {
+        return record;
+    }
+    errors.warn(
+        commit,
+        path,
+        format!(
+            "Record {key} is {} bytes, over --max-record-size; comparing it by hash",
+            content.len()
+        ),
+    );
+    let hash = git2::Oid::hash_object(git2::ObjectType::Blob, &content)
+        .expect("Failed to hash record")
+        .to_string();
+    let mut stand_in = serde_json::Map::new();
+    if let Some(primary_key) = record.get(&args.primary_key) {
+        stand_in.insert(args.primary_key.clone(), primary_key.clone());
+    }
+    stand_in.insert(
+        "$oversized".to_string(),
+        serde_json::json!({ "bytes": content.len(), "hash": hash }),
+    );
+    serde_json::Value::Object(stand_in)
+}
+
+/// Parses the content of a tracked file, as described for [`get_json_data`].
+pub(crate) fn parse_records(
+    content: &[u8],
+    path: &Path,
+    args: &ExtractArgs,
+    keys: Option<&HashSet<String>>,
+    commit: &str,
+    errors: &mut ErrorBudget,
+) -> Result<Option<HashMap<String, serde_json::Value>>, ErrorReport> {
+    let content = match input::decode(content) {
+        Ok(content) => content,
+        Err(e) => {
+            errors.record(commit, path, format!("Failed to decode file: {e}"))?;
+            return Ok(None);
+        }
+    };
+    let content = match input::parse(&content, args.input_format.of(path)) {
+        Ok(content) => content,
+        Err(e) => {
+            errors.record(commit, path, e)?;
+            return Ok(None);
+        }
+    };
+    let content = match content {
+        serde_json::Value::Array(records) => records,
+        serde_json::Value::Object(records) if args.object_keyed => {
+            let data = records
+                .into_iter()
+                .filter(|(key, _)| keys.is_none_or(|keys| keys.contains(key)))
+                .filter(|(key, record)| !is_tombstone(args, record) && in_sample(args, key))
+                .map(|(key, record)| {
+                    let key = if pseudonymizes_primary_key(args) {
+                        pseudonym(args, &serde_json::Value::String(key))
+                    } else {
+                        key
+                    };
+                    let record = bounded(args, redacted(args, record), &key, commit, path, errors);
+                    (key, record)
+                })
+                .collect();
+            return Ok(Some(data));
+        }
+        serde_json::Value::Object(_) => {
+            errors.warn(
+                commit,
+                path,
+                "Skipped file whose top level is an object (see --object-keyed)",
+            );
+            return Ok(None);
+        }
+        _ => {
+            errors.warn(
+                commit,
+                path,
+                "Skipped file whose top level is not an array or object",
+            );
+            return Ok(None);
+        }
+    };
+    let mut data: HashMap<String, serde_json::Value> = HashMap::new();
+    for (i, record) in content.into_iter().enumerate() {
+        let primary_key_val = match &record[&args.primary_key] {
+            serde_json::Value::String(s) => s,
+            _ => {
+                errors.record(
+                    commit,
+                    path,
+                    format!("Primary key of record {i} is not a string"),
+                )?;
+                continue;
+            }
+        };
+        if keys.is_none_or(|keys| keys.contains(primary_key_val))
+            && !is_tombstone(args, &record)
+            && in_sample(args, primary_key_val)
+        {
+            let primary_key_val = if pseudonymizes_primary_key(args) {
+                pseudonym(args, &record[&args.primary_key])
+            } else {
+                primary_key_val.to_string()
+            };
+            let record = bounded(
+                args,
+                redacted(args, record),
+                &primary_key_val,
+                commit,
+                path,
+                errors,
+            );
+            if data.insert(primary_key_val.clone(), record).is_some() {
+                errors.warn(
+                    commit,
+                    path,
+                    format!("Duplicate primary key {primary_key_val}; keeping the last record"),
+                );
+            }
+        }
+    }
+    Ok(Some(data))
+}
+
+/// Records changes into the change records and the `--output` event stream.
+struct Recorder {
+    event_stream: Option<EventStream>,
+    rules: Vec<Rule>,
+    /// Where changes matching `rules` are emitted.
+    rules_stream: Option<EventStream>,
+    tracked_changes: Vec<ChangeType>,
+    /// Only changes to records satisfying all of these are recorded.
+    predicates: Vec<Predicate>,
+    /// JSON pointers of the fields captured with each change.
+    captures: Vec<String>,
+    /// JSON pointer of a timestamp in new versions of records that overrides the commit time.
+    timestamp_field: Option<String>,
+    /// The instants of the current commit with a `--timestamp-field` time, by time, so that
+    /// changes with the same time share one.
+    overridden_instants: HashMap<i64, Arc<ChangeInstant>>,
+    /// Whether modifications carry a JSON Patch from the old version to the new one.
+    patches: bool,
+    /// Whether modifications carry the top-level fields that changed.
+    changed_fields: bool,
+    /// Which versions of the record changes carry.
+    include_values: Option<IncludeValues>,
+    /// Order in which the changes of each record are serialized.
+    order: ChangeOrder,
+    /// Whether changes are kept in the change records. When they are only streamed to `--output`,
+    /// nothing is kept, so memory use does not grow with the history.
+    keep_changes: bool,
+    /// Whether changes carry their [`change_id`].
+    change_ids: bool,
+    /// Number of changes found so far, including those that `--changes` or `--where` leave out.
+    found: usize,
+}
+
+impl Recorder {
+    fn new(args: &ExtractArgs) -> Self {
+        Recorder {
+            event_stream: args.output.as_ref().map(|target| {
+                EventStream::open(target, args.format, args.template.as_deref())
+                    .expect("Failed to open output stream")
+            }),
+            rules: args.rules.as_deref().map_or_else(Vec::new, |rules_path| {
+                rules::load(rules_path).expect("Failed to load --rules")
+            }),
+            rules_stream: args.rules_output.as_ref().map(|target| {
+                EventStream::open(target, EventFormat::Ndjson, None)
+                    .expect("Failed to open rules output stream")
+            }),
+            tracked_changes: args.changes.clone(),
+            predicates: args.predicates.clone(),
+            captures: args.captures.clone(),
+            timestamp_field: args.timestamp_field.clone(),
+            overridden_instants: HashMap::new(),
+            patches: args.patches,
+            changed_fields: args.changed_fields,
+            include_values: args.include_values,
+            order: args.order,
+            keep_changes: args.output_path.is_some()
+                || args.redis.is_some()
+                || args.event_log.is_some()
+                || args.graveyard.is_some()
+                || args.season_summary.is_some()
+                || args.calendar_report.is_some()
+                || args.group_output.is_some()
+                || args.emit_presence_index.is_some()
+                || !args.only_keys.is_empty(),
+            change_ids: args.change_ids,
+            found: 0,
+        }
+    }
+
+    /// Emits the change to `rules_stream` once for each rule it matches.
+    fn notify(
+        &mut self,
+        path: &Path,
+        primary_key: &str,
+        change_instant: &ChangeInstant,
+        change_type: ChangeType,
+        old: Option<&serde_json::Value>,
+        new: Option<&serde_json::Value>,
+    ) {
+        let Some(rules_stream) = &mut self.rules_stream else {
+            return;
+        };
+        if !self.tracked_changes.contains(&change_type) {
+            return;
+        }
+        let id = self
+            .change_ids
+            .then(|| change_id(path, primary_key, &change_instant.commit, change_type));
+        for rule in &self.rules {
+            if rule.matches(change_type, old, new) {
+                rules_stream
+                    .emit_match(
+                        &rule.name,
+                        path,
+                        primary_key,
+                        change_type,
+                        change_instant,
+                        id.as_deref(),
+                    )
+                    .expect("Failed to write rule match");
+            }
+        }
+    }
+
+    /// Returns whether a removed record should go to the graveyard, i.e. it was not re-added
+    /// later in history. `old` is `None` for additions and `new` is `None` for removals;
+    /// `--capture` fields are taken from the record's version at the change (the last one, for
+    /// removals).
+    #[allow(clippy::too_many_arguments)]
+    fn record(
+        &mut self,
+        change_record_entry: &mut HashMap<String, ChangeRecord>,
+        path: &Path,
+        primary_key: String,
+        change_instant: Arc<ChangeInstant>,
+        change_type: ChangeType,
+        old: Option<&serde_json::Value>,
+        new: Option<&serde_json::Value>,
+        patch: Option<Vec<PatchOperation>>,
+        changed_fields: Option<Vec<String>>,
+    ) -> bool {
+        if !self.tracked_changes.contains(&change_type) {
+            return change_type == ChangeType::Removed;
+        }
+        let id = self
+            .change_ids
+            .then(|| change_id(path, &primary_key, &change_instant.commit, change_type));
+        let values = self.include_values.map(|include_values| {
+            let version =
+                |value: Option<&serde_json::Value>| match (include_values, &changed_fields) {
+                    (IncludeValues::Fields, Some(fields)) => value.map(|value| {
+                        fields
+                            .iter()
+                            .filter_map(|field| Some((field.clone(), value.get(field)?.clone())))
+                            .collect()
+                    }),
+                    _ => value.cloned(),
+                };
+            ChangeValues {
+                old: version(old),
+                new: version(new),
+            }
+        });
+        if let Some(event_stream) = &mut self.event_stream {
+            let event_type = self
+                .rules
+                .iter()
+                .filter(|rule| rule.matches(change_type, old, new))
+                .find_map(|rule| rule.promote_to.as_deref())
+                .unwrap_or(change_type.as_str());
+            event_stream
+                .emit(
+                    path,
+                    &primary_key,
+                    event_type,
+                    &change_instant,
+                    id.as_deref(),
+                    values.as_ref(),
+                )
+                .expect("Failed to write event");
+        }
+        if !self.keep_changes {
+            return false;
+        }
+        let content = new.or(old).expect("A change has an old or a new version");
+        let change = Change {
+            id,
+            instant: change_instant,
+            captured: (!self.captures.is_empty()).then(|| {
+                self.captures
+                    .iter()
+                    .filter_map(|pointer| {
+                        Some((pointer.clone(), content.pointer(pointer)?.clone()))
+                    })
+                    .collect()
+            }),
+            patch,
+            changed_fields: changed_fields.filter(|_| self.changed_fields),
+            values,
+        };
+        let change_record = change_record_entry
+            .entry(primary_key)
+            .or_insert(ChangeRecord {
+                added: vec![],
+                removed: vec![],
+                modified: vec![],
+                order: self.order,
+            });
+        match change_type {
+            ChangeType::Added => {
+                change_record.added.push(change);
+            }
+            ChangeType::Removed => {
+                change_record.removed.push(change);
+                return change_record.added.is_empty();
+            }
+            ChangeType::Modified => {
+                change_record.modified.push(change);
+            }
+        }
+        false
+    }
+
+    /// The instant of a change whose new version is `new`: the commit's, unless
+    /// `--timestamp-field` finds a timestamp in `new`.
+    fn instant_for(
+        &mut self,
+        change_instant: &Arc<ChangeInstant>,
+        new: Option<&serde_json::Value>,
+    ) -> Arc<ChangeInstant> {
+        let timestamp = self
+            .timestamp_field
+            .as_deref()
+            .zip(new)
+            .and_then(|(pointer, new)| new.pointer(pointer))
+            .and_then(parse_timestamp);
+        match timestamp {
+            Some(timestamp) if timestamp != change_instant.timestamp => self
+                .overridden_instants
+                .entry(timestamp)
+                .or_insert_with(|| {
+                    Arc::new(ChangeInstant {
+                        timestamp,
+                        ..(**change_instant).clone()
+                    })
+                })
+                .clone(),
+            _ => change_instant.clone(),
+        }
+    }
+
+    /// Whether changes to a record with this content (the new version, or the last one for
+    /// removals) are recorded under `--where`.
+    fn selects(&self, record: &serde_json::Value) -> bool {
+        self.predicates
+            .iter()
+            .all(|predicate| predicate.matches(record))
+    }
+
+    /// Records the changes between two versions of a file, comparing records with
+    /// `diff_options` unless modifications are not tracked. Returns the keys of removed records
+    /// that should go to the graveyard.
+    fn record_diff<'a>(
+        &mut self,
+        change_record_entry: &mut HashMap<String, ChangeRecord>,
+        path: &Path,
+        old_content: &'a FileRecords,
+        new_content: &FileRecords,
+        change_instant: &Arc<ChangeInstant>,
+        diff_options: Option<&DiffOptions>,
+    ) -> Vec<&'a str> {
+        let mut graveyard = vec![];
+        for (pk, old_val) in &old_content.records {
+            let new_val = new_content.records.get(pk).map(Arc::as_ref);
+            let mut patch = None;
+            let change_type = match (new_val, diff_options) {
+                (None, _) => ChangeType::Removed,
+                _ if old_content.unchanged_in(new_content, pk) => continue,
+                (Some(new_val), Some(diff_options)) if self.patches => {
+                    let operations = json_patch(old_val, new_val, diff_options);
+                    if operations.is_empty() {
+                        continue;
+                    }
+                    patch = Some(operations);
+                    ChangeType::Modified
+                }
+                (Some(new_val), Some(diff_options))
+                    if diff_records(old_val, new_val, diff_options).changed =>
+                {
+                    ChangeType::Modified
+                }
+                (Some(_), Some(_)) => continue,
+                // Contents are not compared when modifications are not tracked, so any difference
+                // counts as a change.
+                (Some(_), None) => {
+                    self.found += 1;
+                    continue;
+                }
+            };
+            self.found += 1;
+            if !self.selects(new_val.unwrap_or(old_val)) {
+                continue;
+            }
+            let changed_fields = new_val
+                .zip(diff_options)
+                .filter(|_| {
+                    change_type == ChangeType::Modified
+                        && (self.changed_fields
+                            || self.include_values == Some(IncludeValues::Fields))
+                })
+                .map(|(new_val, diff_options)| changed_fields(old_val, new_val, diff_options));
+            let change_instant = self.instant_for(change_instant, new_val);
+            self.notify(
+                path,
+                pk,
+                &change_instant,
+                change_type,
+                Some(old_val),
+                new_val,
+            );
+            let should_graveyard = self.record(
+                change_record_entry,
+                path,
+                pk.to_string(),
+                change_instant,
+                change_type,
+                Some(old_val),
+                new_val,
+                patch,
+                changed_fields,
+            );
+            if should_graveyard {
+                graveyard.push(pk.as_str());
+            }
+        }
+        for (pk, new_val) in &new_content.records {
+            if old_content.records.contains_key(pk) {
+                continue;
+            }
+            self.found += 1;
+            if self.selects(new_val) {
+                let change_instant = self.instant_for(change_instant, Some(new_val));
+                self.notify(
+                    path,
+                    pk,
+                    &change_instant,
+                    ChangeType::Added,
+                    None,
+                    Some(new_val),
+                );
+                self.record(
+                    change_record_entry,
+                    path,
+                    pk.to_string(),
+                    change_instant,
+                    ChangeType::Added,
+                    None,
+                    Some(new_val),
+                    None,
+                    None,
+                );
+            }
+        }
+        graveyard
+    }
+
+    fn flush(&mut self) {
+        self.overridden_instants.clear();
+        if let Some(event_stream) = &mut self.event_stream {
+            event_stream.flush().expect("Failed to flush output stream");
+        }
+        if let Some(rules_stream) = &mut self.rules_stream {
+            rules_stream
+                .flush()
+                .expect("Failed to flush rules output stream");
+        }
+    }
+}
+
+/// Loads the primary keys of every tracked file in the HEAD tree.
+fn get_presence(
+    repo: &git2::Repository,
+    args: &ExtractArgs,
+    errors: &mut ErrorBudget,
+) -> Result<HashMap<PathBuf, Vec<String>>, ErrorReport> {
+    let head = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .expect("Failed to find HEAD commit");
+    let head_id = head.id().to_string();
+    let tree = head.tree().expect("Failed to get HEAD tree");
+    let mut paths = vec![];
+    tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+        if entry.kind() != Some(git2::ObjectType::Tree) {
+            let path = Path::new(dir).join(entry.name().unwrap());
+            if glob_match(args.include.as_str(), path.to_str().unwrap()) {
+                paths.push(path);
+            }
+        }
+        git2::TreeWalkResult::Ok
+    })
+    .expect("Failed to walk HEAD tree");
+    let only_keys: HashSet<String> = args.only_keys.iter().cloned().collect();
+    let keys = (!only_keys.is_empty()).then_some(&only_keys);
+    let mut presence = HashMap::new();
+    for path in paths {
+        if let Some(data) = get_json_data(repo, &tree, &path, args, keys, &head_id, errors)? {
+            presence.insert(path, data.into_keys().collect());
+        }
+    }
+    Ok(presence)
+}
+
+/// A walk over HEAD and its ancestors, minus everything reachable from an `--until` or `--not`
+/// revision.
+fn new_revwalk<'repo>(repo: &'repo git2::Repository, args: &ExtractArgs) -> git2::Revwalk<'repo> {
+    let mut revwalk = repo.revwalk().expect("Failed to create revwalk");
+    revwalk.push_head().expect("Failed to push HEAD");
+    for rev in args.until.iter().chain(&args.not) {
+        if rev.contains(['*', '?', '[']) {
+            revwalk
+                .hide_glob(rev)
+                .unwrap_or_else(|_| panic!("Failed to hide refs matching {}", rev));
+            continue;
+        }
+        let commit = repo
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_commit())
+            .unwrap_or_else(|_| panic!("Failed to find commit {}", rev));
+        revwalk
+            .hide(commit.id())
+            .unwrap_or_else(|_| panic!("Failed to hide commit {}", rev));
+    }
+    revwalk
+}
+
+/// Parses a timestamp in the data: Unix seconds, RFC 3339, or a date and time without an offset,
+/// taken as UTC. Numbers outside the range of dates, such as microseconds since the epoch, are
+/// not timestamps.
+fn parse_timestamp(value: &serde_json::Value) -> Option<i64> {
+    let timestamp = match value {
+        serde_json::Value::Number(seconds) => seconds.as_i64(),
+        serde_json::Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .map(|time| time.timestamp())
+            .or_else(|_| {
+                NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f")
+                    .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f"))
+                    .map(|time| time.and_utc().timestamp())
+            })
+            .ok(),
+        _ => None,
+    }?;
+    DateTime::from_timestamp(timestamp, 0).map(|_| timestamp)
+}
+
+/// Whether the Unix time `timestamp` is outside every `--flag-outside-schedule` window, if any
+/// are given.
+fn outside_schedule(args: &ExtractArgs, timestamp: i64) -> bool {
+    !args.flag_outside_schedule.is_empty()
+        && !args
+            .flag_outside_schedule
+            .iter()
+            .any(|schedule| schedule.contains(timestamp))
+}
+
+/// The `--with-commit-meta` metadata of `commit`.
+fn commit_meta(commit: &git2::Commit) -> CommitMeta {
+    let person = |signature: git2::Signature| Person {
+        name: String::from_utf8_lossy(signature.name_bytes()).into_owned(),
+        email: String::from_utf8_lossy(signature.email_bytes()).into_owned(),
+    };
+    CommitMeta {
+        author: person(commit.author()),
+        committer: person(commit.committer()),
+        summary: String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default()).into_owned(),
+    }
+}
+
+/// The trailers of `commit` (e.g. `Sync-Batch: 2024-09-01T02:00`) whose keys are in `keys`.
+/// A trailer given more than once keeps its last value.
+fn commit_trailers(commit: &git2::Commit, keys: &[String]) -> BTreeMap<String, String> {
+    if keys.is_empty() {
+        return BTreeMap::new();
+    }
+    let Some(trailers) = commit
+        .message()
+        .and_then(|message| git2::message_trailers_strs(message).ok())
+    else {
+        return BTreeMap::new();
+    };
+    trailers
+        .iter()
+        .filter(|(key, _)| keys.iter().any(|k| k.eq_ignore_ascii_case(key)))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Remembers the most recent value of the field at `field`, if any, of each record in `content`.
+/// Records are seen newest first, so values already noted are kept.
+fn note_field_values(
+    values: &mut HashMap<PathBuf, HashMap<String, String>>,
+    field: Option<&str>,
+    path: &Path,
+    content: &HashMap<String, Arc<serde_json::Value>>,
+) {
+    let Some(field) = field else {
+        return;
+    };
+    let path_values = values.entry(path.to_path_buf()).or_default();
+    for (pk, record) in content {
+        let value = match record.pointer(field) {
+            Some(serde_json::Value::String(value)) => value.clone(),
+            Some(value) => value.to_string(),
+            None => continue,
+        };
+        path_values.entry(pk.clone()).or_insert(value);
+    }
+}
+
+/// Walks the repository from HEAD and records every change to the tracked files.
+pub(crate) fn diff_options(args: &ExtractArgs) -> DiffOptions {
+    DiffOptions {
+        ignore_fields: args.ignore_fields.clone(),
+        ignore_array_order: args.ignore_array_order,
+        normalize_numbers: args.normalize_numbers,
+        ignore_field_names: args.ignore_field_names,
+    }
+}
+
+/// The commit of the change instant produced by `--baseline`.
+pub const BASELINE_COMMIT: &str = "baseline";
+
+/// Compares `baseline` against `--baseline-path` at `--baseline-rev`. A path missing at that
+/// revision counts as empty, so every baseline record is added.
+fn run_baseline(args: &ExtractArgs, baseline: &Path) -> Result<Extraction, ErrorReport> {
+    let repo = git2::Repository::open(&args.repo_path).expect("Failed to open repository");
+    let path = args
+        .baseline_path
+        .as_deref()
+        .expect("--baseline-path is required with --baseline");
+    let commit = repo
+        .revparse_single(&args.baseline_rev)
+        .and_then(|object| object.peel_to_commit())
+        .unwrap_or_else(|_| panic!("Failed to find revision {}", args.baseline_rev));
+    let commit_id = commit.id().to_string();
+    let tree = commit.tree().expect("Failed to get commit tree");
+    let keys: Option<HashSet<String>> =
+        (!args.only_keys.is_empty()).then(|| args.only_keys.iter().cloned().collect());
+    let mut errors = ErrorBudget::new(args.max_errors);
+    let old_content = match tree.get_path(path) {
+        Ok(_) => get_json_data(
+            &repo,
+            &tree,
+            path,
+            args,
+            keys.as_ref(),
+            &commit_id,
+            &mut errors,
+        )?,
+        Err(_) => Some(HashMap::new()),
+    }
+    .map(FileRecords::new);
+    let baseline_content = std::fs::read(baseline).expect("Failed to read --baseline");
+    let new_content = parse_records(
+        &baseline_content,
+        path,
+        args,
+        keys.as_ref(),
+        BASELINE_COMMIT,
+        &mut errors,
+    )?
+    .map(FileRecords::new);
+    let mut extraction = Extraction::default();
+    if let (Some(old_content), Some(new_content)) = (old_content, new_content) {
+        let mut recorder = Recorder::new(args);
+        let now = chrono::Utc::now().timestamp();
+        let change_instant = Arc::new(ChangeInstant {
+            commit: BASELINE_COMMIT.to_string(),
+            timestamp: now,
+            trailers: BTreeMap::new(),
+            timestamp_format: args.timestamp_format.clone(),
+            meta: None,
+            outside_schedule: outside_schedule(args, now),
+        });
+        let diff_options = diff_options(args);
+        let change_record_entry = extraction
+            .change_records
+            .entry(path.to_path_buf())
+            .or_default();
+        let removed = recorder.record_diff(
+            change_record_entry,
+            path,
+            &old_content,
+            &new_content,
+            &change_instant,
+            args.changes
+                .contains(&ChangeType::Modified)
+                .then_some(&diff_options),
+        );
+        if args.graveyard.is_some() {
+            let graveyard_entry = extraction.graveyard.entry(path.to_path_buf()).or_default();
+            for pk in removed {
+                graveyard_entry.insert(pk.to_string(), (*old_content.records[pk]).clone());
+            }
+        }
+        recorder.flush();
+    }
+    (extraction.errors, extraction.warnings) = errors.finish();
+    Ok(extraction)
+}
+
+pub fn run(args: &ExtractArgs) -> Result<Extraction, ErrorReport> {
+    run_with_flush(args, |_| {})
+}
+
+/// The start of the `--recent-first-flush` window: `months` before the time of HEAD's commit.
+fn recent_cutoff(repo: &git2::Repository, months: u32) -> i64 {
+    let head = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .expect("Failed to read HEAD");
+    DateTime::from_timestamp(head.time().seconds(), 0)
+        .unwrap()
+        .checked_sub_months(Months::new(months))
+        .expect("--recent-first-flush is out of range")
+        .timestamp()
+}
+
+/// Like [`run`], but under `--recent-first-flush` also calls `flush` with the changes of the recent
+/// months as soon as the walk reaches older commits.
+pub fn run_with_flush(
+    args: &ExtractArgs,
+    mut flush: impl FnMut(&Extraction),
+) -> Result<Extraction, ErrorReport> {
+    if let Some(baseline) = &args.baseline {
+        return run_baseline(args, baseline);
+    }
+    let repo = git2::Repository::open(&args.repo_path).expect("Failed to open repository");
+    let mut recent_cutoff = args
+        .recent_first_flush
+        .map(|months| recent_cutoff(&repo, months));
+    let mut revwalk = new_revwalk(&repo, args);
+    let commit_count = new_revwalk(&repo, args).count();
+    let mut recorder = Recorder::new(args);
+    let writes_stdout = [&args.output, &args.rules_output]
+        .iter()
+        .any(|target| matches!(target, Some(OutputTarget::Stdout)));
+    let progress_bar = if args.quiet || writes_stdout {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(commit_count as u64)
+    };
+    progress_bar.println(format!("Found {} commits", commit_count));
+    let mut change_records: HashMap<PathBuf, HashMap<String, ChangeRecord>> = HashMap::new();
+    let diff_options = diff_options(args);
+    let mut ignore_revs: HashSet<String> = args.ignore_revs.iter().cloned().collect();
+    if let Some(ignore_revs_file) = &args.ignore_revs_file {
+        let content =
+            std::fs::read_to_string(ignore_revs_file).expect("Failed to read --ignore-revs-file");
+        ignore_revs.extend(
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string),
+        );
+    }
+    let track_modified = args.changes.contains(&ChangeType::Modified);
+    let mut noop_commits = vec![];
+    let mut sync_times = vec![];
+    let mut seasons: HashMap<PathBuf, HashMap<String, String>> = HashMap::new();
+    let mut groups: HashMap<PathBuf, HashMap<String, String>> = HashMap::new();
+    let mut note_fields = |path: &Path, content: &HashMap<String, Arc<serde_json::Value>>| {
+        note_field_values(&mut seasons, args.season_field.as_deref(), path, content);
+        note_field_values(&mut groups, args.group_by.as_deref(), path, content);
+    };
+    let mut record_counts: HashMap<PathBuf, Vec<RecordCount>> = HashMap::new();
+    let mut record_count = |path: &Path, instant: &ChangeInstant, count: usize| {
+        if args.record_counts.is_some() {
+            record_counts
+                .entry(path.to_path_buf())
+                .or_default()
+                .push(RecordCount {
+                    instant: instant.clone(),
+                    count,
+                });
+        }
+    };
+    // Requested keys whose most recent addition has not been found yet.
+    let mut pending_keys: Option<HashSet<String>> =
+        (!args.only_keys.is_empty()).then(|| args.only_keys.iter().cloned().collect());
+    let mut errors = ErrorBudget::new(args.max_errors);
+    let mut blob_cache = BlobCache::default();
+    // Topological so that a commit dated before its parent (clock skew) is still walked before
+    // the parent.
+    revwalk
+        .set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)
+        .unwrap();
+    let mut graveyard: HashMap<PathBuf, HashMap<String, serde_json::Value>> = HashMap::new();
+
+    for oid in revwalk {
+        let oid = oid.expect("Failed to get oid");
+        if ignore_revs.contains(&oid.to_string()) {
+            continue;
+        }
+        let commit = match read_with_refetch(&repo, oid, args.offline, || repo.find_commit(oid)) {
+            Ok(commit) => commit,
+            Err(e) => {
+                errors.warn(
+                    &oid.to_string(),
+                    Path::new(""),
+                    format!("Skipped commit that could not be read: {}", e.message()),
+                );
+                continue;
+            }
+        };
+        if !args.include_authors.is_empty()
+            && !args
+                .include_authors
+                .contains(&commit.author().name().unwrap().to_string())
+            && !args
+                .include_authors
+                .contains(&commit.author().email().unwrap().to_string())
+        {
+            continue;
+        }
+        if recent_cutoff.is_some_and(|cutoff| commit.time().seconds() < cutoff) {
+            recent_cutoff = None;
+            progress_bar.println("Writing the recent changes");
+            let recent = Extraction {
+                change_records: std::mem::take(&mut change_records),
+                graveyard: std::mem::take(&mut graveyard),
+                presence: HashMap::new(),
+                noop_commits: std::mem::take(&mut noop_commits),
+                sync_times: std::mem::take(&mut sync_times),
+                record_counts: HashMap::new(),
+                seasons: HashMap::new(),
+                groups: HashMap::new(),
+                errors: vec![],
+                warnings: vec![],
+            };
+            flush(&recent);
+            change_records = recent.change_records;
+            graveyard = recent.graveyard;
+            noop_commits = recent.noop_commits;
+            sync_times = recent.sync_times;
+        }
+        // Histories merged in from elsewhere have their own root commits, so a root only ends
+        // its own lineage.
+        if commit.parent_count() == 0 {
+            progress_bar.println(format!("Commit {} with no parent", commit.id()));
+            continue;
+        }
+        // A corrupt object skips the commit rather than aborting the run, since the rest of the
+        // history is usually still readable.
+        let objects = read_with_refetch(&repo, oid, args.offline, || {
+            let parent_commit = commit.parent(0)?;
+            let parent_tree = parent_commit.tree()?;
+            let commit_tree = commit.tree()?;
+            let mut diff = repo.diff_tree_to_tree(Some(&parent_tree), Some(&commit_tree), None)?;
+            diff.find_similar(Some(git2::DiffFindOptions::new().renames(true)))?;
+            Ok((parent_commit, parent_tree, commit_tree, diff))
+        });
+        let (parent_commit, parent_tree, commit_tree, diff) = match objects {
+            Ok(objects) => objects,
+            Err(e) => {
+                errors.warn(
+                    &oid.to_string(),
+                    Path::new(""),
+                    format!("Skipped commit that could not be read: {}", e.message()),
+                );
+                continue;
+            }
+        };
+        let (parent_tree, commit_tree) = (&parent_tree, &commit_tree);
+        progress_bar.println(format!(
+            "Diffing {} '{}' with {} '{}'",
+            parent_commit.id(),
+            parent_commit.message().unwrap().trim(),
+            commit.id(),
+            commit.message().unwrap().trim(),
+        ));
+        let changed_files = diff.deltas();
+        progress_bar.println(format!("Changed {} files", changed_files.len()));
+        let found_before = recorder.found;
+        let mut timestamp = commit.time().seconds();
+        let parent_timestamp = parent_commit.time().seconds();
+        if timestamp < parent_timestamp {
+            match args.clock_skew {
+                ClockSkew::Keep => {}
+                ClockSkew::Clamp => timestamp = parent_timestamp,
+                ClockSkew::Warn => errors.warn(
+                    &commit.id().to_string(),
+                    Path::new(""),
+                    format!(
+                        "Commit time is {}s before its parent's",
+                        parent_timestamp - timestamp
+                    ),
+                ),
+            }
+        }
+        let change_instant = Arc::new(ChangeInstant {
+            commit: commit.id().to_string(),
+            timestamp,
+            trailers: commit_trailers(&commit, &args.trailers),
+            timestamp_format: args.timestamp_format.clone(),
+            meta: args.with_commit_meta.then(|| commit_meta(&commit)),
+            outside_schedule: outside_schedule(args, timestamp),
+        });
+        let mut tracked_files = vec![];
+        // A file that could not be read may have changed, so the commit is not a no-op.
+        let mut skipped_files = false;
+        // A renamed file's records are removed from its old path and added at its new one, and
+        // each side is included if its own path matches, so that files moving into or out of
+        // the included paths are still recorded.
+        let file_changes = changed_files.flat_map(|delta| {
+            let old_path = delta.old_file().path().unwrap();
+            let new_path = delta.new_file().path().unwrap();
+            match delta.status() {
+                git2::Delta::Renamed => vec![
+                    (git2::Delta::Deleted, old_path, old_path),
+                    (git2::Delta::Added, new_path, new_path),
+                ],
+                status => vec![(status, old_path, new_path)],
+            }
+        });
+        for (status, old_path, new_path) in file_changes {
+            let path = match status {
+                git2::Delta::Deleted => old_path,
+                _ => new_path,
+            };
+            if !glob_match(args.include.as_str(), path.to_str().unwrap()) {
+                continue;
+            }
+            progress_bar.println(format!("Diffing: {}", old_path.to_string_lossy()));
+            tracked_files.push(new_path.to_path_buf());
+            let commit_id = commit.id().to_string();
+            let change_record_entry = change_records.entry(new_path.to_path_buf()).or_default();
+            let graveyard_entry = graveyard.entry(new_path.to_path_buf()).or_default();
+            match status {
+                git2::Delta::Added => {
+                    let Some(new_content) = blob_cache.load(
+                        &repo,
+                        commit_tree,
+                        new_path,
+                        args,
+                        pending_keys.as_ref(),
+                        &commit_id,
+                        &mut errors,
+                        None,
+                    )?
+                    else {
+                        skipped_files = true;
+                        continue;
+                    };
+                    record_count(new_path, &change_instant, new_content.records.len());
+                    note_fields(new_path, &new_content.records);
+                    recorder.record_diff(
+                        change_record_entry,
+                        new_path,
+                        &FileRecords::default(),
+                        &new_content,
+                        &change_instant,
+                        None,
+                    );
+                }
+                git2::Delta::Deleted => {
+                    let Some(old_content) = blob_cache.load(
+                        &repo,
+                        parent_tree,
+                        old_path,
+                        args,
+                        pending_keys.as_ref(),
+                        &commit_id,
+                        &mut errors,
+                        None,
+                    )?
+                    else {
+                        skipped_files = true;
+                        continue;
+                    };
+                    record_count(old_path, &change_instant, 0);
+                    note_fields(old_path, &old_content.records);
+                    let removed = recorder.record_diff(
+                        change_record_entry,
+                        old_path,
+                        &old_content,
+                        &FileRecords::default(),
+                        &change_instant,
+                        None,
+                    );
+                    if args.graveyard.is_some() {
+                        for pk in removed {
+                            graveyard_entry
+                                .insert(pk.to_string(), (*old_content.records[pk]).clone());
+                        }
+                    }
+                }
+                git2::Delta::Modified => {
+                    let Some(new_content) = blob_cache.load(
+                        &repo,
+                        commit_tree,
+                        new_path,
+                        args,
+                        pending_keys.as_ref(),
+                        &commit_id,
+                        &mut errors,
+                        None,
+                    )?
+                    else {
+                        skipped_files = true;
+                        continue;
+                    };
+                    let Some(old_content) = blob_cache.load(
+                        &repo,
+                        parent_tree,
+                        old_path,
+                        args,
+                        pending_keys.as_ref(),
+                        &commit_id,
+                        &mut errors,
+                        Some(new_content.as_ref()),
+                    )?
+                    else {
+                        skipped_files = true;
+                        continue;
+                    };
+                    record_count(new_path, &change_instant, new_content.records.len());
+                    note_fields(new_path, &new_content.records);
+                    note_fields(old_path, &old_content.records);
+                    let removed = recorder.record_diff(
+                        change_record_entry,
+                        new_path,
+                        &old_content,
+                        &new_content,
+                        &change_instant,
+                        track_modified.then_some(&diff_options),
+                    );
+                    if args.graveyard.is_some() {
+                        for pk in removed {
+                            graveyard_entry
+                                .insert(pk.to_string(), (*old_content.records[pk]).clone());
+                        }
+                    }
+                }
+                _ => panic!("Unknown delta type {status:?}"),
+            }
+        }
+        if !tracked_files.is_empty() {
+            sync_times.push(timestamp);
+        }
+        if !tracked_files.is_empty() && !skipped_files && recorder.found == found_before {
+            noop_commits.push(NoopCommit {
+                instant: (*change_instant).clone(),
+                message: commit.summary().unwrap_or_default().to_string(),
+                files: tracked_files,
+            });
+        }
+        recorder.flush();
+        progress_bar.inc(1);
+        blob_cache.next_commit();
+        if let Some(pending_keys) = &mut pending_keys {
+            let pending = pending_keys.len();
+            pending_keys.retain(|pk| {
+                !change_records
+                    .values()
+                    .any(|records| records.get(pk).is_some_and(|r| !r.added.is_empty()))
+            });
+            if pending_keys.len() < pending {
+                // Cached versions still hold the keys found now, which older versions leave out.
+                blob_cache = BlobCache::default();
+            }
+            if pending_keys.is_empty() {
+                progress_bar.println("Found the addition of every requested key");
+                break;
+            }
+        }
+    }
+    progress_bar.finish();
+    let presence = match args.emit_presence_index {
+        Some(_) => get_presence(&repo, args, &mut errors)?,
+        None => HashMap::new(),
+    };
+    let (errors, warnings) = errors.finish();
+    Ok(Extraction {
+        change_records,
+        graveyard,
+        presence,
+        noop_commits,
+        sync_times,
+        record_counts,
+        seasons,
+        groups,
+        errors,
+        warnings,
+    })
+}
@@ -0,0 +1,209 @@
+//! Helpers for building small synthetic data repositories and checking extraction output against
+//! golden files. `delorean selftest` is built on these, and they are public so that users can
+//! write regression tests for their own configurations.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Commits without an explicit time are spaced one day apart starting here
+/// (2023-01-01T00:00:00Z), so fixture commit hashes are stable across runs.
+const DEFAULT_START_TIME: i64 = 1672531200;
+const DAY: i64 = 24 * 60 * 60;
+
+/// A git repository with a work tree, built one [`FixtureCommit`] at a time.
+pub struct FixtureRepo {
+    repo: git2::Repository,
+    next_time: i64,
+}
+
+/// The file changes, author, and time of a single fixture commit.
+pub struct FixtureCommit {
+    message: String,
+    author_name: String,
+    author_email: String,
+    time: Option<i64>,
+    changes: Vec<(PathBuf, Option<Vec<u8>>)>,
+    /// Parents besides HEAD
+    merged: Vec<git2::Oid>,
+}
+
+impl FixtureCommit {
+    pub fn new(message: impl Into<String>) -> Self {
+        FixtureCommit {
+            message: message.into(),
+            author_name: "DeLorean Fixture".to_string(),
+            author_email: "fixture@delorean.invalid".to_string(),
+            time: None,
+            changes: vec![],
+            merged: vec![],
+        }
+    }
+
+    pub fn author(mut self, name: impl Into<String>, email: impl Into<String>) -> Self {
+        self.author_name = name.into();
+        self.author_email = email.into();
+        self
+    }
+
+    /// Sets the author and committer time, in seconds since the epoch.
+    pub fn at(mut self, time: i64) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    pub fn write(mut self, path: impl AsRef<Path>, content: impl Into<Vec<u8>>) -> Self {
+        self.changes
+            .push((path.as_ref().to_path_buf(), Some(content.into())));
+        self
+    }
+
+    pub fn write_json(self, path: impl AsRef<Path>, value: &serde_json::Value) -> Self {
+        let content = serde_json::to_vec_pretty(value).expect("Failed to serialize json");
+        self.write(path, content)
+    }
+
+    pub fn remove(mut self, path: impl AsRef<Path>) -> Self {
+        self.changes.push((path.as_ref().to_path_buf(), None));
+        self
+    }
+
+    /// Makes the commit a merge of `parent`, usually made with [`FixtureRepo::commit_aside`].
+    pub fn merge(mut self, parent: git2::Oid) -> Self {
+        self.merged.push(parent);
+        self
+    }
+}
+
+fn io_error(err: std::io::Error) -> git2::Error {
+    git2::Error::from_str(&err.to_string())
+}
+
+impl FixtureRepo {
+    /// Creates an empty repository at `path`, which must not already contain one.
+    pub fn init(path: &Path) -> Result<Self, git2::Error> {
+        let repo = git2::Repository::init(path)?;
+        Ok(FixtureRepo {
+            repo,
+            next_time: DEFAULT_START_TIME,
+        })
+    }
+
+    /// The work tree of the repository, suitable as the `repo_path` of an extraction.
+    pub fn path(&self) -> &Path {
+        self.repo
+            .workdir()
+            .expect("Fixture repository has no work tree")
+    }
+
+    pub fn repository(&self) -> &git2::Repository {
+        &self.repo
+    }
+
+    /// Applies the commit's changes to the work tree and index and commits them on top of HEAD.
+    pub fn commit(&mut self, commit: FixtureCommit) -> Result<git2::Oid, git2::Error> {
+        self.commit_to(commit, Some("HEAD"))
+    }
+
+    /// Like [`FixtureRepo::commit`], but leaves HEAD where it is, as if committing on a side
+    /// branch. The changes stay in the work tree and index, so the next commit, which can
+    /// [`FixtureCommit::merge`] this one, has them too.
+    pub fn commit_aside(&mut self, commit: FixtureCommit) -> Result<git2::Oid, git2::Error> {
+        self.commit_to(commit, None)
+    }
+
+    fn commit_to(
+        &mut self,
+        commit: FixtureCommit,
+        update_ref: Option<&str>,
+    ) -> Result<git2::Oid, git2::Error> {
+        let workdir = self.path().to_path_buf();
+        let mut index = self.repo.index()?;
+        for (path, content) in &commit.changes {
+            let full_path = workdir.join(path);
+            match content {
+                Some(content) => {
+                    fs::create_dir_all(full_path.parent().unwrap()).map_err(io_error)?;
+                    fs::write(&full_path, content).map_err(io_error)?;
+                    index.add_path(path)?;
+                }
+                None => {
+                    fs::remove_file(&full_path).map_err(io_error)?;
+                    index.remove_path(path)?;
+                }
+            }
+        }
+        index.write()?;
+        let tree = self.repo.find_tree(index.write_tree()?)?;
+
+        let time = commit.time.unwrap_or(self.next_time);
+        self.next_time = time + DAY;
+        let signature = git2::Signature::new(
+            &commit.author_name,
+            &commit.author_email,
+            &git2::Time::new(time, 0),
+        )?;
+        let mut parents = match self.repo.head() {
+            Ok(head) => vec![head.peel_to_commit()?],
+            Err(_) => vec![],
+        };
+        for parent in &commit.merged {
+            parents.push(self.repo.find_commit(*parent)?);
+        }
+        let parents = parents.iter().collect::<Vec<_>>();
+        self.repo.commit(
+            update_ref,
+            &signature,
+            &signature,
+            &commit.message,
+            &tree,
+            &parents,
+        )
+    }
+}
+
+pub(crate) fn list_files(root: &Path, dir: &Path, files: &mut BTreeSet<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries {
+        let path = entry.expect("Failed to read directory entry").path();
+        if path.is_dir() {
+            list_files(root, &path, files);
+        } else {
+            files.insert(path.strip_prefix(root).unwrap().to_path_buf());
+        }
+    }
+}
+
+/// Compares two directory trees file by file and returns a description of every difference.
+/// JSON files are compared by value, so formatting differences are ignored.
+pub fn compare_dirs(actual: &Path, expected: &Path) -> Vec<String> {
+    let mut actual_files = BTreeSet::new();
+    list_files(actual, actual, &mut actual_files);
+    let mut expected_files = BTreeSet::new();
+    list_files(expected, expected, &mut expected_files);
+
+    let mut mismatches = vec![];
+    for path in expected_files.difference(&actual_files) {
+        mismatches.push(format!("Missing file {}", path.display()));
+    }
+    for path in actual_files.difference(&expected_files) {
+        mismatches.push(format!("Unexpected file {}", path.display()));
+    }
+    for path in actual_files.intersection(&expected_files) {
+        let actual_content = fs::read(actual.join(path)).expect("Failed to read file");
+        let expected_content = fs::read(expected.join(path)).expect("Failed to read file");
+        let same = match (
+            serde_json::from_slice::<serde_json::Value>(&actual_content),
+            serde_json::from_slice::<serde_json::Value>(&expected_content),
+        ) {
+            (Ok(actual_json), Ok(expected_json)) => actual_json == expected_json,
+            _ => actual_content == expected_content,
+        };
+        if !same {
+            mismatches.push(format!("Content differs for {}", path.display()));
+        }
+    }
+    mismatches
+}
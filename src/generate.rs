@@ -0,0 +1,182 @@
+//! `delorean generate-fixture`: builds a synthetic data repository of a given size and shape,
+//! with renames, merges, and unparseable files mixed in, so that a configuration can be
+//! benchmarked and validated before it is pointed at a production repository.
+
+use crate::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The primary key of the generated records.
+pub const PRIMARY_KEY: &str = "id";
+
+/// An `--include` glob matching every generated file, including renamed ones.
+pub const INCLUDE: &str = "data/*.json";
+
+/// Options of `delorean generate-fixture`.
+#[derive(clap::Args, Debug, Clone)]
+pub struct GenerateArgs {
+    /// Number of commits after the initial one
+    #[arg(long, default_value_t = 100)]
+    pub commits: usize,
+
+    /// Number of tracked files
+    #[arg(long, default_value_t = 4)]
+    pub files: usize,
+
+    /// Number of records in each file at the initial commit
+    #[arg(long, default_value_t = 100)]
+    pub records: usize,
+
+    /// Approximate size of each record in bytes, reached with a filler field
+    #[arg(long, value_name = "BYTES", default_value_t = 200)]
+    pub record_size: usize,
+
+    /// Number of records each commit adds, removes, or modifies in one of the files
+    #[arg(long, default_value_t = 10)]
+    pub changes_per_commit: usize,
+
+    /// Rename one of the files instead of changing it every this many commits (0 for never)
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub rename_every: usize,
+
+    /// Make every this many commits on a side branch and merge it (0 for never)
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub merge_every: usize,
+
+    /// Leave the changed file unparseable every this many commits, until the next commit fixes
+    /// it (0 for never). Extracting such a repository requires `--max-errors`.
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub corrupt_every: usize,
+
+    /// Seed of the random changes. The same options and seed always build the same repository,
+    /// down to the commit hashes.
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+}
+
+/// SplitMix64, which is enough to spread changes around and gives the same sequence everywhere.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+struct GeneratedFile {
+    path: PathBuf,
+    records: BTreeMap<u64, Value>,
+}
+
+impl GeneratedFile {
+    fn content(&self) -> Value {
+        Value::Array(self.records.values().cloned().collect())
+    }
+}
+
+fn every(n: usize, commit: usize) -> bool {
+    n != 0 && commit.is_multiple_of(n)
+}
+
+fn record(id: u64, rng: &mut Rng, size: usize) -> Value {
+    json!({
+        PRIMARY_KEY: id.to_string(),
+        "value": rng.next() % 1000,
+        "filler": "x".repeat(size.saturating_sub(50)),
+    })
+}
+
+/// Builds a repository at `path`, which must not already contain one, as `args` describe.
+pub fn generate(path: &Path, args: &GenerateArgs) -> Result<FixtureRepo, git2::Error> {
+    let mut fixture = FixtureRepo::init(path)?;
+    let mut rng = Rng(args.seed);
+    let mut next_id = 0;
+    let mut files = (0..args.files.max(1))
+        .map(|index| GeneratedFile {
+            path: PathBuf::from(format!("data/{index}.json")),
+            records: BTreeMap::new(),
+        })
+        .collect::<Vec<_>>();
+    let mut initial = FixtureCommit::new("Initial sync");
+    for file in &mut files {
+        for _ in 0..args.records {
+            file.records
+                .insert(next_id, record(next_id, &mut rng, args.record_size));
+            next_id += 1;
+        }
+        initial = initial.write_json(&file.path, &file.content());
+    }
+    fixture.commit(initial)?;
+
+    let mut corrupted = None;
+    for number in 1..=args.commits {
+        let index = rng.below(files.len());
+        let rename = every(args.rename_every, number)
+            .then(|| PathBuf::from(format!("data/{index}-{number}.json")));
+        let mut commit = FixtureCommit::new(match &rename {
+            Some(new_path) => format!(
+                "Rename {} to {}",
+                files[index].path.display(),
+                new_path.display()
+            ),
+            None => format!("Update {}", files[index].path.display()),
+        });
+        if let Some(corrupted) = corrupted.take() {
+            let file: &GeneratedFile = &files[corrupted];
+            commit = commit.write_json(&file.path, &file.content());
+        }
+        let file = &mut files[index];
+        if let Some(new_path) = rename {
+            commit = commit
+                .remove(&file.path)
+                .write_json(&new_path, &file.content());
+            file.path = new_path;
+        } else {
+            for _ in 0..args.changes_per_commit {
+                let existing = (!file.records.is_empty()).then(|| {
+                    *file
+                        .records
+                        .keys()
+                        .nth(rng.below(file.records.len()))
+                        .unwrap()
+                });
+                match (rng.below(3), existing) {
+                    (0, Some(id)) => {
+                        file.records.remove(&id);
+                    }
+                    (1, Some(id)) => {
+                        file.records
+                            .insert(id, record(id, &mut rng, args.record_size));
+                    }
+                    _ => {
+                        file.records
+                            .insert(next_id, record(next_id, &mut rng, args.record_size));
+                        next_id += 1;
+                    }
+                }
+            }
+            if every(args.corrupt_every, number) {
+                commit = commit.write(&file.path, "[{\"id\": ");
+                corrupted = Some(index);
+            } else {
+                commit = commit.write_json(&file.path, &file.content());
+            }
+        }
+        if every(args.merge_every, number) {
+            let side = fixture.commit_aside(commit)?;
+            fixture.commit(FixtureCommit::new(format!("Merge {side}")).merge(side))?;
+        } else {
+            fixture.commit(commit)?;
+        }
+    }
+    Ok(fixture)
+}
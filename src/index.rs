@@ -0,0 +1,67 @@
+//! Primary-key index (`--write-index`): for every primary key, the output files its record
+//! appears in and the commits of its oldest and newest changes in each, so that where a record
+//! lived over time is a single lookup instead of a scan of every file.
+//!
+//! The index is `<OUTPUT_PATH>/index.json`, with each key's files in the order the record first
+//! changed in them:
+//!
+//! ```json
+//! {
+//!   "12345": [
+//!     {
+//!       "file": "parsed_courses/202301.json",
+//!       "first_commit": "<commit hash>",
+//!       "last_commit": "<commit hash>"
+//!     }
+//!   ]
+//! }
+//! ```
+
+use crate::extract::{ExtractArgs, Extraction};
+use crate::manifest::{change_range, output_file};
+use crate::output::{self, extended_length};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the index file in the output directory.
+pub const FILE_NAME: &str = "index.json";
+
+#[derive(Serialize)]
+struct Location {
+    file: PathBuf,
+    first_commit: String,
+    last_commit: String,
+    #[serde(skip)]
+    first_timestamp: i64,
+}
+
+/// Writes the primary-key index of the output directory written for `extraction`, if
+/// `--write-index` is set.
+pub fn write(args: &ExtractArgs, extraction: &Extraction) -> io::Result<()> {
+    let Some(output_path) = args.output_path.as_ref().filter(|_| args.write_index) else {
+        return Ok(());
+    };
+    let mut index: BTreeMap<&str, Vec<Location>> = BTreeMap::new();
+    for (path, records) in &extraction.change_records {
+        let file = output_file(args, path);
+        for (key, record) in records {
+            let Some((first, last)) = change_range([record]) else {
+                continue;
+            };
+            index.entry(key).or_default().push(Location {
+                file: file.clone(),
+                first_commit: first.commit.clone(),
+                last_commit: last.commit.clone(),
+                first_timestamp: first.timestamp,
+            });
+        }
+    }
+    for locations in index.values_mut() {
+        locations.sort_by(|a, b| (a.first_timestamp, &a.file).cmp(&(b.first_timestamp, &b.file)));
+    }
+    let index_path = extended_length(Path::new(output_path)).join(FILE_NAME);
+    output::write_if_changed(&index_path, &serde_json::to_vec_pretty(&index)?);
+    Ok(())
+}
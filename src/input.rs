@@ -0,0 +1,179 @@
+//! Turns the raw bytes of a tracked blob into something the JSON parser accepts, and parses
+//! other formats into the same JSON value model.
+
+use flate2::read::GzDecoder;
+use std::borrow::Cow;
+use std::io::Read;
+use std::path::Path;
+
+const GZIP_MAGIC: &[u8] = &[0x1F, 0x8B];
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16_BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+fn decode_utf16(content: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Result<Vec<u8>, String> {
+    if !content.len().is_multiple_of(2) {
+        return Err("UTF-16 content has an odd number of bytes".to_string());
+    }
+    let units = content
+        .chunks_exact(2)
+        .map(|pair| from_bytes([pair[0], pair[1]]))
+        .collect::<Vec<u16>>();
+    String::from_utf16(&units)
+        .map(String::into_bytes)
+        .map_err(|e| format!("Invalid UTF-16: {e}"))
+}
+
+/// Transcodes `content` to UTF-8, stripping any byte order mark. UTF-16 without a BOM is
+/// recognized by the zero byte next to the leading ASCII character every JSON document starts
+/// with. Anything else is assumed to already be UTF-8.
+pub fn to_utf8(content: &[u8]) -> Result<Cow<'_, [u8]>, String> {
+    if let Some(rest) = content.strip_prefix(UTF8_BOM) {
+        return Ok(Cow::Borrowed(rest));
+    }
+    if let Some(rest) = content.strip_prefix(UTF16_LE_BOM) {
+        return decode_utf16(rest, u16::from_le_bytes).map(Cow::Owned);
+    }
+    if let Some(rest) = content.strip_prefix(UTF16_BE_BOM) {
+        return decode_utf16(rest, u16::from_be_bytes).map(Cow::Owned);
+    }
+    match content {
+        [0, b, ..] if *b != 0 => decode_utf16(content, u16::from_be_bytes).map(Cow::Owned),
+        [b, 0, ..] if *b != 0 => decode_utf16(content, u16::from_le_bytes).map(Cow::Owned),
+        _ => Ok(Cow::Borrowed(content)),
+    }
+}
+
+/// Whether `content` is gzip-compressed, judging by its magic bytes rather than the file name so
+/// that compressed files are recognized whatever they are called.
+pub fn is_gzip(content: &[u8]) -> bool {
+    content.starts_with(GZIP_MAGIC)
+}
+
+/// Decompresses gzip content and transcodes the result to UTF-8.
+pub fn decode(content: &[u8]) -> Result<Cow<'_, [u8]>, String> {
+    if !is_gzip(content) {
+        return to_utf8(content);
+    }
+    let mut decompressed = vec![];
+    GzDecoder::new(content)
+        .read_to_end(&mut decompressed)
+        .map_err(|e| format!("Failed to decompress gzip: {e}"))?;
+    Ok(Cow::Owned(to_utf8(&decompressed)?.into_owned()))
+}
+
+/// The format of tracked files, from `--input-format`.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum InputFormat {
+    /// By extension: YAML for `.yaml` and `.yml`, JSON Lines for `.ndjson` and `.jsonl`, TOML
+    /// for `.toml`, and otherwise JSON, or JSON Lines if the file holds more than one value
+    #[default]
+    Auto,
+    Json,
+    /// JSON Lines: a record per line
+    Ndjson,
+    Yaml,
+    /// TOML: a document whose only key is an array of tables is that array of records, and any
+    /// other document is a table, as used by `--object-keyed`
+    Toml,
+}
+
+impl InputFormat {
+    /// The format of the tracked file at `path`, looking past a `.gz` extension. Files whose
+    /// extension does not tell stay `auto`.
+    pub fn of(self, path: &Path) -> InputFormat {
+        if self != InputFormat::Auto {
+            return self;
+        }
+        let path = match path.extension() {
+            Some(extension) if extension == "gz" => Cow::Owned(path.with_extension("")),
+            _ => Cow::Borrowed(path),
+        };
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("yaml" | "yml") => InputFormat::Yaml,
+            Some("ndjson" | "jsonl") => InputFormat::Ndjson,
+            Some("toml") => InputFormat::Toml,
+            _ => InputFormat::Auto,
+        }
+    }
+}
+
+/// Parses JSON Lines into an array of the values on its lines, skipping blank lines.
+fn parse_ndjson(content: &[u8]) -> Result<serde_json::Value, String> {
+    content
+        .split(|&byte| byte == b'\n')
+        .enumerate()
+        .filter(|(_, line)| !line.trim_ascii().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_slice(line)
+                .map_err(|e| format!("Failed to parse json on line {}: {e}", i + 1))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(serde_json::Value::Array)
+}
+
+/// Converts a TOML value to JSON, with datetimes as their TOML text, such as
+/// `1979-05-27T07:32:00Z`. Non-finite floats, which JSON cannot hold, become null.
+fn toml_to_json(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(s) => serde_json::Value::String(s),
+        toml::Value::Integer(i) => serde_json::Value::from(i),
+        toml::Value::Float(f) => serde_json::Value::from(f),
+        toml::Value::Boolean(b) => serde_json::Value::Bool(b),
+        toml::Value::Datetime(datetime) => serde_json::Value::String(datetime.to_string()),
+        toml::Value::Array(values) => values.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => serde_json::Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Parses TOML, unwrapping a document such as `[[course]]` tables, whose only key holds an array
+/// of tables, into that array.
+fn parse_toml(content: &[u8]) -> Result<serde_json::Value, String> {
+    let content = std::str::from_utf8(content).map_err(|e| format!("Invalid UTF-8: {e}"))?;
+    let table = content
+        .parse::<toml::Table>()
+        .map_err(|e| format!("Failed to parse toml: {e}"))?;
+    let is_records = table.len() == 1
+        && table.values().all(|value| {
+            value
+                .as_array()
+                .is_some_and(|records| records.iter().all(toml::Value::is_table))
+        });
+    let document = if is_records {
+        table.into_iter().next().unwrap().1
+    } else {
+        toml::Value::Table(table)
+    };
+    Ok(toml_to_json(document))
+}
+
+/// Parses decoded content in `format` into a JSON value. Under `auto`, content with more than one
+/// top-level value is read as JSON Lines, so a file holding a single record stays an object.
+pub fn parse(content: &[u8], format: InputFormat) -> Result<serde_json::Value, String> {
+    match format {
+        InputFormat::Auto => {
+            let mut values = serde_json::Deserializer::from_slice(content)
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Failed to parse json: {e}"))?;
+            match values.len() {
+                0 => parse(content, InputFormat::Json),
+                1 => Ok(values.pop().unwrap()),
+                _ => Ok(serde_json::Value::Array(values)),
+            }
+        }
+        InputFormat::Json => {
+            serde_json::from_slice(content).map_err(|e| format!("Failed to parse json: {e}"))
+        }
+        InputFormat::Ndjson => parse_ndjson(content),
+        InputFormat::Yaml => {
+            serde_yaml::from_slice(content).map_err(|e| format!("Failed to parse yaml: {e}"))
+        }
+        InputFormat::Toml => parse_toml(content),
+    }
+}
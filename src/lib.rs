@@ -0,0 +1,47 @@
+//! DeLorean traverses the history of a git repository and records, for every record in the
+//! tracked JSON files, when it was added, removed, or modified.
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod attest;
+pub mod bigquery;
+pub mod bulk;
+pub mod calendar;
+pub mod check;
+pub mod compare;
+pub mod diff;
+#[cfg(feature = "duckdb")]
+pub mod duckdb;
+pub mod errors;
+pub mod events;
+pub mod extract;
+pub mod fixtures;
+pub mod generate;
+pub mod index;
+pub mod input;
+pub mod manifest;
+pub mod memory;
+pub mod meta;
+#[cfg(feature = "object_store")]
+pub mod object_store;
+pub mod output;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod plugin;
+pub mod predicate;
+#[cfg(feature = "proto")]
+pub mod proto;
+pub mod query;
+pub mod record;
+#[cfg(feature = "redis")]
+pub mod redis;
+pub mod rollup;
+pub mod rules;
+pub mod season;
+pub mod selftest;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod suggest;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod verify;
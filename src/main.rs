@@ -1,413 +1,497 @@
-use chrono::DateTime;
-use clap::Parser;
-use glob_match::glob_match;
-use indicatif::ProgressBar;
-use itertools::Itertools;
-use serde::{ser::SerializeSeq, Serialize};
-use std::collections::{BTreeMap, HashMap, HashSet};
-use std::fs::{self, File};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
+use delorean::check::CheckArgs;
+use delorean::errors::{self, ErrorReport, RecoverableError};
+use delorean::events::OutputTarget;
+use delorean::extract::{self, ExtractArgs};
+use delorean::generate::{self, GenerateArgs};
+#[cfg(feature = "mem_stats")]
+use delorean::memory::CountingAllocator;
+use delorean::memory::MemStats;
+use delorean::output::{OutputFormat, SplitBy};
+use delorean::{
+    attest, check, compare, index, manifest, meta, output, plugin, selftest, suggest, verify,
+};
+use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use std::vec;
+use std::process::ExitCode;
+
+// Counting every allocation has a cost, so it is only built in for `--mem-stats`.
+#[cfg(feature = "mem_stats")]
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-struct Args {
-    repo_path: String,
-    output_path: String,
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    /// Identifies records for adding, removing, and modifying
-    #[arg(long)]
-    primary_key: String,
+    #[command(flatten)]
+    extract: Option<ExtractArgs>,
+}
 
-    /// Glob pattern to match files to include in the diff.
-    #[arg(short, long, default_value = "**/*")]
-    include: String,
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Build a synthetic fixture repository, extract it, and compare against golden outputs
+    Selftest {
+        /// Directory to build the fixture and outputs in. Defaults to a temporary directory that
+        /// is removed if the selftest passes.
+        #[arg(long)]
+        work_dir: Option<PathBuf>,
+    },
+    /// Find commits that only reorder arrays, reformat numbers, or rename fields, and write them
+    /// to a file for `--ignore-revs-file`
+    SuggestIgnores {
+        #[command(flatten)]
+        extract: Box<ExtractArgs>,
 
-    /// List of commit authors (name or email). If empty, all authors are included.
-    /// Otherwise, only commits by the specified authors are included.
-    #[arg(short = 'a', long)]
-    include_authors: Vec<String>,
+        /// File to write the suggested commits to
+        #[arg(long, value_name = "FILE")]
+        write: PathBuf,
+    },
+    /// Compare the working tree or staged versions of the tracked files against HEAD and fail if
+    /// too many records would be removed or modified. Meant for a pre-commit hook.
+    Check {
+        #[command(flatten)]
+        extract: Box<ExtractArgs>,
 
-    /// List of revisions (their commit hashes) to ignore
-    #[arg(long)]
-    ignore_revs: Vec<String>,
+        #[command(flatten)]
+        check: CheckArgs,
+    },
+    /// Replay a sample of the record histories in an output directory against the repository
+    /// and report change instants that git does not bear out. Takes the options the output was
+    /// extracted with.
+    Verify {
+        #[command(flatten)]
+        extract: Box<ExtractArgs>,
 
-    /// A commit spec (e.g. HEAD~10) to stop at (exclusive)
-    #[arg(long)]
-    until: Option<String>,
+        /// Number of records to replay, spread evenly over the output
+        #[arg(long, default_value_t = 100)]
+        replay: usize,
+    },
+    /// Compare two output directories, e.g. before and after changing diff options, and list the
+    /// change instants each record gained or lost. Exits with 1 if they differ.
+    CompareOutputs {
+        /// The output directory of the first run
+        dir_a: PathBuf,
+        /// The output directory of the second run
+        dir_b: PathBuf,
+    },
+    /// Build a synthetic data repository of a given size, with renames, merges, and unparseable
+    /// files mixed in, to benchmark and validate a configuration on
+    GenerateFixture {
+        /// Directory to create the repository in
+        path: PathBuf,
 
-    /// A directory to output all removed objects
-    #[arg(long)]
-    graveyard: Option<String>,
+        #[command(flatten)]
+        generate: GenerateArgs,
+    },
+    /// Browse an output directory: its files, their records, and the timeline of each record
+    /// (requires the tui feature)
+    Tui {
+        /// The output directory to browse
+        dir: PathBuf,
+    },
 }
 
-#[derive(Debug, Serialize)]
-struct ChangeInstant {
-    commit: String,
-    #[serde(serialize_with = "serialize_timestamp")]
-    timestamp: i64,
-}
-
-#[derive(Serialize)]
-struct ChangeRecord {
-    #[serde(serialize_with = "serialize_change_instants")]
-    added: Vec<Arc<ChangeInstant>>,
-    #[serde(serialize_with = "serialize_change_instants")]
-    removed: Vec<Arc<ChangeInstant>>,
-    #[serde(serialize_with = "serialize_change_instants")]
-    modified: Vec<Arc<ChangeInstant>>,
-}
-
-fn serialize_timestamp<S: serde::Serializer>(
-    timestamp: &i64,
-    serializer: S,
-) -> Result<S::Ok, S::Error> {
-    let dt = DateTime::from_timestamp(*timestamp, 0).unwrap();
-    let s = dt.format("%+").to_string();
-    serializer.serialize_str(&s)
+fn run_selftest(work_dir: Option<PathBuf>) -> ExitCode {
+    let keep = work_dir.is_some();
+    let work_dir = work_dir.unwrap_or_else(|| {
+        std::env::temp_dir().join(format!("delorean-selftest-{}", std::process::id()))
+    });
+    match selftest::run(&work_dir) {
+        Ok(()) => {
+            if !keep {
+                fs::remove_dir_all(&work_dir).expect("Failed to remove selftest directory");
+            }
+            eprintln!("Selftest passed");
+            ExitCode::SUCCESS
+        }
+        Err(mismatches) => {
+            for mismatch in mismatches {
+                eprintln!("{mismatch}");
+            }
+            eprintln!("Selftest failed; see {}", work_dir.display());
+            ExitCode::FAILURE
+        }
+    }
 }
 
-fn serialize_change_instants<S>(
-    instants: &Vec<Arc<ChangeInstant>>,
-    serializer: S,
-) -> Result<S::Ok, S::Error>
-where
-    S: serde::Serializer,
-{
-    let mut seq = serializer.serialize_seq(Some(instants.len()))?;
-    let mut reversed = instants.clone();
-    reversed.reverse();
-    for instant in reversed {
-        seq.serialize_element(&*instant)?;
+/// Prints the errors that aborted the run, and writes them to `--warnings` along with the
+/// warnings collected before it aborted.
+fn report_abort(args: &ExtractArgs, report: &ErrorReport) {
+    eprint!("{report}");
+    if let Some(warnings_path) = &args.warnings {
+        errors::write_ndjson(warnings_path, &report.warnings, &report.errors)
+            .expect("Failed to write warnings");
+        eprintln!(
+            "{} warnings and {} errors written to {}",
+            report.warnings.len(),
+            report.errors.len(),
+            warnings_path.display()
+        );
     }
-    seq.end()
 }
 
-// TODO: return exactly what changed
-fn deep_diff_json(old_json: &serde_json::Value, new_json: &serde_json::Value) -> bool {
-    match (old_json, new_json) {
-        (serde_json::Value::Object(old_obj), serde_json::Value::Object(new_obj)) => {
-            let mut old_keys = old_obj.keys().collect::<Vec<&String>>();
-            old_keys.sort();
-            let mut new_keys = new_obj.keys().collect::<Vec<&String>>();
-            new_keys.sort();
-            if old_keys.len() != new_keys.len()
-                || old_keys.iter().zip(new_keys.iter()).any(|(a, b)| a != b)
-            {
-                return true;
-            }
-            for (key, old_val) in old_obj {
-                match new_obj.get(key) {
-                    Some(new_val) => {
-                        if deep_diff_json(old_val, new_val) {
-                            return true;
-                        }
-                    }
-                    None => return true,
-                }
-            }
+fn report_skipped(args: &ExtractArgs, warnings: &[RecoverableError], errors: &[RecoverableError]) {
+    if let Some(warnings_path) = &args.warnings {
+        errors::write_ndjson(warnings_path, warnings, errors).expect("Failed to write warnings");
+        if !warnings.is_empty() || !errors.is_empty() {
+            eprintln!(
+                "{} warnings and {} skipped errors written to {}",
+                warnings.len(),
+                errors.len(),
+                warnings_path.display()
+            );
         }
-        (serde_json::Value::Array(old_arr), serde_json::Value::Array(new_arr)) => {
-            if old_arr.len() != new_arr.len() {
-                return true;
-            }
-            for (old_val, new_val) in old_arr.iter().zip(new_arr.iter()) {
-                if deep_diff_json(old_val, new_val) {
-                    return true;
-                }
-            }
+        return;
+    }
+    if !warnings.is_empty() {
+        eprintln!("{} warnings:", warnings.len());
+        for warning in warnings {
+            eprintln!("  {warning}");
         }
-        (old_val, new_val) => {
-            if old_val != new_val {
-                return true;
-            }
+    }
+    if !errors.is_empty() {
+        eprintln!("Skipped {} errors:", errors.len());
+        for error in errors {
+            eprintln!("  {error}");
         }
     }
-    return false;
 }
 
-fn get_json_data(
-    repo: &git2::Repository,
-    tree: &git2::Tree,
-    path: &Path,
-    primary_key: &str,
-) -> HashMap<String, serde_json::Value> {
-    let tree_entry = tree.get_path(path).expect("Failed to get tree entry");
-    let object = match tree_entry.to_object(&repo) {
-        Ok(object) => object,
-        Err(_) => {
-            // Fetch object from remote
-            todo!()
+fn run_suggest_ignores(args: Box<ExtractArgs>, write: PathBuf) -> ExitCode {
+    let extraction = match suggest::run(&args) {
+        Ok(extraction) => extraction,
+        Err(report) => {
+            report_abort(&args, &report);
+            return ExitCode::FAILURE;
         }
     };
-    let blob = object.into_blob().expect("Failed to get blob");
-    let content = blob.content();
-    let content: Vec<serde_json::Value> =
-        serde_json::from_slice(content).expect("Failed to parse json");
-    let mut data: HashMap<String, serde_json::Value> = HashMap::new();
-    for record in content {
-        let primary_key_val = match &record[primary_key] {
-            serde_json::Value::String(s) => s,
-            _ => panic!("Primary key is not a string"),
-        };
-        data.insert(primary_key_val.to_string(), record);
-    }
-    data
+    // The extraction tracks and normalizes more than `args` ask for, so it is not written out.
+    suggest::write_ignore_revs(&write, &extraction).expect("Failed to write ignore-revs file");
+    eprintln!(
+        "Suggested {} commits to ignore in {}",
+        extraction.noop_commits.len(),
+        write.display()
+    );
+    report_skipped(&args, &extraction.warnings, &extraction.errors);
+    ExitCode::SUCCESS
 }
 
-enum ChangeType {
-    Added,
-    Removed,
-    Modified,
+fn run_check(args: &ExtractArgs, check_args: &CheckArgs) -> ExitCode {
+    let check = match check::run(args, check_args) {
+        Ok(check) => check,
+        Err(report) => {
+            report_abort(args, &report);
+            return ExitCode::FAILURE;
+        }
+    };
+    for file in &check.files {
+        eprintln!("{file}");
+    }
+    report_skipped(args, &check.warnings, &check.errors);
+    if check.passed() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
 }
 
-fn update_change_record_entry(
-    change_record_entry: &mut HashMap<String, ChangeRecord>,
-    primary_key: String,
-    change_instant: Arc<ChangeInstant>,
-    change_type: ChangeType,
-) -> bool {
-    let change_record = change_record_entry
-        .entry(primary_key)
-        .or_insert(ChangeRecord {
-            added: vec![],
-            removed: vec![],
-            modified: vec![],
-        });
-    match change_type {
-        ChangeType::Added => {
-            change_record.added.push(change_instant);
-        }
-        ChangeType::Removed => {
-            change_record.removed.push(change_instant);
-            return change_record.added.is_empty();
-        }
-        ChangeType::Modified => {
-            change_record.modified.push(change_instant);
+fn run_verify(args: &ExtractArgs, sample: usize) -> ExitCode {
+    if args.output_format != OutputFormat::Json
+        || args.merge_output.is_some()
+        || args.compress.is_some()
+        || args.compact_commits
+        || args.chunk_size.is_some()
+        || args.cdn_mode
+        || args.split_by != SplitBy::Path
+        || args.split_by_window.is_some()
+    {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "verify only reads uncompressed JSON output split by path; it does not support \
+                 --output-format, --merge-output, --compress, --compact-commits, --chunk-size, \
+                 --cdn-mode, --split-by, or --split-by-window",
+            )
+            .exit();
+    }
+    let verification = match verify::run(args, sample) {
+        Ok(verification) => verification,
+        Err(report) => {
+            report_abort(args, &report);
+            return ExitCode::FAILURE;
         }
+    };
+    for mismatch in &verification.mismatches {
+        eprintln!("{mismatch}");
+    }
+    eprintln!(
+        "Checked {} change instants, {} inconsistent",
+        verification.checked,
+        verification.mismatches.len()
+    );
+    report_skipped(args, &verification.warnings, &verification.errors);
+    if verification.mismatches.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
     }
-    return false;
 }
 
-fn main() {
-    let args = Args::parse();
-    let repo = git2::Repository::open(&args.repo_path).expect("Failed to open repository");
-    let mut revwalk = repo.revwalk().expect("Failed to create revwalk");
-    revwalk.push_head().unwrap();
-    let mut revwalk_count = repo.revwalk().expect("Failed to create revwalk");
-    revwalk_count.push_head().expect("Failed to push HEAD");
-    let commit_count = revwalk_count.count();
-    let progress_bar = ProgressBar::new(commit_count as u64);
-    progress_bar.println(format!("Found {} commits", commit_count));
-    let mut change_records: HashMap<PathBuf, HashMap<String, ChangeRecord>> = HashMap::new();
-    let until_commit = match &args.until {
-        Some(until) => repo
-            .revparse_single(until)
-            .expect(format!("Failed to find commit {}", until).as_str())
-            .id(),
-        None => git2::Oid::zero(),
+fn run_compare_outputs(dir_a: &Path, dir_b: &Path) -> ExitCode {
+    let comparison = match compare::compare_outputs(dir_a, dir_b) {
+        Ok(comparison) => comparison,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
     };
-    let mut cached_data: HashMap<PathBuf, HashMap<String, serde_json::Value>> = HashMap::new();
-    let mut prev_oid = git2::Oid::zero();
-    revwalk.set_sorting(git2::Sort::TIME).unwrap();
-    let mut graveyard: HashMap<PathBuf, HashMap<String, serde_json::Value>> = HashMap::new();
+    print!("{comparison}");
+    if comparison.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
 
-    for oid in revwalk {
-        let oid = oid.expect("Failed to get oid");
-        if oid != prev_oid {
-            cached_data.clear();
-        }
-        // Now cached_data is ready to be used. We've guaranteed that it contains data for the
-        // current commit (which was previously the parent). At the end of the loop, we'll
-        // overwrite it with the data from the parent.
-        let mut next_cached_data: HashMap<PathBuf, HashMap<String, serde_json::Value>> =
-            HashMap::new();
-        if args.ignore_revs.contains(&oid.to_string()) {
-            continue;
+fn run_generate_fixture(path: &Path, generate: &GenerateArgs) -> ExitCode {
+    match generate::generate(path, generate) {
+        Ok(fixture) => {
+            eprintln!(
+                "Generated {} commits in {}; extract them with\n  delorean {} <OUTPUT_PATH> \
+                 --primary-key {} --include '{}'{}",
+                generate.commits + 1,
+                fixture.path().display(),
+                fixture.path().display(),
+                generate::PRIMARY_KEY,
+                generate::INCLUDE,
+                if generate.corrupt_every > 0 {
+                    " --max-errors <N>"
+                } else {
+                    ""
+                },
+            );
+            ExitCode::SUCCESS
         }
-        if oid == until_commit {
-            progress_bar.println("Reached until commit");
-            break;
+        Err(e) => {
+            eprintln!("Failed to generate fixture: {e}");
+            ExitCode::FAILURE
         }
-        let commit = repo
-            .find_commit(oid)
-            .expect(format!("Failed to find commit {oid}").as_str());
-        if !args.include_authors.is_empty()
-            && !args
-                .include_authors
-                .contains(&commit.author().name().unwrap().to_string())
-            && !args
-                .include_authors
-                .contains(&commit.author().email().unwrap().to_string())
-        {
-            continue;
+    }
+}
+
+#[cfg(feature = "tui")]
+fn run_tui(dir: &Path) -> ExitCode {
+    match delorean::tui::run(dir) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Failed to run the explorer: {e}");
+            ExitCode::FAILURE
         }
-        let parent_commit = match commit.parent(0) {
-            Ok(parent) => parent,
-            Err(_) => {
-                progress_bar.println(format!("Commit {} with no parent", commit.id()));
-                break;
+    }
+}
+
+#[cfg(not(feature = "tui"))]
+fn run_tui(_dir: &Path) -> ExitCode {
+    Cli::command()
+        .error(ErrorKind::InvalidSubcommand, "tui requires the tui feature")
+        .exit()
+}
+
+/// Runs the plugin named by the first argument, if it is neither an option, a built-in
+/// subcommand, nor an existing path, and a `delorean-<name>` executable is on `PATH`.
+fn run_plugin() -> Option<ExitCode> {
+    let arguments = std::env::args_os().collect::<Vec<_>>();
+    let name = arguments.get(1)?.to_str()?;
+    if name.starts_with('-')
+        || Cli::command().find_subcommand(name).is_some()
+        || Path::new(name).exists()
+    {
+        return None;
+    }
+    let mut command = plugin::command(name).ok()?;
+    let status = command
+        .args(&arguments[2..])
+        .status()
+        .unwrap_or_else(|e| panic!("Failed to run {}{name}: {e}", plugin::PREFIX));
+    Some(match status.code() {
+        Some(code) => ExitCode::from(code as u8),
+        None => ExitCode::FAILURE,
+    })
+}
+
+fn main() -> ExitCode {
+    if let Some(exit_code) = run_plugin() {
+        return exit_code;
+    }
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Selftest { work_dir }) => run_selftest(work_dir),
+        Some(Command::SuggestIgnores { extract, write }) => run_suggest_ignores(extract, write),
+        Some(Command::Check { extract, check }) => run_check(&extract, &check),
+        Some(Command::Verify { extract, replay }) => run_verify(&extract, replay),
+        Some(Command::CompareOutputs { dir_a, dir_b }) => run_compare_outputs(&dir_a, &dir_b),
+        Some(Command::GenerateFixture { path, generate }) => run_generate_fixture(&path, &generate),
+        Some(Command::Tui { dir }) => run_tui(&dir),
+        None => {
+            #[cfg_attr(not(feature = "object_store"), allow(unused_mut))]
+            let mut args = cli.extract.expect("Extraction arguments are required");
+            if args.output_path.is_none()
+                && args.output.is_none()
+                && args.redis.is_none()
+                && args.event_log.is_none()
+            {
+                Cli::command()
+                    .error(
+                        ErrorKind::MissingRequiredArgument,
+                        "either <OUTPUT_PATH>, --output, --redis, or --event-log is required",
+                    )
+                    .exit();
             }
-        };
-        progress_bar.println(format!(
-            "Diffing {} '{}' with {} '{}'",
-            parent_commit.id(),
-            parent_commit.message().unwrap().trim(),
-            commit.id(),
-            commit.message().unwrap().trim(),
-        ));
-        let parent_tree = &parent_commit.tree().expect("Failed to get parent tree");
-        let commit_tree = &commit.tree().expect("Failed to get commit tree");
-        let diff = repo
-            .diff_tree_to_tree(Some(parent_tree), Some(commit_tree), None)
-            .unwrap();
-        let changed_files = diff.deltas();
-        progress_bar.println(format!("Changed {} files", changed_files.len()));
-        for delta in changed_files {
-            let old_path = delta.old_file().path().unwrap();
-            let new_path = delta.new_file().path().unwrap();
-            if old_path != new_path {
-                panic!(
-                    "Old path {} does not match new path {}",
-                    old_path.to_string_lossy(),
-                    new_path.to_string_lossy()
-                );
+            if args.output_format != OutputFormat::Json
+                && (args.rollup.is_some()
+                    || args.cdn_mode
+                    || args.merge_output.is_some()
+                    || args.compress.is_some()
+                    || args.chunk_size.is_some()
+                    || args.split_by != SplitBy::Path
+                    || args.split_by_window.is_some()
+                    || args.record_manifest.is_some()
+                    || args.append
+                    || args.write_manifest
+                    || args.write_index)
+            {
+                Cli::command()
+                    .error(
+                        ErrorKind::ArgumentConflict,
+                        "--rollup, --cdn-mode, --merge-output, --compress, --chunk-size, \
+                         --split-by, --split-by-window, --record-manifest, --append, \
+                         --write-manifest, and --write-index require --output-format json",
+                    )
+                    .exit();
             }
-            if !glob_match(args.include.as_str(), old_path.to_str().unwrap()) {
-                continue;
+            if args.output_path.as_deref() == Some("-")
+                && (!matches!(
+                    args.output_format,
+                    OutputFormat::Json
+                        | OutputFormat::Csv
+                        | OutputFormat::Msgpack
+                        | OutputFormat::Elasticsearch
+                ) || args.cdn_mode
+                    || args.chunk_size.is_some()
+                    || args.record_manifest.is_some()
+                    || args.write_meta
+                    || args.append
+                    || args.write_manifest
+                    || args.write_index
+                    || args.split_by != SplitBy::Path
+                    || args.split_by_window.is_some()
+                    || args.recent_first_flush.is_some()
+                    || (args.compact_commits && args.output_format == OutputFormat::Json)
+                    || [&args.output, &args.rules_output]
+                        .iter()
+                        .any(|target| matches!(target, Some(OutputTarget::Stdout))))
+            {
+                Cli::command()
+                    .error(
+                        ErrorKind::ArgumentConflict,
+                        "- as <OUTPUT_PATH> requires --output-format json, csv, msgpack, or \
+                         elasticsearch, and does not support --cdn-mode, --chunk-size, \
+                         --record-manifest, --write-meta, --split-by, --split-by-window, \
+                         --append, --write-manifest, --write-index, --recent-first-flush, \
+                         --compact-commits with json, or - as --output or --rules-output",
+                    )
+                    .exit();
             }
-            progress_bar.println(format!("Diffing: {}", old_path.to_string_lossy()));
-            let change_instant = Arc::new(ChangeInstant {
-                commit: commit.id().to_string(),
-                timestamp: commit.time().seconds(),
-            });
-            let change_record_entry = change_records
-                .entry(new_path.to_path_buf())
-                .or_insert(HashMap::new());
-            let graveyard_entry = graveyard.entry(new_path.to_path_buf()).or_insert(HashMap::new());
-            match &delta.status() {
-                git2::Delta::Added => {
-                    let new_content = cached_data
-                        .remove(new_path)
-                        .or_else(|| {
-                            Some(get_json_data(
-                                &repo,
-                                &commit_tree,
-                                new_path,
-                                &args.primary_key,
-                            ))
-                        })
-                        .unwrap();
-                    for pk in new_content.keys() {
-                        update_change_record_entry(
-                            change_record_entry,
-                            pk.to_string(),
-                            change_instant.clone(),
-                            ChangeType::Added,
-                        );
-                    }
-                }
-                git2::Delta::Deleted => {
-                    let old_content =
-                        get_json_data(&repo, &parent_tree, old_path, &args.primary_key);
-                    for pk in old_content.keys() {
-                        let should_graveyard = update_change_record_entry(
-                            change_record_entry,
-                            pk.to_string(),
-                            change_instant.clone(),
-                            ChangeType::Removed,
-                        );
-                        if should_graveyard && args.graveyard.is_some() {
-                            graveyard_entry.insert(pk.to_string(), old_content.get(pk).unwrap().clone());
-                        }
-                    }
-                    next_cached_data.insert(old_path.to_path_buf(), old_content);
-                }
-                git2::Delta::Modified => {
-                    let new_content = cached_data
-                        .remove(new_path)
-                        .or_else(|| {
-                            Some(get_json_data(
-                                &repo,
-                                &commit_tree,
-                                new_path,
-                                &args.primary_key,
-                            ))
-                        })
-                        .unwrap();
-                    let old_content =
-                        get_json_data(&repo, &parent_tree, old_path, &args.primary_key);
-                    let mut unseen_new_pks: HashSet<String> =
-                        new_content.keys().map(|s| s.clone()).collect();
-                    for (pk, old_val) in &old_content {
-                        unseen_new_pks.remove(pk);
-                        let new_val = match new_content.get(pk) {
-                            Some(new_val) => new_val,
-                            None => {
-                                let should_graveyard = update_change_record_entry(
-                                    change_record_entry,
-                                    pk.to_string(),
-                                    change_instant.clone(),
-                                    ChangeType::Removed,
-                                );
-                                if should_graveyard && args.graveyard.is_some() {
-                                    graveyard_entry.insert(
-                                        pk.to_string(),
-                                        old_content.get(pk).unwrap().clone(),
-                                    );
-                                }
-                                continue;
-                            }
-                        };
-                        if !deep_diff_json(&old_val, &new_val) {
-                            continue;
-                        }
-                        update_change_record_entry(
-                            change_record_entry,
-                            pk.to_string(),
-                            change_instant.clone(),
-                            ChangeType::Modified,
-                        );
-                    }
-                    for pk in unseen_new_pks {
-                        update_change_record_entry(
-                            change_record_entry,
-                            pk,
-                            change_instant.clone(),
-                            ChangeType::Added,
-                        );
-                    }
+            if (args.path_template.is_some() || args.body_template.is_some())
+                && args.split_by != SplitBy::Record
+            {
+                Cli::command()
+                    .error(
+                        ErrorKind::MissingRequiredArgument,
+                        "--path-template and --body-template require --split-by record",
+                    )
+                    .exit();
+            }
+            if (args.bulk_index.is_some() || args.bulk_id.is_some())
+                && args.output_format != OutputFormat::Elasticsearch
+            {
+                Cli::command()
+                    .error(
+                        ErrorKind::ArgumentConflict,
+                        "--bulk-index and --bulk-id require --output-format elasticsearch",
+                    )
+                    .exit();
+            }
+            if args.compact_commits
+                && !matches!(
+                    args.output_format,
+                    OutputFormat::Json | OutputFormat::Msgpack
+                )
+            {
+                Cli::command()
+                    .error(
+                        ErrorKind::ArgumentConflict,
+                        "--compact-commits requires --output-format json or msgpack",
+                    )
+                    .exit();
+            }
+            if args.output_path.as_deref().is_some_and(output::is_url)
+                && !cfg!(feature = "object_store")
+            {
+                Cli::command()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        "object store URLs as <OUTPUT_PATH> require the object_store feature",
+                    )
+                    .exit();
+            }
+            if args.redis.is_some() && !cfg!(feature = "redis") {
+                Cli::command()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        "--redis requires the redis feature",
+                    )
+                    .exit();
+            }
+            if args.mem_stats && !cfg!(feature = "mem_stats") {
+                Cli::command()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        "--mem-stats requires the mem_stats feature",
+                    )
+                    .exit();
+            }
+            #[cfg(feature = "object_store")]
+            let upload = delorean::object_store::Upload::stage(&mut args)
+                .expect("Failed to open object store");
+            let mut mem_stats = MemStats::default();
+            let extraction = match mem_stats.stage("extract", || {
+                extract::run_with_flush(&args, |recent| output::write(&args, recent))
+            }) {
+                Ok(extraction) => extraction,
+                Err(report) => {
+                    report_abort(&args, &report);
+                    return ExitCode::FAILURE;
                 }
-                _ => panic!("Unknown delta type {:?}", delta.status()),
+            };
+            mem_stats.stage("output", || output::write(&args, &extraction));
+            meta::write(&args, std::env::args().collect()).expect("Failed to write metadata");
+            manifest::write(&args, &extraction).expect("Failed to write manifest");
+            index::write(&args, &extraction).expect("Failed to write index");
+            attest::write(&args, std::env::args().collect()).expect("Failed to write attestation");
+            #[cfg(feature = "object_store")]
+            if let Some(upload) = upload {
+                upload.finish().expect("Failed to upload output");
             }
-        }
-        progress_bar.inc(1);
-        cached_data = next_cached_data;
-        prev_oid = oid;
-    }
-    progress_bar.finish();
-    for (path, change_record) in change_records {
-        let output_path = Path::join(Path::new(&args.output_path), &path);
-        fs::create_dir_all(output_path.parent().unwrap()).expect("Failed to create directory");
-        let file = File::create(output_path).unwrap();
-        let sorted_map = change_record
-            .iter()
-            .sorted_by_key(|v| v.0)
-            .collect::<BTreeMap<_, _>>();
-        serde_json::to_writer_pretty(file, &sorted_map).expect("Failed to write json");
-    }
-    if let Some(graveyard_path) = args.graveyard {
-        let graveyard_path = Path::new(&graveyard_path);
-        for (path, graveyard_entry) in graveyard {
-            let output_path = Path::join(graveyard_path, &path);
-            fs::create_dir_all(output_path.parent().unwrap()).expect("Failed to create directory");
-            let file = File::create(output_path).unwrap();
-            let sorted_map = graveyard_entry
-                .iter()
-                .sorted_by_key(|v| v.0)
-                .collect::<BTreeMap<_, _>>();
-            serde_json::to_writer_pretty(file, &sorted_map).expect("Failed to write json");
+            report_skipped(&args, &extraction.warnings, &extraction.errors);
+            if args.mem_stats {
+                eprint!("{mem_stats}");
+            }
+            ExitCode::SUCCESS
         }
     }
 }
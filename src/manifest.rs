@@ -0,0 +1,105 @@
+//! Output manifest (`--write-manifest`): every file of the output directory with its record
+//! count, the commits of its oldest and newest changes, and a hash of its content, so that
+//! consumers mirroring the output (e.g. to a CDN) can tell which files changed between runs
+//! without reading them.
+//!
+//! The manifest is `<OUTPUT_PATH>/manifest.json`, keyed by each file's path in the directory:
+//!
+//! ```json
+//! {
+//!   "files": {
+//!     "parsed_courses/202301.json": {
+//!       "records": 3,
+//!       "first_commit": "<commit hash>",
+//!       "last_commit": "<commit hash>",
+//!       "hash": "<git blob hash>"
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Hashes are git blob ids (SHA-1), as `git hash-object` computes them. A file without changes
+//! has no `first_commit` or `last_commit`.
+
+use crate::attest::blob_hash;
+use crate::extract::{ExtractArgs, Extraction};
+use crate::output::{self, extended_length, output_file_path, with_suffix};
+use crate::record::{ChangeInstant, ChangeRecord};
+use itertools::{Itertools, MinMaxResult};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the manifest file in the output directory.
+pub const FILE_NAME: &str = "manifest.json";
+
+#[derive(Serialize)]
+struct FileEntry {
+    records: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_commit: Option<String>,
+    hash: String,
+}
+
+#[derive(Serialize)]
+struct Manifest {
+    files: BTreeMap<PathBuf, FileEntry>,
+}
+
+/// The file of the tracked `path` in the output directory, relative to it.
+pub(crate) fn output_file(args: &ExtractArgs, path: &Path) -> PathBuf {
+    let file = output_file_path(Path::new(""), path);
+    match args.compress {
+        Some(compression) => with_suffix(&file, compression.extension()),
+        None => file,
+    }
+}
+
+/// The instants of the oldest and newest of the changes to `records`, if there are any.
+pub(crate) fn change_range<'a>(
+    records: impl IntoIterator<Item = &'a ChangeRecord>,
+) -> Option<(&'a ChangeInstant, &'a ChangeInstant)> {
+    let instants = records
+        .into_iter()
+        .flat_map(|record| [&record.added, &record.removed, &record.modified])
+        .flatten()
+        .map(|change| change.instant.as_ref());
+    match instants.minmax_by_key(|instant| instant.timestamp) {
+        MinMaxResult::NoElements => None,
+        MinMaxResult::OneElement(instant) => Some((instant, instant)),
+        MinMaxResult::MinMax(first, last) => Some((first, last)),
+    }
+}
+
+/// Writes the manifest of the output directory written for `extraction`, if `--write-manifest`
+/// is set.
+pub fn write(args: &ExtractArgs, extraction: &Extraction) -> io::Result<()> {
+    let Some(output_path) = args.output_path.as_ref().filter(|_| args.write_manifest) else {
+        return Ok(());
+    };
+    let output_dir = extended_length(Path::new(output_path));
+    let mut files = BTreeMap::new();
+    for (path, records) in &extraction.change_records {
+        let file = output_file(args, path);
+        let (first_commit, last_commit) = change_range(records.values())
+            .map(|(first, last)| (first.commit.clone(), last.commit.clone()))
+            .unzip();
+        let entry = FileEntry {
+            records: records.len(),
+            first_commit,
+            last_commit,
+            hash: blob_hash(&fs::read(output_dir.join(&file))?),
+        };
+        files.insert(file, entry);
+    }
+    let manifest = Manifest { files };
+    output::write_if_changed(
+        &output_dir.join(FILE_NAME),
+        &serde_json::to_vec_pretty(&manifest)?,
+    );
+    Ok(())
+}
@@ -0,0 +1,145 @@
+//! Memory accounting for `--mem-stats`: a global allocator that counts allocations, and the peak
+//! resident set size reported by the OS. The `delorean` binary installs [`CountingAllocator`] when
+//! built with the `mem_stats` feature, since counting slows down every allocation; embedders who
+//! want the counts install it in their own binary, and otherwise see zeros.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator, counting every allocation and the bytes live at any time.
+pub struct CountingAllocator;
+
+fn allocated(size: usize) {
+    let live = LIVE_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK_BYTES.fetch_max(live, Ordering::Relaxed);
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED_BYTES.fetch_add(size, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+            allocated(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// Allocation counts of one stage of a run.
+#[derive(Debug, Clone)]
+pub struct Stage {
+    pub name: &'static str,
+    pub allocations: usize,
+    pub allocated_bytes: usize,
+    /// Most bytes live at once during the stage
+    pub peak_bytes: usize,
+    /// Bytes live at the end of the stage
+    pub live_bytes: usize,
+}
+
+/// Memory use of a run, stage by stage.
+#[derive(Debug, Clone, Default)]
+pub struct MemStats {
+    pub stages: Vec<Stage>,
+}
+
+impl MemStats {
+    /// Runs `f` as the stage `name`, recording its allocations.
+    pub fn stage<T>(&mut self, name: &'static str, f: impl FnOnce() -> T) -> T {
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+        let allocated_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+        PEAK_BYTES.store(LIVE_BYTES.load(Ordering::Relaxed), Ordering::Relaxed);
+        let result = f();
+        self.stages.push(Stage {
+            name,
+            allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+            allocated_bytes: ALLOCATED_BYTES.load(Ordering::Relaxed) - allocated_bytes,
+            peak_bytes: PEAK_BYTES.load(Ordering::Relaxed),
+            live_bytes: LIVE_BYTES.load(Ordering::Relaxed),
+        });
+        result
+    }
+}
+
+/// The peak resident set size of this process in bytes, where the OS reports it (Linux).
+pub fn peak_rss() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kilobytes = line
+        .trim_start_matches("VmHWM:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+fn mebibytes(bytes: impl Into<u64>) -> f64 {
+    bytes.into() as f64 / (1024.0 * 1024.0)
+}
+
+impl fmt::Display for MemStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match peak_rss() {
+            Some(rss) => writeln!(f, "Peak RSS: {:.1} MiB", mebibytes(rss))?,
+            None => writeln!(f, "Peak RSS: unknown")?,
+        }
+        let allocations = self
+            .stages
+            .iter()
+            .map(|stage| stage.allocations)
+            .sum::<usize>();
+        let allocated_bytes = self
+            .stages
+            .iter()
+            .map(|stage| stage.allocated_bytes)
+            .sum::<usize>();
+        writeln!(
+            f,
+            "Allocations: {allocations} ({:.1} MiB)",
+            mebibytes(allocated_bytes as u64)
+        )?;
+        for stage in &self.stages {
+            writeln!(
+                f,
+                "  {}: {} allocations ({:.1} MiB), peak {:.1} MiB, {:.1} MiB live after",
+                stage.name,
+                stage.allocations,
+                mebibytes(stage.allocated_bytes as u64),
+                mebibytes(stage.peak_bytes as u64),
+                mebibytes(stage.live_bytes as u64)
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+//! Output metadata (`--write-meta`): the tool version, output schema version, command line,
+//! repository HEAD, generation time, and change order of a run, written next to its output so
+//! that consumers can detect outputs they do not understand instead of misreading them.
+//!
+//! For the directory output the metadata is `<OUTPUT_PATH>/_meta.json`, which `verify` and
+//! `compare` skip; single-file outputs get `<OUTPUT_PATH>.meta.json`.
+//!
+//! ```json
+//! {
+//!   "tool": {"name": "delorean", "version": "0.1.0"},
+//!   "schema_version": 1,
+//!   "arguments": ["delorean", "repo", "output", "--primary-key", "crn"],
+//!   "head": "<commit hash>",
+//!   "generated_at": "2024-01-01T00:00:00Z",
+//!   "order": "asc"
+//! }
+//! ```
+
+use crate::attest::{head_commit, Tool};
+use crate::extract::ExtractArgs;
+use crate::output::OutputFormat;
+use crate::record::ChangeOrder;
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Version of the layout of the output files. Incremented whenever a change to it could break
+/// consumers, such as renaming or removing a field.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Name of the metadata file in the output directory.
+pub const FILE_NAME: &str = "_meta.json";
+
+#[derive(Serialize)]
+struct Meta {
+    tool: Tool,
+    schema_version: u32,
+    /// The command line of the run, including the program name
+    arguments: Vec<String>,
+    /// The commit at HEAD of the walked repository
+    head: String,
+    generated_at: String,
+    /// `--order` of the changes of each record
+    order: ChangeOrder,
+}
+
+/// Where the metadata of a run with `args` is written, if `--write-meta` is set.
+pub fn path(args: &ExtractArgs) -> Option<PathBuf> {
+    let output_path = args.output_path.as_ref().filter(|_| args.write_meta)?;
+    Some(match args.output_format {
+        OutputFormat::Json => PathBuf::from(output_path).join(FILE_NAME),
+        _ => {
+            let mut path = OsString::from(output_path);
+            path.push(".meta.json");
+            PathBuf::from(path)
+        }
+    })
+}
+
+/// Writes the metadata of a finished run with `args`, invoked as `arguments`.
+pub fn write(args: &ExtractArgs, arguments: Vec<String>) -> io::Result<()> {
+    let Some(meta_path) = path(args) else {
+        return Ok(());
+    };
+    let meta = Meta {
+        tool: Tool::CURRENT,
+        schema_version: SCHEMA_VERSION,
+        arguments,
+        head: head_commit(&args.repo_path)?,
+        generated_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        order: args.order,
+    };
+    fs::write(meta_path, serde_json::to_vec_pretty(&meta)?)
+}
@@ -0,0 +1,96 @@
+//! Output to object storage: an `<OUTPUT_PATH>` such as `s3://bucket/prefix`, `gs://bucket/prefix`,
+//! or `az://container/prefix` is written to a local staging directory as usual, then uploaded
+//! under the URL. Built with the `object_store` feature.
+//!
+//! Credentials and other settings of the store are read from the environment, e.g.
+//! `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`, and `AWS_REGION` for S3,
+//! `GOOGLE_SERVICE_ACCOUNT` for GCS, and `AZURE_STORAGE_ACCOUNT_NAME` and
+//! `AZURE_STORAGE_ACCOUNT_KEY` for Azure.
+
+use crate::extract::ExtractArgs;
+use crate::output;
+use ::object_store::path::{Path as ObjectPath, PathPart};
+use ::object_store::{ObjectStore, ObjectStoreExt, PutPayload};
+use std::fs;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// An output that is staged locally and uploaded to an object store once written.
+pub struct Upload {
+    store: Box<dyn ObjectStore>,
+    /// Where the staging directory is uploaded to
+    prefix: ObjectPath,
+    staging_dir: PathBuf,
+}
+
+impl Upload {
+    /// If the output path of `args` is an object store URL, points it at a fresh local staging
+    /// directory instead, keeping its last segment so that files written next to it (such as
+    /// `--write-meta`) are uploaded next to it too.
+    pub fn stage(args: &mut ExtractArgs) -> ::object_store::Result<Option<Self>> {
+        let Some(output_path) = args
+            .output_path
+            .as_deref()
+            .filter(|path| output::is_url(path))
+        else {
+            return Ok(None);
+        };
+        let url = Url::parse(output_path).map_err(|e| ::object_store::Error::Generic {
+            store: "url",
+            source: Box::new(e),
+        })?;
+        let (store, path) = ::object_store::parse_url_opts(&url, std::env::vars())?;
+        let mut parts = path.parts().collect::<Vec<_>>();
+        let name = parts.pop();
+        let staging_dir =
+            std::env::temp_dir().join(format!("delorean-upload-{}", std::process::id()));
+        if staging_dir.exists() {
+            fs::remove_dir_all(&staging_dir).expect("Failed to remove staging directory");
+        }
+        fs::create_dir_all(&staging_dir).expect("Failed to create staging directory");
+        let local_path = match &name {
+            Some(name) => staging_dir.join(name.as_ref()),
+            None => staging_dir.clone(),
+        };
+        args.output_path = Some(local_path.to_string_lossy().into_owned());
+        Ok(Some(Upload {
+            store,
+            prefix: ObjectPath::from_iter(parts),
+            staging_dir,
+        }))
+    }
+
+    /// Uploads every file of the staging directory, then removes it.
+    pub fn finish(self) -> ::object_store::Result<()> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to start runtime");
+        let mut files = vec![];
+        list_files(&self.staging_dir, &mut files);
+        runtime.block_on(async {
+            for file in &files {
+                let relative = file.strip_prefix(&self.staging_dir).unwrap();
+                let location = relative.iter().fold(self.prefix.clone(), |location, part| {
+                    location.join(PathPart::from(part.to_str().unwrap()))
+                });
+                let content = fs::read(file).expect("Failed to read staged output");
+                self.store.put(&location, PutPayload::from(content)).await?;
+            }
+            Ok::<_, ::object_store::Error>(())
+        })?;
+        fs::remove_dir_all(&self.staging_dir).expect("Failed to remove staging directory");
+        Ok(())
+    }
+}
+
+fn list_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).expect("Failed to read staging directory") {
+        let path = entry.expect("Failed to read staging directory").path();
+        if path.is_dir() {
+            list_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
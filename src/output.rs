@@ -0,0 +1,1488 @@
+use crate::bigquery;
+use crate::bulk;
+use crate::calendar;
+use crate::extract::{ExtractArgs, Extraction, NoopCommit};
+use crate::manifest;
+use crate::record::{
+    Change, ChangeInstant, ChangeOrder, ChangeRecord, ChangeType, CommitMeta, FormattedTimestamp,
+};
+use crate::rollup::{self, ChangeCounts, RollupPeriod};
+use crate::season;
+use chrono::DateTime;
+use handlebars::{handlebars_helper, Handlebars};
+use itertools::Itertools;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+/// How the change records are written to `<OUTPUT_PATH>`.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// A directory with a pretty-printed JSON file per tracked file
+    #[default]
+    Json,
+    /// A single CSV file with a row per change
+    Csv,
+    /// A single MessagePack file with the change records of every path, as in
+    /// `--merge-output json`
+    Msgpack,
+    /// A single Elasticsearch/OpenSearch bulk API file with an index action and a document per
+    /// change
+    Elasticsearch,
+    /// A single newline-delimited JSON file with a flat row per change, for BigQuery, and its
+    /// table schema
+    Bigquery,
+    /// A single Arrow IPC (Feather) file with a row per change
+    #[cfg(feature = "arrow")]
+    Arrow,
+    /// A single Parquet file with a row per change
+    #[cfg(feature = "parquet")]
+    Parquet,
+    /// A single Protocol Buffers `Extraction` message, as defined in `proto/delorean.proto`
+    #[cfg(feature = "proto")]
+    Proto,
+    /// A single DuckDB database with a row per change
+    #[cfg(feature = "duckdb")]
+    Duckdb,
+    /// A single SQLite database with tables for files, records, and changes
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+/// Layout of the single file written by `--merge-output`.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeFormat {
+    /// One pretty-printed JSON object keyed by path, then by primary key
+    Json,
+    /// One line per path: `{"path": ..., "records": {...}}`
+    Ndjson,
+}
+
+/// What each file of the output directory holds, with `--split-by`.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SplitBy {
+    /// The records of a tracked path, keyed by primary key
+    #[default]
+    Path,
+    /// The history of a single record
+    Record,
+}
+
+/// The time windows of `--split-by-window`, in UTC.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitWindow {
+    /// Files like 2019.json
+    Year,
+    /// Files like 2019-09.json
+    Month,
+}
+
+impl SplitWindow {
+    /// The window a timestamp falls into.
+    pub fn window(self, timestamp: i64) -> String {
+        let period = match self {
+            SplitWindow::Year => RollupPeriod::Yearly,
+            SplitWindow::Month => RollupPeriod::Monthly,
+        };
+        period.bucket(timestamp)
+    }
+}
+
+/// Compression of the files written by `--compress`.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// The suffix appended to the names of compressed files.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => ".gz",
+            Compression::Zstd => ".zst",
+        }
+    }
+
+    /// Compresses `content` at the default level. The output only depends on `content`, so
+    /// unchanged files stay unchanged.
+    pub fn compress(self, content: &[u8]) -> Vec<u8> {
+        match self {
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+                encoder
+                    .write_all(content)
+                    .and_then(|()| encoder.finish())
+                    .expect("Failed to compress output")
+            }
+            Compression::Zstd => zstd::encode_all(content, 0).expect("Failed to compress output"),
+        }
+    }
+}
+
+/// Writes `content` to `path` with [`write_if_changed`], compressed and with the compression's
+/// extension appended if `compression` is given. Returns the path written.
+fn write_compressed(path: &Path, content: &[u8], compression: Option<Compression>) -> PathBuf {
+    match compression {
+        Some(compression) => {
+            let path = with_suffix(path, compression.extension());
+            write_if_changed(&path, &compression.compress(content));
+            path
+        }
+        None => {
+            write_if_changed(path, content);
+            path.to_path_buf()
+        }
+    }
+}
+
+/// One change, flattened for tabular outputs.
+pub struct ChangeRow<'a> {
+    pub path: &'a Path,
+    pub primary_key: &'a str,
+    pub change_type: ChangeType,
+    pub commit: &'a str,
+    pub timestamp: i64,
+    /// The change's ID, under `--change-ids`
+    pub id: Option<&'a str>,
+    /// The change itself, for outputs that write more than the columns above
+    pub change: &'a Change,
+}
+
+/// Every change in `extraction`, ordered by path, key, and change type, with each record's
+/// changes of a type oldest first.
+pub fn change_rows(extraction: &Extraction) -> impl Iterator<Item = ChangeRow<'_>> {
+    extraction
+        .change_records
+        .iter()
+        .sorted_by(|(a, _), (b, _)| a.cmp(b))
+        .flat_map(|(path, records)| {
+            records
+                .iter()
+                .sorted_by(|(a, _), (b, _)| a.cmp(b))
+                .flat_map(move |(primary_key, record)| {
+                    [
+                        (ChangeType::Added, &record.added),
+                        (ChangeType::Removed, &record.removed),
+                        (ChangeType::Modified, &record.modified),
+                    ]
+                    .into_iter()
+                    .flat_map(move |(change_type, changes)| {
+                        changes.iter().rev().map(move |change| ChangeRow {
+                            path,
+                            primary_key,
+                            change_type,
+                            commit: &change.instant.commit,
+                            timestamp: change.instant.timestamp,
+                            id: change.id.as_deref(),
+                            change,
+                        })
+                    })
+                })
+        })
+}
+
+/// A line of the `--event-log`.
+#[derive(Serialize)]
+struct LoggedEvent<'a> {
+    timestamp: FormattedTimestamp<'a>,
+    commit: &'a str,
+    path: &'a Path,
+    pk: &'a str,
+    #[serde(rename = "type")]
+    change_type: ChangeType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<&'a str>,
+}
+
+/// Writes every change to `path` as a line of JSON, oldest first. Changes at the same time keep
+/// the order of [`change_rows`].
+fn write_event_log(path: &Path, extraction: &Extraction) -> std::io::Result<()> {
+    let mut writer = std::io::BufWriter::new(fs::File::create(path)?);
+    for row in change_rows(extraction).sorted_by_key(|row| row.timestamp) {
+        let event = LoggedEvent {
+            timestamp: FormattedTimestamp(row.timestamp, &row.change.instant.timestamp_format),
+            commit: row.commit,
+            path: row.path,
+            pk: row.primary_key,
+            change_type: row.change_type,
+            id: row.id,
+        };
+        serde_json::to_writer(&mut writer, &event)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+/// Writes every change as a CSV row with the columns `path`, `primary_key`, `change_type`,
+/// `commit`, and `iso_timestamp`, followed by `id` under `--change-ids`, in the order of
+/// [`change_rows`].
+fn write_csv<W: Write>(
+    mut writer: csv::Writer<W>,
+    extraction: &Extraction,
+    change_ids: bool,
+) -> csv::Result<()> {
+    let columns = [
+        "path",
+        "primary_key",
+        "change_type",
+        "commit",
+        "iso_timestamp",
+        "id",
+    ];
+    let column_count = if change_ids { 6 } else { 5 };
+    writer.write_record(&columns[..column_count])?;
+    for row in change_rows(extraction) {
+        let timestamp = DateTime::from_timestamp(row.timestamp, 0).unwrap();
+        let fields = [
+            &row.path.to_string_lossy(),
+            row.primary_key,
+            &row.change_type.to_string(),
+            row.commit,
+            &timestamp.format("%+").to_string(),
+            row.id.unwrap_or_default(),
+        ];
+        writer.write_record(&fields[..column_count])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the change records of every tracked path to `path` as MessagePack, in the layout of
+/// `--merge-output json`: a map from each path's output path to its records by primary key.
+/// Structs are maps with their field names, so the file converts back to that JSON as is.
+fn write_msgpack(path: &Path, extraction: &Extraction, compact_commits: bool) {
+    fs::write(path, msgpack_content(extraction, compact_commits)).expect("Failed to write file");
+}
+
+fn msgpack_content(extraction: &Extraction, compact_commits: bool) -> Vec<u8> {
+    if compact_commits {
+        let table = InstantTable::new(extraction.change_records.values().flat_map(HashMap::values));
+        let records = extraction
+            .change_records
+            .iter()
+            .map(|(path, entry)| (output_file_path(Path::new(""), path), table.compact(entry)))
+            .collect::<BTreeMap<_, _>>();
+        rmp_serde::to_vec_named(&table.output(records))
+    } else {
+        rmp_serde::to_vec_named(&merge_entries(&extraction.change_records))
+    }
+    .expect("Failed to write MessagePack")
+}
+
+/// Whether `output_path` is the URL of an object store, such as `s3://bucket/prefix`, rather than
+/// a local path.
+pub fn is_url(output_path: &str) -> bool {
+    output_path.contains("://")
+}
+
+/// The output file for a tracked path. Compressed inputs are written uncompressed, so their
+/// `.gz` extension is dropped. The path is joined a component at a time, so that git's `/`
+/// separators become native ones, and on Windows each component is made a valid file name.
+pub fn output_file_path(dir: &Path, path: &Path) -> PathBuf {
+    let path = match path.extension() {
+        Some(extension) if extension == "gz" => path.with_extension(""),
+        _ => path.to_path_buf(),
+    };
+    let mut output_path = dir.to_path_buf();
+    for component in path.components() {
+        let name = component.as_os_str();
+        if cfg!(windows) {
+            output_path.push(windows_file_name(&name.to_string_lossy()));
+        } else {
+            output_path.push(name);
+        }
+    }
+    output_path
+}
+
+/// `name` as a valid Windows file name: characters Windows does not allow in names (`<>:"/\|?*`
+/// and control characters), trailing dots and spaces, and the last character of device names
+/// such as `CON` or `nul.json` are percent-encoded, as is `%` itself so that distinct names stay
+/// distinct.
+pub fn windows_file_name(name: &str) -> String {
+    const DEVICE_NAMES: [&str; 22] = [
+        "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+        "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+    ];
+    let stem_end = name.find('.').unwrap_or(name.len());
+    let is_device = DEVICE_NAMES
+        .iter()
+        .any(|device| name[..stem_end].eq_ignore_ascii_case(device));
+    let trailing_start = name.trim_end_matches(['.', ' ']).len();
+    let mut sanitized = String::new();
+    for (i, c) in name.char_indices() {
+        if matches!(
+            c,
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' | '%'
+        ) || c.is_ascii_control()
+            || i >= trailing_start
+            || (is_device && i + 1 == stem_end)
+        {
+            sanitized.push_str(&format!("%{:02X}", c as u32));
+        } else {
+            sanitized.push(c);
+        }
+    }
+    sanitized
+}
+
+/// `path` as a Windows extended-length path (`\\?\C:\...`), which may be longer than `MAX_PATH`
+/// (260 characters). Elsewhere there is no such limit, and `path` is returned as is.
+pub fn extended_length(path: &Path) -> PathBuf {
+    if !cfg!(windows) {
+        return path.to_path_buf();
+    }
+    let Ok(absolute) = std::path::absolute(path) else {
+        return path.to_path_buf();
+    };
+    let absolute = absolute.to_string_lossy();
+    if absolute.starts_with(r"\\?\") {
+        PathBuf::from(absolute.as_ref())
+    } else if let Some(share) = absolute.strip_prefix(r"\\") {
+        PathBuf::from(format!(r"\\?\UNC\{share}"))
+    } else {
+        PathBuf::from(format!(r"\\?\{absolute}"))
+    }
+}
+
+/// Writes `content` to `path` unless the file already holds exactly that, so unchanged outputs
+/// keep their modification time and are skipped by rsync or CDN syncs.
+pub fn write_if_changed(path: &Path, content: &[u8]) {
+    let unchanged = fs::metadata(path).is_ok_and(|metadata| metadata.len() == content.len() as u64)
+        && fs::read(path).is_ok_and(|existing| existing == content);
+    if !unchanged {
+        fs::write(path, content).expect("Failed to write output");
+    }
+}
+
+/// Writes `value` as pretty-printed JSON with [`write_if_changed`].
+fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) {
+    let content = serde_json::to_vec_pretty(value).expect("Failed to write json");
+    write_if_changed(path, &content);
+}
+
+/// Where a record's entry is in an output file: the file, relative to the output directory, and
+/// the byte range of the entry's value.
+#[derive(Debug, Serialize)]
+pub struct EntryLocation {
+    pub file: PathBuf,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Pretty-prints entries, already sorted by key, exactly like `serde_json::to_writer_pretty`
+/// does a sorted map, and returns the byte range of each value.
+fn to_pretty_json<T: Serialize>(entries: &[(&str, &T)]) -> (Vec<u8>, Vec<Range<usize>>) {
+    if entries.is_empty() {
+        return (b"{}".to_vec(), vec![]);
+    }
+    let mut buf = b"{".to_vec();
+    let mut ranges = vec![];
+    for (i, (key, value)) in entries.iter().enumerate() {
+        buf.extend_from_slice(if i == 0 { b"\n  " } else { b",\n  " });
+        serde_json::to_writer(&mut buf, key).expect("Failed to write json");
+        buf.extend_from_slice(b": ");
+        let value = serde_json::to_string_pretty(value).expect("Failed to write json");
+        let start = buf.len();
+        // Strings never contain raw newlines, so every line break is formatting.
+        buf.extend_from_slice(value.replace('\n', "\n  ").as_bytes());
+        ranges.push(start..buf.len());
+    }
+    buf.extend_from_slice(b"\n}");
+    (buf, ranges)
+}
+
+/// Splits entries into consecutive runs whose rendering takes at most about `chunk_size` bytes
+/// each, given the byte range of each entry in the rendering of all of them. An entry larger
+/// than `chunk_size` gets a chunk of its own.
+fn split_chunks<'a, 'b, T>(
+    entries: &'b [(&'a str, &'b T)],
+    ranges: &[Range<usize>],
+    chunk_size: usize,
+) -> Vec<&'b [(&'a str, &'b T)]> {
+    let mut chunks = vec![];
+    let mut chunk_start = 0;
+    let mut chunk_bytes = 0;
+    let mut prev_end = 0;
+    for (i, range) in ranges.iter().enumerate() {
+        let entry_bytes = range.end - prev_end;
+        prev_end = range.end;
+        if i > chunk_start && chunk_bytes + entry_bytes > chunk_size {
+            chunks.push(&entries[chunk_start..i]);
+            chunk_start = i;
+            chunk_bytes = 0;
+        }
+        chunk_bytes += entry_bytes;
+    }
+    chunks.push(&entries[chunk_start..]);
+    chunks
+}
+
+/// `path` with `suffix` appended to its file name.
+pub(crate) fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// One chunk of a split output file, as listed in the file's `.index`.
+#[derive(Serialize)]
+struct ChunkIndexEntry<'a> {
+    /// File name of the chunk, next to the index
+    chunk: String,
+    first_key: &'a str,
+    last_key: &'a str,
+}
+
+/// Writes one pretty-printed JSON file per tracked path under `dir`, mirroring the layout of the
+/// repository, with entries sorted by primary key. Returns where each entry was written.
+///
+/// With a `chunk_size`, a file that would be larger is split by key range into chunks
+/// `<file>.0`, `<file>.1`, ... and an index `<file>.index` listing the first and last key of
+/// each chunk. Keys are ordered as strings.
+///
+/// With a `compression`, files (but not chunk indexes) are compressed and get its extension, and
+/// the returned byte ranges are those of the uncompressed content.
+pub fn write_per_path<T: Serialize>(
+    dir: &Path,
+    entries: &HashMap<PathBuf, HashMap<String, T>>,
+    chunk_size: Option<usize>,
+    compression: Option<Compression>,
+) -> HashMap<String, Vec<EntryLocation>> {
+    let mut locations: HashMap<String, Vec<EntryLocation>> = HashMap::new();
+    let mut write_file = |output_path: &Path, entries: &[(&str, &T)]| {
+        let (content, ranges) = to_pretty_json(entries);
+        let written_path = write_compressed(output_path, &content, compression);
+        let file = written_path.strip_prefix(dir).unwrap();
+        for ((key, _), range) in entries.iter().zip(ranges) {
+            locations
+                .entry(key.to_string())
+                .or_default()
+                .push(EntryLocation {
+                    file: file.to_path_buf(),
+                    start: range.start,
+                    end: range.end,
+                });
+        }
+        written_path
+    };
+    for (path, entry) in entries {
+        let output_path = output_file_path(dir, path);
+        fs::create_dir_all(output_path.parent().unwrap()).expect("Failed to create directory");
+        let sorted_entries = entry
+            .iter()
+            .map(|(key, value)| (key.as_str(), value))
+            .sorted_by_key(|(key, _)| *key)
+            .collect::<Vec<_>>();
+        let chunk_size = match chunk_size {
+            Some(chunk_size) => chunk_size,
+            None => {
+                write_file(&output_path, &sorted_entries);
+                continue;
+            }
+        };
+        let (content, ranges) = to_pretty_json(&sorted_entries);
+        if content.len() <= chunk_size {
+            write_file(&output_path, &sorted_entries);
+            continue;
+        }
+        let mut index = vec![];
+        for (i, chunk) in split_chunks(&sorted_entries, &ranges, chunk_size)
+            .into_iter()
+            .enumerate()
+        {
+            let chunk_path = write_file(&with_suffix(&output_path, &format!(".{i}")), chunk);
+            index.push(ChunkIndexEntry {
+                chunk: chunk_path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned(),
+                first_key: chunk[0].0,
+                last_key: chunk[chunk.len() - 1].0,
+            });
+        }
+        write_json(&with_suffix(&output_path, ".index"), &index);
+    }
+    locations
+}
+
+#[derive(Serialize)]
+struct MergedLine<'a, T> {
+    path: &'a Path,
+    records: &'a BTreeMap<&'a str, &'a T>,
+}
+
+/// The entries of every tracked path keyed by the path each would have in an output directory,
+/// with paths and keys sorted.
+fn merge_entries<T>(
+    entries: &HashMap<PathBuf, HashMap<String, T>>,
+) -> BTreeMap<PathBuf, BTreeMap<&str, &T>> {
+    entries
+        .iter()
+        .map(|(path, entry)| {
+            let sorted_map = entry
+                .iter()
+                .map(|(key, value)| (key.as_str(), value))
+                .collect::<BTreeMap<_, _>>();
+            (output_file_path(Path::new(""), path), sorted_map)
+        })
+        .collect()
+}
+
+/// Writes the entries of every tracked path into the single file `file`, as in
+/// [`merge_entries`]. With a `compression`, the file is compressed and gets its extension.
+pub fn write_merged<T: Serialize>(
+    file: &Path,
+    entries: &HashMap<PathBuf, HashMap<String, T>>,
+    format: MergeFormat,
+    compression: Option<Compression>,
+) {
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent).expect("Failed to create directory");
+    }
+    write_compressed(file, &merged_content(entries, format), compression);
+}
+
+fn merged_content<T: Serialize>(
+    entries: &HashMap<PathBuf, HashMap<String, T>>,
+    format: MergeFormat,
+) -> Vec<u8> {
+    let merged = merge_entries(entries);
+    match format {
+        MergeFormat::Json => serde_json::to_vec_pretty(&merged).expect("Failed to write json"),
+        MergeFormat::Ndjson => {
+            let mut content = vec![];
+            for (path, records) in &merged {
+                serde_json::to_writer(&mut content, &MergedLine { path, records })
+                    .expect("Failed to write json");
+                content.push(b'\n');
+            }
+            content
+        }
+    }
+}
+
+/// Length of the content hash in `--cdn-mode` file names.
+const CDN_HASH_LEN: usize = 12;
+
+/// Writes one minified, brotli-compressed JSON file per tracked path under `dir`, named after
+/// a hash of its content (e.g. `parsed_courses/202301.0123456789ab.json.br`), and a
+/// `manifest.json` mapping each tracked path's usual output path to its file. Files listed in
+/// the previous manifest but not the new one are removed.
+pub fn write_cdn<T: Serialize>(dir: &Path, entries: &HashMap<PathBuf, HashMap<String, T>>) {
+    let manifest_path = dir.join("manifest.json");
+    let previous: BTreeMap<PathBuf, PathBuf> = read_existing(&manifest_path);
+    let mut manifest = BTreeMap::new();
+    for (path, entry) in entries {
+        let output_path = output_file_path(dir, path);
+        fs::create_dir_all(output_path.parent().unwrap()).expect("Failed to create directory");
+        let sorted_map = entry.iter().collect::<BTreeMap<_, _>>();
+        let content = serde_json::to_vec(&sorted_map).expect("Failed to write json");
+        let hash = git2::Oid::hash_object(git2::ObjectType::Blob, &content)
+            .expect("Failed to hash output")
+            .to_string();
+        let file_name = format!(
+            "{}.{}.{}.br",
+            output_path.file_stem().unwrap().to_string_lossy(),
+            &hash[..CDN_HASH_LEN],
+            output_path
+                .extension()
+                .map_or("json".into(), |extension| extension.to_string_lossy()),
+        );
+        let cdn_path = output_path.with_file_name(file_name);
+        let mut writer = brotli::CompressorWriter::new(vec![], 4096, 11, 22);
+        writer
+            .write_all(&content)
+            .expect("Failed to compress output");
+        write_if_changed(&cdn_path, &writer.into_inner());
+        manifest.insert(
+            output_path.strip_prefix(dir).unwrap().to_path_buf(),
+            cdn_path.strip_prefix(dir).unwrap().to_path_buf(),
+        );
+    }
+    write_json(&manifest_path, &manifest);
+    let current = manifest.values().collect::<HashSet<_>>();
+    for stale in previous
+        .values()
+        .filter(|cdn_path| !current.contains(cdn_path))
+    {
+        let stale = dir.join(stale);
+        match fs::remove_file(&stale) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                panic!("Failed to remove {}: {e}", stale.display())
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The instants changes refer to under `--compact-commits`, each stored once and ordered by
+/// time.
+struct InstantTable<'a> {
+    instants: Vec<&'a ChangeInstant>,
+    indexes: HashMap<&'a str, usize>,
+}
+
+impl<'a> InstantTable<'a> {
+    fn new(records: impl Iterator<Item = &'a ChangeRecord>) -> Self {
+        let instants = records
+            .flat_map(|record| [&record.added, &record.removed, &record.modified])
+            .flatten()
+            .map(|change| change.instant.as_ref())
+            .sorted_by(|a, b| (a.timestamp, &a.commit).cmp(&(b.timestamp, &b.commit)))
+            .dedup_by(|a, b| a.commit == b.commit)
+            .collect::<Vec<_>>();
+        let indexes = instants
+            .iter()
+            .enumerate()
+            .map(|(index, instant)| (instant.commit.as_str(), index))
+            .collect();
+        InstantTable { instants, indexes }
+    }
+
+    /// Serializes `records`, replacing the `commit`, `timestamp`, trailers, and commit metadata of
+    /// each change with the index of its instant.
+    fn compact<T: Serialize>(&self, records: &HashMap<String, T>) -> serde_json::Value {
+        let sorted_map = records.iter().collect::<BTreeMap<_, _>>();
+        let mut records = serde_json::to_value(sorted_map).expect("Failed to serialize records");
+        let changes = records
+            .as_object_mut()
+            .into_iter()
+            .flat_map(|records| records.values_mut())
+            .filter_map(|record| record.as_object_mut())
+            .flat_map(|record| {
+                record
+                    .iter_mut()
+                    .filter(|(change_type, _)| {
+                        ["added", "removed", "modified"].contains(&change_type.as_str())
+                    })
+                    .filter_map(|(_, changes)| changes.as_array_mut())
+                    .flatten()
+            })
+            .filter_map(|change| change.as_object_mut());
+        for change in changes {
+            let instant_fields = [
+                "timestamp",
+                "trailers",
+                "author",
+                "committer",
+                "summary",
+                "outside_schedule",
+            ];
+            for field in instant_fields {
+                change.remove(field);
+            }
+            if let Some(commit) = change.get_mut("commit") {
+                *commit = self.indexes[commit.as_str().unwrap()].into();
+            }
+        }
+        records
+    }
+
+    fn output<R>(&self, records: R) -> CompactOutput<'a, R> {
+        let timestamps = self
+            .instants
+            .iter()
+            .scan(0, |previous, instant| {
+                let delta = instant.timestamp - *previous;
+                *previous = instant.timestamp;
+                Some(delta)
+            })
+            .collect();
+        CompactOutput {
+            commits: self
+                .instants
+                .iter()
+                .map(|instant| instant.commit.as_str())
+                .collect(),
+            timestamps,
+            trailers: self
+                .instants
+                .iter()
+                .any(|instant| !instant.trailers.is_empty())
+                .then(|| {
+                    self.instants
+                        .iter()
+                        .map(|instant| &instant.trailers)
+                        .collect()
+                }),
+            meta: self
+                .instants
+                .iter()
+                .map(|instant| instant.meta.as_ref())
+                .collect(),
+            outside_schedule: self
+                .instants
+                .iter()
+                .positions(|instant| instant.outside_schedule)
+                .collect(),
+            records,
+        }
+    }
+}
+
+/// Change records under `--compact-commits`. A change's `commit` is an index into `commits`,
+/// `timestamps`, and the other lists, which hold each instant once, oldest first.
+#[derive(Serialize)]
+struct CompactOutput<'a, R> {
+    commits: Vec<&'a str>,
+    /// Unix time of the first instant, then of each instant the seconds since the previous one
+    timestamps: Vec<i64>,
+    /// The `--trailer`s of each instant, if any instant has some
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trailers: Option<Vec<&'a BTreeMap<String, String>>>,
+    /// The `--with-commit-meta` metadata of each instant
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<Vec<&'a CommitMeta>>,
+    /// The indexes of the instants outside every `--flag-outside-schedule` window
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    outside_schedule: Vec<usize>,
+    records: R,
+}
+
+/// `key` with bytes other than ASCII letters, digits, `-`, and `_` percent-encoded, so that any
+/// key is a valid file name.
+fn escape_key(key: &str) -> String {
+    let mut escaped = String::new();
+    for byte in key.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' => escaped.push(byte as char),
+            _ => escaped.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    escaped
+}
+
+/// The first two digits of the git blob hash of `key`, which spread records evenly over 256
+/// directories.
+fn key_shard(key: &str) -> String {
+    let hash = git2::Oid::hash_object(git2::ObjectType::Blob, key.as_bytes())
+        .expect("Failed to hash key")
+        .to_string();
+    hash[..2].to_string()
+}
+
+/// The file of the record `key` of a tracked path under `--split-by record`, relative to the
+/// path's output file without its extension: `<shard>/<key>.json`, with the key escaped so that
+/// any key is a valid file name.
+pub fn record_file_path(key: &str) -> PathBuf {
+    Path::new(&key_shard(key)).join(escape_key(key) + ".json")
+}
+
+const PATH_TEMPLATE: &str = "path";
+const BODY_TEMPLATE: &str = "body";
+
+handlebars_helper!(json: |value: Json| serde_json::to_string(value).expect("Failed to write json"));
+
+/// The `--path-template` and `--body-template` of `--split-by record`, if given.
+struct RecordTemplates {
+    handlebars: Handlebars<'static>,
+}
+
+#[derive(Serialize)]
+struct RecordPathContext {
+    /// The tracked path without its extension
+    path: PathBuf,
+    /// The escaped primary key, as in [`record_file_path`]
+    primary_key: String,
+    shard: String,
+}
+
+#[derive(Serialize)]
+struct RecordBodyContext<'a, T> {
+    path: &'a Path,
+    primary_key: &'a str,
+    record: &'a T,
+}
+
+impl RecordTemplates {
+    fn new(args: &ExtractArgs) -> Self {
+        let mut handlebars = Handlebars::new();
+        // Paths and bodies are not HTML.
+        handlebars.register_escape_fn(handlebars::no_escape);
+        handlebars.set_strict_mode(true);
+        handlebars.register_helper("json", Box::new(json));
+        if let Some(path_template) = &args.path_template {
+            handlebars
+                .register_template_string(PATH_TEMPLATE, path_template)
+                .expect("Failed to parse --path-template");
+        }
+        if let Some(body_template) = &args.body_template {
+            handlebars
+                .register_template_file(BODY_TEMPLATE, body_template)
+                .expect("Failed to parse --body-template");
+        }
+        RecordTemplates { handlebars }
+    }
+
+    /// The file of the record `key` of `path`, relative to the output directory.
+    fn path(&self, path: &Path, key: &str) -> PathBuf {
+        let path = output_file_path(Path::new(""), path).with_extension("");
+        if !self.handlebars.has_template(PATH_TEMPLATE) {
+            return path.join(record_file_path(key));
+        }
+        let context = RecordPathContext {
+            path,
+            primary_key: escape_key(key),
+            shard: key_shard(key),
+        };
+        let rendered = PathBuf::from(
+            self.handlebars
+                .render(PATH_TEMPLATE, &context)
+                .expect("Failed to render --path-template"),
+        );
+        if !rendered
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            panic!(
+                "--path-template rendered {}, which is not a path within the output directory",
+                rendered.display()
+            );
+        }
+        rendered
+    }
+
+    fn body<T: Serialize>(&self, path: &Path, key: &str, record: &T) -> Vec<u8> {
+        if !self.handlebars.has_template(BODY_TEMPLATE) {
+            return serde_json::to_vec_pretty(record).expect("Failed to write json");
+        }
+        let context = RecordBodyContext {
+            path,
+            primary_key: key,
+            record,
+        };
+        self.handlebars
+            .render(BODY_TEMPLATE, &context)
+            .expect("Failed to render --body-template")
+            .into_bytes()
+    }
+}
+
+/// Writes one file per record under `dir`, at the [`record_file_path`] of its key, or where
+/// `templates` put it and with the content they render.
+fn write_per_record<T: Serialize>(
+    dir: &Path,
+    entries: &HashMap<PathBuf, HashMap<String, T>>,
+    compression: Option<Compression>,
+    templates: &RecordTemplates,
+) {
+    for (path, entry) in entries {
+        for (key, record) in entry {
+            let output_path = dir.join(templates.path(path, key));
+            fs::create_dir_all(output_path.parent().unwrap()).expect("Failed to create directory");
+            let content = templates.body(path, key, record);
+            write_compressed(&output_path, &content, compression);
+        }
+    }
+}
+
+/// The name of the index of the windows of a tracked path under `--split-by-window`.
+pub const WINDOW_INDEX: &str = "index.json";
+
+/// One window of a tracked path, as listed in its [`WINDOW_INDEX`].
+#[derive(Serialize)]
+struct WindowIndexEntry<'a> {
+    window: &'a str,
+    /// File name of the window, next to the index
+    file: PathBuf,
+    /// How many records changed in the window
+    records: usize,
+    first_commit: &'a str,
+    last_commit: &'a str,
+}
+
+/// The changes of `record` that fall into each window.
+fn split_windows(record: &ChangeRecord, window: SplitWindow) -> BTreeMap<String, ChangeRecord> {
+    let mut windows: BTreeMap<String, ChangeRecord> = BTreeMap::new();
+    let mut split = |changes: &[Change], list: fn(&mut ChangeRecord) -> &mut Vec<Change>| {
+        for change in changes {
+            let windowed = windows
+                .entry(window.window(change.instant.timestamp))
+                .or_insert_with(|| ChangeRecord {
+                    added: vec![],
+                    removed: vec![],
+                    modified: vec![],
+                    order: record.order,
+                });
+            list(windowed).push(change.clone());
+        }
+    };
+    split(&record.added, |record| &mut record.added);
+    split(&record.removed, |record| &mut record.removed);
+    split(&record.modified, |record| &mut record.modified);
+    windows
+}
+
+/// Writes the change records of each tracked path into a directory at its usual output path under
+/// `dir`, with a file per `window` holding the changes made in it (e.g.
+/// `parsed_courses/202301.json/2019.json`) and a [`WINDOW_INDEX`] listing the windows oldest
+/// first, with the number of records that changed in each and the commits of its oldest and
+/// newest changes.
+fn write_per_window(
+    dir: &Path,
+    entries: &HashMap<PathBuf, HashMap<String, ChangeRecord>>,
+    window: SplitWindow,
+    compression: Option<Compression>,
+) {
+    for (path, records) in entries {
+        let output_dir = output_file_path(dir, path);
+        fs::create_dir_all(&output_dir).expect("Failed to create directory");
+        let mut windows: BTreeMap<String, BTreeMap<&str, ChangeRecord>> = BTreeMap::new();
+        for (key, record) in records {
+            for (name, record) in split_windows(record, window) {
+                windows.entry(name).or_default().insert(key, record);
+            }
+        }
+        let mut index = vec![];
+        for (name, records) in &windows {
+            let content = serde_json::to_vec_pretty(records).expect("Failed to write json");
+            let file = write_compressed(
+                &output_dir.join(format!("{name}.json")),
+                &content,
+                compression,
+            );
+            let (first, last) = manifest::change_range(records.values()).unwrap();
+            index.push(WindowIndexEntry {
+                window: name,
+                file: PathBuf::from(file.file_name().unwrap()),
+                records: records.len(),
+                first_commit: &first.commit,
+                last_commit: &last.commit,
+            });
+        }
+        write_json(&output_dir.join(WINDOW_INDEX), &index);
+    }
+}
+
+/// Writes one `--compact-commits` JSON file per tracked path under `dir`.
+fn write_compact(
+    dir: &Path,
+    entries: &HashMap<PathBuf, HashMap<String, RecordOutput>>,
+    compression: Option<Compression>,
+) {
+    for (path, entry) in entries {
+        let table = InstantTable::new(entry.values().map(|output| output.record));
+        let output_path = output_file_path(dir, path);
+        fs::create_dir_all(output_path.parent().unwrap()).expect("Failed to create directory");
+        let content = serde_json::to_vec_pretty(&table.output(table.compact(entry)))
+            .expect("Failed to write json");
+        write_compressed(&output_path, &content, compression);
+    }
+}
+
+/// A change record as written to the output directory.
+#[derive(Serialize)]
+struct RecordOutput<'a> {
+    #[serde(flatten)]
+    record: &'a ChangeRecord,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rollup: Option<BTreeMap<String, ChangeCounts>>,
+}
+
+fn record_outputs(
+    extraction: &Extraction,
+    rollup_period: Option<RollupPeriod>,
+) -> HashMap<PathBuf, HashMap<String, RecordOutput<'_>>> {
+    extraction
+        .change_records
+        .iter()
+        .map(|(path, records)| {
+            let records = records
+                .iter()
+                .map(|(key, record)| {
+                    let output = RecordOutput {
+                        record,
+                        rollup: rollup_period.map(|period| rollup::rollup(record, period)),
+                    };
+                    (key.clone(), output)
+                })
+                .collect();
+            (path.clone(), records)
+        })
+        .collect()
+}
+
+/// The combined history of a `--group-by` group.
+#[derive(Serialize)]
+struct GroupOutput<'a> {
+    /// Primary keys of the group's records, sorted
+    members: Vec<&'a str>,
+    #[serde(flatten)]
+    record: ChangeRecord,
+}
+
+/// Merges the changes of several records, newest first, keeping one per commit. Captured
+/// fields belong to a single record, so they are dropped.
+fn merge_changes<'a>(changes: impl Iterator<Item = &'a Vec<Change>>) -> Vec<Change> {
+    changes
+        .flatten()
+        .map(|change| &change.instant)
+        .sorted_by_key(|instant| std::cmp::Reverse(instant.timestamp))
+        .unique_by(|instant| &instant.commit)
+        .map(|instant| Change {
+            id: None,
+            instant: instant.clone(),
+            captured: None,
+            patch: None,
+            changed_fields: None,
+            values: None,
+        })
+        .collect()
+}
+
+fn group_outputs(extraction: &Extraction) -> HashMap<PathBuf, HashMap<String, GroupOutput<'_>>> {
+    extraction
+        .groups
+        .iter()
+        .map(|(path, groups)| {
+            let records = &extraction.change_records[path];
+            let groups = groups
+                .iter()
+                .filter(|(key, _)| records.contains_key(*key))
+                .into_group_map_by(|(_, group)| group.as_str())
+                .into_iter()
+                .map(|(group, members)| {
+                    let members = members
+                        .into_iter()
+                        .map(|(key, _)| key.as_str())
+                        .sorted()
+                        .collect::<Vec<_>>();
+                    let member_records = members.iter().map(|key| &records[*key]);
+                    let record = ChangeRecord {
+                        added: merge_changes(member_records.clone().map(|r| &r.added)),
+                        removed: merge_changes(member_records.clone().map(|r| &r.removed)),
+                        modified: merge_changes(member_records.map(|r| &r.modified)),
+                        order: records[members[0]].order,
+                    };
+                    (group.to_string(), GroupOutput { members, record })
+                })
+                .collect();
+            (path.clone(), groups)
+        })
+        .collect()
+}
+
+/// When a record first appeared: its oldest `added` instant, unless an even older removal or
+/// modification shows it already existed before the walked history began.
+fn first_seen(record: &ChangeRecord) -> Option<&ChangeInstant> {
+    // Instants are stored newest first.
+    let added = &record.added.last()?.instant;
+    let existed_before = [&record.removed, &record.modified]
+        .iter()
+        .filter_map(|changes| changes.last())
+        .any(|change| change.instant.timestamp < added.timestamp);
+    if existed_before {
+        None
+    } else {
+        Some(added)
+    }
+}
+
+/// For each key present at HEAD, the instant it first appeared, if the walk saw it.
+fn presence_index(
+    extraction: &Extraction,
+) -> HashMap<PathBuf, HashMap<String, Option<&ChangeInstant>>> {
+    extraction
+        .presence
+        .iter()
+        .map(|(path, keys)| {
+            let change_records = extraction.change_records.get(path);
+            let first_seen = keys
+                .iter()
+                .map(|key| {
+                    let first_seen = change_records
+                        .and_then(|records| records.get(key))
+                        .and_then(first_seen);
+                    (key.clone(), first_seen)
+                })
+                .collect();
+            (path.clone(), first_seen)
+        })
+        .collect()
+}
+
+/// Writes the change records to `output_dir` as JSON files, in the layout requested by `args`.
+fn write_output_dir(args: &ExtractArgs, extraction: &Extraction, output_dir: &Path) {
+    let records = record_outputs(extraction, args.rollup);
+    if let Some(format) = args.merge_output {
+        write_merged(output_dir, &records, format, args.compress);
+    } else if args.cdn_mode {
+        fs::create_dir_all(output_dir).expect("Failed to create directory");
+        write_cdn(output_dir, &records);
+    } else if args.compact_commits {
+        write_compact(output_dir, &records, args.compress);
+    } else if args.append {
+        let records = appended_outputs(output_dir, &records, args.order);
+        write_per_path(output_dir, &records, None, None);
+    } else if let Some(window) = args.split_by_window {
+        write_per_window(
+            output_dir,
+            &extraction.change_records,
+            window,
+            args.compress,
+        );
+    } else if args.split_by == SplitBy::Record {
+        write_per_record(
+            output_dir,
+            &records,
+            args.compress,
+            &RecordTemplates::new(args),
+        );
+    } else {
+        let mut locations = write_per_path(output_dir, &records, args.chunk_size, args.compress);
+        if let Some(manifest_path) = &args.record_manifest {
+            for entries in locations.values_mut() {
+                entries.sort_by(|a, b| a.file.cmp(&b.file));
+            }
+            let sorted_locations = locations.iter().collect::<BTreeMap<_, _>>();
+            write_json(manifest_path, &sorted_locations);
+        }
+    }
+}
+
+/// A change record as read back from an output file, for `--append`.
+#[derive(Serialize, Deserialize)]
+struct StoredRecord {
+    added: Vec<serde_json::Value>,
+    removed: Vec<serde_json::Value>,
+    modified: Vec<serde_json::Value>,
+}
+
+impl StoredRecord {
+    /// Adds the changes of `new` that this record does not list yet, after the existing ones
+    /// under `--order asc` and before them under `desc`. A change is already listed if the
+    /// record has a change of the same type in the same commit.
+    fn append(&mut self, new: StoredRecord, order: ChangeOrder) {
+        let mut listed = HashSet::new();
+        for (change_type, changes) in [
+            (ChangeType::Added, &self.added),
+            (ChangeType::Removed, &self.removed),
+            (ChangeType::Modified, &self.modified),
+        ] {
+            for change in changes {
+                listed.insert((change_type, change["commit"].to_string()));
+            }
+        }
+        let StoredRecord {
+            added,
+            removed,
+            modified,
+        } = new;
+        for (change_type, new) in [
+            (ChangeType::Added, added),
+            (ChangeType::Removed, removed),
+            (ChangeType::Modified, modified),
+        ] {
+            let new = new
+                .into_iter()
+                .filter(|change| listed.insert((change_type, change["commit"].to_string())))
+                .collect::<Vec<_>>();
+            let existing = match change_type {
+                ChangeType::Added => &mut self.added,
+                ChangeType::Removed => &mut self.removed,
+                ChangeType::Modified => &mut self.modified,
+            };
+            match order {
+                ChangeOrder::Asc => existing.extend(new),
+                ChangeOrder::Desc => {
+                    existing.splice(0..0, new);
+                }
+            }
+        }
+    }
+}
+
+/// The contents of `path` as written by an earlier run, for `--append`, or the default if there
+/// is no such file.
+fn read_existing<T: DeserializeOwned + Default>(path: &Path) -> T {
+    match fs::read(path) {
+        Ok(content) => serde_json::from_slice(&content)
+            .unwrap_or_else(|e| panic!("Failed to parse {}: {e}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => T::default(),
+        Err(e) => panic!("Failed to read {}: {e}", path.display()),
+    }
+}
+
+/// The change records of each tracked path merged into those already in its file in
+/// `output_dir`, if any, for `--append`. Records only in the existing file are kept as they are.
+fn appended_outputs(
+    output_dir: &Path,
+    records: &HashMap<PathBuf, HashMap<String, RecordOutput>>,
+    order: ChangeOrder,
+) -> HashMap<PathBuf, HashMap<String, StoredRecord>> {
+    records
+        .iter()
+        .map(|(path, records)| {
+            let mut existing: HashMap<String, StoredRecord> =
+                read_existing(&output_file_path(output_dir, path));
+            for (key, record) in records {
+                let record: StoredRecord = serde_json::to_value(record)
+                    .and_then(serde_json::from_value)
+                    .expect("Failed to write json");
+                match existing.get_mut(key) {
+                    Some(existing) => existing.append(record, order),
+                    None => {
+                        existing.insert(key.clone(), record);
+                    }
+                }
+            }
+            (path.clone(), existing)
+        })
+        .collect()
+}
+
+/// The removed objects of each tracked path added to those already in its file in
+/// `graveyard_dir`, for `--append`. An object removed again replaces the one already there.
+fn appended_graveyard(
+    graveyard_dir: &Path,
+    graveyard: &HashMap<PathBuf, HashMap<String, serde_json::Value>>,
+) -> HashMap<PathBuf, HashMap<String, serde_json::Value>> {
+    graveyard
+        .iter()
+        .map(|(path, objects)| {
+            let mut existing: HashMap<String, serde_json::Value> =
+                read_existing(&output_file_path(graveyard_dir, path));
+            existing.extend(
+                objects
+                    .iter()
+                    .map(|(key, object)| (key.clone(), object.clone())),
+            );
+            (path.clone(), existing)
+        })
+        .collect()
+}
+
+/// The no-op commits of this run before those already in `path` that it does not list yet, for
+/// `--append`.
+fn appended_noop_commits(path: &Path, noop_commits: &[NoopCommit]) -> Vec<serde_json::Value> {
+    let existing: Vec<serde_json::Value> = read_existing(path);
+    let listed = existing
+        .iter()
+        .map(|commit| commit["commit"].to_string())
+        .collect::<HashSet<_>>();
+    let new = serde_json::to_value(noop_commits).expect("Failed to write json");
+    let serde_json::Value::Array(new) = new else {
+        unreachable!("a list serializes as an array")
+    };
+    new.into_iter()
+        .filter(|commit| !listed.contains(&commit["commit"].to_string()))
+        .chain(existing)
+        .collect()
+}
+
+/// The presence index of this run, for `--append`: keys present at HEAD whose first appearance
+/// predates this run keep the instant an earlier run recorded in `presence_dir`.
+fn appended_presence_index(
+    presence_dir: &Path,
+    extraction: &Extraction,
+) -> HashMap<PathBuf, HashMap<String, serde_json::Value>> {
+    presence_index(extraction)
+        .into_iter()
+        .map(|(path, first_seen)| {
+            let mut existing: HashMap<String, serde_json::Value> =
+                read_existing(&output_file_path(presence_dir, &path));
+            let first_seen = first_seen
+                .into_iter()
+                .map(|(key, instant)| {
+                    let instant = match instant {
+                        Some(instant) => {
+                            serde_json::to_value(instant).expect("Failed to write json")
+                        }
+                        None => existing.remove(&key).unwrap_or_default(),
+                    };
+                    (key, instant)
+                })
+                .collect();
+            (path, first_seen)
+        })
+        .collect()
+}
+
+/// Writes the change records to stdout, for `-` as `<OUTPUT_PATH>`: in the layout of
+/// `--merge-output` (`json` unless given) for the JSON output format, or as a CSV, MessagePack, or
+/// bulk API file. Stopping early is not an error when the reader has gone away (e.g. `| head`).
+fn write_stdout(args: &ExtractArgs, extraction: &Extraction) {
+    let content = match args.output_format {
+        OutputFormat::Json => merged_content(
+            &record_outputs(extraction, args.rollup),
+            args.merge_output.unwrap_or(MergeFormat::Json),
+        ),
+        OutputFormat::Csv => {
+            let mut content = vec![];
+            write_csv(
+                csv::Writer::from_writer(&mut content),
+                extraction,
+                args.change_ids,
+            )
+            .expect("Failed to write CSV");
+            content
+        }
+        OutputFormat::Msgpack => msgpack_content(extraction, args.compact_commits),
+        OutputFormat::Elasticsearch => {
+            let mut content = vec![];
+            bulk::write(&mut content, extraction, args).expect("Failed to write bulk actions");
+            content
+        }
+        #[allow(unreachable_patterns)]
+        format => panic!("--output-format {format:?} cannot be written to stdout"),
+    };
+    let content = match args.compress {
+        Some(compression) => compression.compress(&content),
+        None => content,
+    };
+    let mut stdout = std::io::stdout().lock();
+    match stdout.write_all(&content).and_then(|()| stdout.flush()) {
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {}
+        result => result.expect("Failed to write to stdout"),
+    }
+}
+
+/// `args` with every path [`write`] writes to made an [`extended_length`] path.
+fn with_extended_lengths(args: &ExtractArgs) -> ExtractArgs {
+    let extend = |path: &str| {
+        extended_length(Path::new(path))
+            .to_string_lossy()
+            .into_owned()
+    };
+    let mut args = args.clone();
+    if let Some(output_path) = args.output_path.as_mut().filter(|path| *path != "-") {
+        *output_path = extend(output_path);
+    }
+    for path in [
+        &mut args.graveyard,
+        &mut args.record_counts,
+        &mut args.group_output,
+        &mut args.emit_presence_index,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        *path = extend(path);
+    }
+    for path in [
+        &mut args.event_log,
+        &mut args.noop_commits,
+        &mut args.season_summary,
+        &mut args.calendar_report,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        *path = extended_length(path);
+    }
+    args
+}
+
+/// Writes the output directory, graveyard, and reports requested by `args`.
+pub fn write(args: &ExtractArgs, extraction: &Extraction) {
+    let args = &with_extended_lengths(args);
+    if args.output_path.as_deref() == Some("-") {
+        write_stdout(args, extraction);
+    } else if let Some(output_path) = &args.output_path {
+        let output_path = Path::new(output_path);
+        match args.output_format {
+            OutputFormat::Json => write_output_dir(args, extraction, output_path),
+            OutputFormat::Csv => csv::Writer::from_path(output_path)
+                .and_then(|writer| write_csv(writer, extraction, args.change_ids))
+                .expect("Failed to write CSV"),
+            OutputFormat::Msgpack => write_msgpack(output_path, extraction, args.compact_commits),
+            OutputFormat::Elasticsearch => fs::File::create(output_path)
+                .map(std::io::BufWriter::new)
+                .and_then(|writer| bulk::write(writer, extraction, args))
+                .expect("Failed to write bulk actions"),
+            OutputFormat::Bigquery => {
+                bigquery::write(output_path, extraction).expect("Failed to write BigQuery rows")
+            }
+            #[cfg(feature = "arrow")]
+            OutputFormat::Arrow => {
+                crate::arrow::write(output_path, extraction).expect("Failed to write Arrow")
+            }
+            #[cfg(feature = "parquet")]
+            OutputFormat::Parquet => {
+                crate::parquet::write(output_path, extraction).expect("Failed to write Parquet")
+            }
+            #[cfg(feature = "proto")]
+            OutputFormat::Proto => crate::proto::write(output_path, extraction)
+                .expect("Failed to write Protocol Buffers"),
+            #[cfg(feature = "duckdb")]
+            OutputFormat::Duckdb => {
+                crate::duckdb::write(output_path, extraction).expect("Failed to write database")
+            }
+            #[cfg(feature = "sqlite")]
+            OutputFormat::Sqlite => {
+                crate::sqlite::write(output_path, extraction).expect("Failed to write database")
+            }
+        }
+    }
+    #[cfg(feature = "redis")]
+    if let Some(redis_url) = &args.redis {
+        crate::redis::write(redis_url, extraction).expect("Failed to write to Redis");
+    }
+    if let Some(event_log_path) = &args.event_log {
+        write_event_log(event_log_path, extraction).expect("Failed to write event log");
+    }
+    if let Some(graveyard_path) = &args.graveyard {
+        let graveyard_path = Path::new(graveyard_path);
+        if args.append {
+            let graveyard = appended_graveyard(graveyard_path, &extraction.graveyard);
+            write_per_path(graveyard_path, &graveyard, None, None);
+        } else {
+            write_per_path(graveyard_path, &extraction.graveyard, None, None);
+        }
+    }
+    if let Some(noop_commits_path) = &args.noop_commits {
+        if args.append {
+            let noop_commits = appended_noop_commits(noop_commits_path, &extraction.noop_commits);
+            write_json(noop_commits_path, &noop_commits);
+        } else {
+            write_json(noop_commits_path, &extraction.noop_commits);
+        }
+    }
+    if let Some(record_counts_path) = &args.record_counts {
+        for (path, counts) in &extraction.record_counts {
+            let output_path = output_file_path(Path::new(record_counts_path), path);
+            fs::create_dir_all(output_path.parent().unwrap()).expect("Failed to create directory");
+            let oldest_first = counts.iter().rev().collect::<Vec<_>>();
+            write_json(&output_path, &oldest_first);
+        }
+    }
+    if let Some(season_summary_path) = &args.season_summary {
+        write_json(season_summary_path, &season::season_summary(extraction));
+    }
+    if let Some(calendar_report_path) = &args.calendar_report {
+        let calendar_path = args
+            .calendar
+            .as_deref()
+            .expect("--calendar-report requires --calendar");
+        let calendar = calendar::load(calendar_path).expect("Failed to load --calendar");
+        write_json(
+            calendar_report_path,
+            &calendar::report(&calendar, extraction),
+        );
+    }
+    if let Some(group_output_path) = &args.group_output {
+        write_per_path(
+            Path::new(group_output_path),
+            &group_outputs(extraction),
+            None,
+            None,
+        );
+    }
+    if let Some(presence_path) = &args.emit_presence_index {
+        let presence_path = Path::new(presence_path);
+        if args.append {
+            let presence = appended_presence_index(presence_path, extraction);
+            write_per_path(presence_path, &presence, None, None);
+        } else {
+            write_per_path(presence_path, &presence_index(extraction), None, None);
+        }
+    }
+}
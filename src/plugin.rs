@@ -0,0 +1,59 @@
+//! Plugins: executables named `delorean-<name>` on `PATH`, found the way cargo and git find their
+//! external subcommands, so that private sinks and reports can be shipped without patching
+//! DeLorean. A plugin is run in one of two ways:
+//!
+//! - `delorean <name> [ARGS]...` runs `delorean-<name> [ARGS]...` when `<name>` is neither a
+//!   built-in subcommand nor an existing path, and exits with its exit code.
+//! - `--output plugin:<name>` (or `--rules-output plugin:<name>`) runs `delorean-<name>` without
+//!   arguments and streams the change events of the walk to its stdin.
+//!
+//! Either way, the `DELOREAN` environment variable holds the path of the running `delorean`
+//! executable, so that a plugin can run extractions of its own.
+//!
+//! # Event protocol
+//!
+//! Events arrive on stdin as NDJSON: one UTF-8 JSON object per line, in the order the walk
+//! records them (newest commit first), flushed after every commit. Each object has
+//!
+//! - `path`: the tracked file, relative to the repository root
+//! - `primary_key`: the record's primary key
+//! - `change_type`: `added`, `removed`, `modified`, or the `promote_to` of a `--rules` rule
+//! - `commit` and `timestamp`: the commit hash and its time, in `--timestamp-format`
+//!
+//! and, when the options that produce them are given, `id` (`--change-ids`), `rule`
+//! (`--rules-output`), `trailers` (`--trailer`), `author`, `committer`, and `summary`
+//! (`--with-commit-meta`), and `old` and `new` (`--include-values`). Consumers should ignore
+//! fields they do not know, as later versions may add more. Stdin is closed once the walk is done,
+//! and DeLorean waits for the plugin to exit before it exits itself.
+
+use std::env;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// The prefix of plugin executables.
+pub const PREFIX: &str = "delorean-";
+
+/// The executable of the plugin `name` on `PATH`, if there is one.
+pub fn find(name: &str) -> Option<PathBuf> {
+    if name.is_empty() || name.contains(std::path::is_separator) {
+        return None;
+    }
+    let file_name = format!("{PREFIX}{name}{}", env::consts::EXE_SUFFIX);
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(&file_name))
+        .find(|path| path.is_file())
+}
+
+/// A command running the plugin `name`, with `DELOREAN` set.
+pub fn command(name: &str) -> io::Result<Command> {
+    let executable = find(name).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No {PREFIX}{name} executable on PATH"),
+        )
+    })?;
+    let mut command = Command::new(executable);
+    command.env("DELOREAN", env::current_exe()?);
+    Ok(command)
+}
@@ -0,0 +1,310 @@
+//! Record predicates for `--where`, such as `/school == "YC"`: a JSON pointer, `==` or `!=`, and
+//! a JSON value. A missing field equals nothing, so it only satisfies `!=`. Also the
+//! `--tombstone-field` marker of soft-deleted records, such as `status=cancelled`, the `--sample`
+//! of primary keys, such as `1%`, and the `--flag-outside-schedule` windows of commit times, such
+//! as `Mon-Fri 02:00-04:00`.
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, TimeDelta, Weekday};
+use serde_json::Value;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, Clone)]
+pub struct Predicate {
+    pointer: String,
+    operator: Operator,
+    value: Value,
+}
+
+impl FromStr for Predicate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The first operator splits, so values may contain `==` or `!=`.
+        let (index, token, operator) = [("==", Operator::Eq), ("!=", Operator::Ne)]
+            .into_iter()
+            .filter_map(|(token, operator)| s.find(token).map(|index| (index, token, operator)))
+            .min_by_key(|(index, _, _)| *index)
+            .ok_or_else(|| format!("Expected `<pointer> == <value>` or `!=`, got {s}"))?;
+        let (pointer, value) = (&s[..index], &s[index + token.len()..]);
+        let pointer = pointer.trim();
+        if !pointer.is_empty() && !pointer.starts_with('/') {
+            return Err(format!("{pointer} is not a JSON pointer (e.g. /school)"));
+        }
+        let value = serde_json::from_str(value.trim())
+            .map_err(|e| format!("{} is not a JSON value: {e}", value.trim()))?;
+        Ok(Predicate {
+            pointer: pointer.to_string(),
+            operator,
+            value,
+        })
+    }
+}
+
+impl Predicate {
+    pub fn matches(&self, record: &Value) -> bool {
+        let equal = record.pointer(&self.pointer) == Some(&self.value);
+        match self.operator {
+            Operator::Eq => equal,
+            Operator::Ne => !equal,
+        }
+    }
+}
+
+/// A `--tombstone-field` such as `status=cancelled` or `/meta/deleted=true`: a top-level field
+/// name or JSON pointer, `=`, and a value, which is JSON or else a string. Records where the field
+/// has the value are treated as absent.
+#[derive(Debug, Clone)]
+pub struct Tombstone {
+    pointer: String,
+    value: Value,
+}
+
+impl FromStr for Tombstone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (field, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Expected `<field>=<value>`, got {s}"))?;
+        let pointer = if field.starts_with('/') {
+            field.to_string()
+        } else {
+            format!("/{}", field.replace('~', "~0").replace('/', "~1"))
+        };
+        let value = serde_json::from_str(value).unwrap_or_else(|_| Value::from(value));
+        Ok(Tombstone { pointer, value })
+    }
+}
+
+impl Tombstone {
+    pub fn matches(&self, record: &Value) -> bool {
+        record.pointer(&self.pointer) == Some(&self.value)
+    }
+}
+
+/// A `--sample` such as `1%` or `0.5%`: the share of primary keys to track. A key is in the sample
+/// if the git blob hash of the key falls in that share of the hash space, so every run with the
+/// same share tracks the same keys, and a larger share tracks a superset of a smaller one.
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    fraction: f64,
+}
+
+impl FromStr for Sample {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let percent = s
+            .strip_suffix('%')
+            .and_then(|percent| percent.parse::<f64>().ok())
+            .filter(|percent| *percent > 0.0 && *percent <= 100.0)
+            .ok_or_else(|| format!("Expected a percentage above 0% and up to 100%, got {s}"))?;
+        Ok(Sample {
+            fraction: percent / 100.0,
+        })
+    }
+}
+
+impl Sample {
+    pub fn contains(&self, primary_key: &str) -> bool {
+        let hash = git2::Oid::hash_object(git2::ObjectType::Blob, primary_key.as_bytes())
+            .expect("Failed to hash key");
+        let prefix = u64::from_be_bytes(hash.as_bytes()[..8].try_into().unwrap());
+        (prefix as f64) < self.fraction * (u64::MAX as f64)
+    }
+}
+
+/// A weekly window of `--flag-outside-schedule` such as `Mon-Fri 02:00-04:00` or
+/// `Mon,Wed,Sat-Sun 22:00-02:00`, in UTC. The end is exclusive, and a window whose end is not
+/// after its start runs past midnight, into the next day.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    /// By days from Monday
+    days: [bool; 7],
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+fn parse_weekday(s: &str) -> Result<Weekday, String> {
+    s.parse().map_err(|_| format!("Unknown day {s}"))
+}
+
+fn parse_time(s: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s, "%H:%M")
+        .map_err(|_| format!("Expected a time like 02:00, got {s}"))
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (days, times) = s
+            .split_once(' ')
+            .ok_or_else(|| format!("Expected days and times like Mon-Fri 02:00-04:00, got {s}"))?;
+        let mut selected = [false; 7];
+        for range in days.split(',') {
+            let (first, last) = range.split_once('-').unwrap_or((range, range));
+            let (mut day, last) = (parse_weekday(first)?, parse_weekday(last)?);
+            selected[day.num_days_from_monday() as usize] = true;
+            while day != last {
+                day = day.succ();
+                selected[day.num_days_from_monday() as usize] = true;
+            }
+        }
+        let (start, end) = times
+            .trim()
+            .split_once('-')
+            .ok_or_else(|| format!("Expected times like 02:00-04:00, got {times}"))?;
+        Ok(Schedule {
+            days: selected,
+            start: parse_time(start)?,
+            end: parse_time(end)?,
+        })
+    }
+}
+
+impl Schedule {
+    /// Whether the Unix time `timestamp` falls in the window. The day of a time after midnight in
+    /// a window that runs past it is the day the window started.
+    pub fn contains(&self, timestamp: i64) -> bool {
+        let time = DateTime::from_timestamp(timestamp, 0).unwrap();
+        let on = |day: Weekday| self.days[day.num_days_from_monday() as usize];
+        let (weekday, time) = (time.weekday(), time.time());
+        if self.start < self.end {
+            on(weekday) && self.start <= time && time < self.end
+        } else {
+            (on(weekday) && self.start <= time) || (on(weekday.pred()) && time < self.end)
+        }
+    }
+
+    /// The Unix times of the start and exclusive end of the window that starts on `day`, if the
+    /// window is open on that day.
+    pub fn window_on(&self, day: NaiveDate) -> Option<(i64, i64)> {
+        if !self.days[day.weekday().num_days_from_monday() as usize] {
+            return None;
+        }
+        let start = day.and_time(self.start).and_utc();
+        let mut end = day.and_time(self.end).and_utc();
+        if self.end <= self.start {
+            end += TimeDelta::days(1);
+        }
+        Some((start.timestamp(), end.timestamp()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn predicates_compare_the_value_at_a_pointer() {
+        let yc = "/school == \"YC\"".parse::<Predicate>().unwrap();
+        let not_yc = "/school!=\"YC\"".parse::<Predicate>().unwrap();
+        let record = json!({ "school": "YC", "seats": 1 });
+        let other = json!({ "school": "SOM" });
+        let missing = json!({});
+
+        assert!(yc.matches(&record) && !not_yc.matches(&record));
+        assert!(!yc.matches(&other) && not_yc.matches(&other));
+        // A missing field equals nothing
+        assert!(!yc.matches(&missing) && not_yc.matches(&missing));
+        assert!("/seats == 1".parse::<Predicate>().unwrap().matches(&record));
+        assert!(!"/seats == \"1\""
+            .parse::<Predicate>()
+            .unwrap()
+            .matches(&record));
+    }
+
+    #[test]
+    fn the_first_operator_splits_a_predicate() {
+        let predicate = "/note == \"a != b\"".parse::<Predicate>().unwrap();
+
+        assert!(predicate.matches(&json!({ "note": "a != b" })));
+        assert!("school == \"YC\"".parse::<Predicate>().is_err());
+        assert!("/school = \"YC\"".parse::<Predicate>().is_err());
+        assert!("/school == YC".parse::<Predicate>().is_err());
+    }
+
+    #[test]
+    fn tombstones_match_fields_or_pointers_with_json_or_string_values() {
+        let cancelled = "status=cancelled".parse::<Tombstone>().unwrap();
+        let deleted = "/meta/deleted=true".parse::<Tombstone>().unwrap();
+        let slashed = "a/b=1".parse::<Tombstone>().unwrap();
+
+        assert!(cancelled.matches(&json!({ "status": "cancelled" })));
+        assert!(!cancelled.matches(&json!({ "status": "open" })));
+        assert!(deleted.matches(&json!({ "meta": { "deleted": true } })));
+        assert!(!deleted.matches(&json!({ "meta": { "deleted": "true" } })));
+        // A field name is not a pointer
+        assert!(slashed.matches(&json!({ "a/b": 1 })));
+        assert!("status".parse::<Tombstone>().is_err());
+    }
+
+    #[test]
+    fn samples_are_stable_and_nested() {
+        let keys = (0..1000).map(|key| key.to_string()).collect::<Vec<_>>();
+        let sampled = |share: &str| {
+            let sample = share.parse::<Sample>().unwrap();
+            keys.iter()
+                .filter(|key| sample.contains(key))
+                .collect::<Vec<_>>()
+        };
+        let (small, large) = (sampled("10%"), sampled("50%"));
+
+        assert!((50..150).contains(&small.len()), "{}", small.len());
+        assert!(small.iter().all(|key| large.contains(key)));
+        assert_eq!(sampled("10%"), small);
+        assert_eq!(sampled("100%").len(), keys.len());
+        for invalid in ["0%", "101%", "10", "ten%"] {
+            assert!(invalid.parse::<Sample>().is_err(), "{invalid}");
+        }
+    }
+
+    // Monday 2024-09-02T00:00:00Z
+    const MONDAY: i64 = 1725235200;
+    const HOUR: i64 = 60 * 60;
+    const DAY: i64 = 24 * HOUR;
+
+    #[test]
+    fn schedules_contain_times_in_their_windows() {
+        let schedule = "Mon-Fri 02:00-04:00".parse::<Schedule>().unwrap();
+
+        assert!(schedule.contains(MONDAY + 2 * HOUR));
+        assert!(schedule.contains(4 * DAY + MONDAY + 3 * HOUR));
+        // The end is exclusive
+        assert!(!schedule.contains(MONDAY + 4 * HOUR));
+        assert!(!schedule.contains(5 * DAY + MONDAY + 3 * HOUR));
+        assert_eq!(
+            schedule.window_on(NaiveDate::from_ymd_opt(2024, 9, 2).unwrap()),
+            Some((MONDAY + 2 * HOUR, MONDAY + 4 * HOUR))
+        );
+        assert_eq!(
+            schedule.window_on(NaiveDate::from_ymd_opt(2024, 9, 7).unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn schedule_windows_can_run_past_midnight() {
+        let schedule = "Mon,Sun 22:00-02:00".parse::<Schedule>().unwrap();
+
+        assert!(schedule.contains(MONDAY + 23 * HOUR));
+        // Tuesday 01:00 is in Monday's window, and Monday 01:00 in Sunday's
+        assert!(schedule.contains(DAY + MONDAY + HOUR));
+        assert!(schedule.contains(MONDAY + HOUR));
+        assert!(!schedule.contains(2 * DAY + MONDAY + HOUR));
+        assert_eq!(
+            schedule.window_on(NaiveDate::from_ymd_opt(2024, 9, 2).unwrap()),
+            Some((MONDAY + 22 * HOUR, MONDAY + DAY + 2 * HOUR))
+        );
+        assert!("Mon-Fri".parse::<Schedule>().is_err());
+        assert!("Someday 02:00-04:00".parse::<Schedule>().is_err());
+    }
+}
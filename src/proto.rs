@@ -0,0 +1,170 @@
+//! Protocol Buffers output (`--output-format proto`): the change records of every path as a single
+//! `Extraction` message of `proto/delorean.proto`, for consumers that want a typed binary contract
+//! rather than parsing JSON. Built with the `proto` feature.
+//!
+//! The types below are the prost types of the `.proto`, written out so that building does not
+//! need `protoc`. Values whose type varies from record to record (captured fields, patches, and
+//! record versions) are JSON text.
+
+use crate::extract::Extraction as ExtractionResult;
+use crate::record::{self, ChangeOrder};
+use itertools::Itertools;
+use prost::Message as _;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Extraction {
+    #[prost(message, repeated, tag = "1")]
+    pub files: Vec<File>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct File {
+    #[prost(string, tag = "1")]
+    pub path: String,
+    #[prost(btree_map = "string, message", tag = "2")]
+    pub records: BTreeMap<String, ChangeRecord>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ChangeRecord {
+    #[prost(message, repeated, tag = "1")]
+    pub added: Vec<Change>,
+    #[prost(message, repeated, tag = "2")]
+    pub removed: Vec<Change>,
+    #[prost(message, repeated, tag = "3")]
+    pub modified: Vec<Change>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Change {
+    #[prost(message, optional, tag = "1")]
+    pub instant: Option<ChangeInstant>,
+    #[prost(string, optional, tag = "2")]
+    pub id: Option<String>,
+    #[prost(btree_map = "string, string", tag = "3")]
+    pub captured: BTreeMap<String, String>,
+    #[prost(string, optional, tag = "4")]
+    pub patch: Option<String>,
+    #[prost(string, repeated, tag = "5")]
+    pub changed_fields: Vec<String>,
+    #[prost(string, optional, tag = "6")]
+    pub old: Option<String>,
+    #[prost(string, optional, tag = "7")]
+    pub new: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ChangeInstant {
+    #[prost(string, tag = "1")]
+    pub commit: String,
+    #[prost(int64, tag = "2")]
+    pub timestamp: i64,
+    #[prost(btree_map = "string, string", tag = "3")]
+    pub trailers: BTreeMap<String, String>,
+    #[prost(message, optional, tag = "4")]
+    pub meta: Option<CommitMeta>,
+    #[prost(bool, tag = "5")]
+    pub outside_schedule: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct CommitMeta {
+    #[prost(message, optional, tag = "1")]
+    pub author: Option<Person>,
+    #[prost(message, optional, tag = "2")]
+    pub committer: Option<Person>,
+    #[prost(string, tag = "3")]
+    pub summary: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Person {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub email: String,
+}
+
+fn json_text(value: &impl serde::Serialize) -> String {
+    serde_json::to_string(value).expect("Failed to write json")
+}
+
+fn person(person: &record::Person) -> Person {
+    Person {
+        name: person.name.clone(),
+        email: person.email.clone(),
+    }
+}
+
+fn instant(instant: &record::ChangeInstant) -> ChangeInstant {
+    ChangeInstant {
+        commit: instant.commit.clone(),
+        timestamp: instant.timestamp,
+        trailers: instant.trailers.clone(),
+        meta: instant.meta.as_ref().map(|meta| CommitMeta {
+            author: Some(person(&meta.author)),
+            committer: Some(person(&meta.committer)),
+            summary: meta.summary.clone(),
+        }),
+        outside_schedule: instant.outside_schedule,
+    }
+}
+
+fn change(change: &record::Change) -> Change {
+    let values = change.values.as_ref();
+    Change {
+        instant: Some(instant(&change.instant)),
+        id: change.id.clone(),
+        captured: change
+            .captured
+            .iter()
+            .flatten()
+            .map(|(pointer, value)| (pointer.clone(), json_text(value)))
+            .collect(),
+        patch: change.patch.as_ref().map(json_text),
+        changed_fields: change.changed_fields.clone().unwrap_or_default(),
+        old: values.and_then(|values| values.old.as_ref()).map(json_text),
+        new: values.and_then(|values| values.new.as_ref()).map(json_text),
+    }
+}
+
+/// `changes`, stored newest first, in `order`.
+fn changes(changes: &[record::Change], order: ChangeOrder) -> Vec<Change> {
+    match order {
+        ChangeOrder::Asc => changes.iter().rev().map(change).collect(),
+        ChangeOrder::Desc => changes.iter().map(change).collect(),
+    }
+}
+
+/// The `Extraction` message of the change records of `extraction`.
+pub fn extraction(extraction: &ExtractionResult) -> Extraction {
+    let files = extraction
+        .change_records
+        .iter()
+        .sorted_by_key(|(path, _)| *path)
+        .map(|(path, records)| File {
+            path: path.to_string_lossy().into_owned(),
+            records: records
+                .iter()
+                .map(|(key, record)| {
+                    let record = ChangeRecord {
+                        added: changes(&record.added, record.order),
+                        removed: changes(&record.removed, record.order),
+                        modified: changes(&record.modified, record.order),
+                    };
+                    (key.clone(), record)
+                })
+                .collect(),
+        })
+        .collect();
+    Extraction { files }
+}
+
+/// Writes the `Extraction` message of `extraction` to `path`.
+pub fn write(path: &Path, extraction: &ExtractionResult) -> io::Result<()> {
+    fs::write(path, self::extraction(extraction).encode_to_vec())
+}
@@ -0,0 +1,160 @@
+//! Lookups over the changes of an extraction for library users, so that they don't have to walk
+//! [`Extraction::change_records`] themselves:
+//!
+//! ```no_run
+//! # use clap::Parser;
+//! # use delorean::extract::{self, ExtractArgs};
+//! # use delorean::query::ExtractionResult;
+//! let args = ExtractArgs::parse_from(["delorean", "repo", "out", "--primary-key", "crn"]);
+//! let result = ExtractionResult::new(extract::run(&args).unwrap());
+//! for change in result.history("12345") {
+//!     println!("{} {} {}", change.path.display(), change.change_type, change.change.instant.commit);
+//! }
+//! ```
+
+use crate::extract::Extraction;
+use crate::record::{Change, ChangeType};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A change together with the record it was made to.
+#[derive(Debug, Clone, Copy)]
+pub struct RecordChange<'a> {
+    pub path: &'a Path,
+    pub primary_key: &'a str,
+    pub change_type: ChangeType,
+    pub change: &'a Change,
+}
+
+/// Where a change is kept in the extraction.
+struct Entry {
+    /// Index into `files`
+    file: usize,
+    primary_key: String,
+    change_type: ChangeType,
+    /// Index into the record's changes of `change_type`
+    position: usize,
+    timestamp: i64,
+}
+
+/// An extraction indexed by primary key and by time. Changes are only kept in an extraction when
+/// it has somewhere to write them, e.g. an `<OUTPUT_PATH>`.
+pub struct ExtractionResult {
+    extraction: Extraction,
+    files: Vec<PathBuf>,
+    /// Every change, oldest first
+    entries: Vec<Entry>,
+    /// Indices into `entries` of the changes to each primary key, oldest first
+    by_key: HashMap<String, Vec<usize>>,
+}
+
+impl ExtractionResult {
+    pub fn new(extraction: Extraction) -> Self {
+        let mut files = extraction
+            .change_records
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        files.sort();
+        let mut entries = vec![];
+        for (file, path) in files.iter().enumerate() {
+            for (primary_key, record) in &extraction.change_records[path] {
+                for (change_type, changes) in [
+                    (ChangeType::Added, &record.added),
+                    (ChangeType::Removed, &record.removed),
+                    (ChangeType::Modified, &record.modified),
+                ] {
+                    for (position, change) in changes.iter().enumerate() {
+                        entries.push(Entry {
+                            file,
+                            primary_key: primary_key.clone(),
+                            change_type,
+                            position,
+                            timestamp: change.instant.timestamp,
+                        });
+                    }
+                }
+            }
+        }
+        // Changes are recorded newest first, so among changes at the same time, the one recorded
+        // later is the older.
+        entries.sort_by(|a, b| {
+            (a.timestamp, a.file, &a.primary_key)
+                .cmp(&(b.timestamp, b.file, &b.primary_key))
+                .then(b.position.cmp(&a.position))
+        });
+        let mut by_key = HashMap::<String, Vec<usize>>::new();
+        for (index, entry) in entries.iter().enumerate() {
+            by_key
+                .entry(entry.primary_key.clone())
+                .or_default()
+                .push(index);
+        }
+        ExtractionResult {
+            extraction,
+            files,
+            entries,
+            by_key,
+        }
+    }
+
+    /// The underlying extraction.
+    pub fn extraction(&self) -> &Extraction {
+        &self.extraction
+    }
+
+    /// The tracked files with recorded changes, in order.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Every change to records with this primary key, in any file, oldest first.
+    pub fn history(&self, primary_key: &str) -> impl Iterator<Item = RecordChange<'_>> {
+        self.by_key
+            .get(primary_key)
+            .into_iter()
+            .flatten()
+            .map(|&index| self.resolve(&self.entries[index]))
+    }
+
+    /// Every change from `since` (inclusive) to `until` (exclusive), in seconds since the epoch,
+    /// oldest first.
+    pub fn changes_between(
+        &self,
+        since: i64,
+        until: i64,
+    ) -> impl Iterator<Item = RecordChange<'_>> {
+        let start = self
+            .entries
+            .partition_point(|entry| entry.timestamp < since);
+        let end = self
+            .entries
+            .partition_point(|entry| entry.timestamp < until)
+            .max(start);
+        self.entries[start..end]
+            .iter()
+            .map(|entry| self.resolve(entry))
+    }
+
+    fn resolve<'a>(&'a self, entry: &'a Entry) -> RecordChange<'a> {
+        let path = &self.files[entry.file];
+        let record = &self.extraction.change_records[path][&entry.primary_key];
+        let changes = match entry.change_type {
+            ChangeType::Added => &record.added,
+            ChangeType::Removed => &record.removed,
+            ChangeType::Modified => &record.modified,
+        };
+        RecordChange {
+            path,
+            primary_key: &entry.primary_key,
+            change_type: entry.change_type,
+            change: &changes[entry.position],
+        }
+    }
+}
+
+impl From<Extraction> for ExtractionResult {
+    fn from(extraction: Extraction) -> Self {
+        ExtractionResult::new(extraction)
+    }
+}
@@ -0,0 +1,256 @@
+use crate::diff::PatchOperation;
+use chrono::format::StrftimeItems;
+use chrono::DateTime;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub struct ChangeInstant {
+    pub commit: String,
+    pub timestamp: i64,
+    /// `--trailer`s of the commit
+    pub trailers: BTreeMap<String, String>,
+    /// How `timestamp` is serialized
+    pub timestamp_format: TimestampFormat,
+    /// Under `--with-commit-meta`, flattened into the instant
+    pub meta: Option<CommitMeta>,
+    /// Whether the commit time is outside every `--flag-outside-schedule` window; serialized as
+    /// `"outside_schedule": true` only when it is
+    pub outside_schedule: bool,
+}
+
+impl Serialize for ChangeInstant {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let len = 2
+            + usize::from(!self.trailers.is_empty())
+            + if self.meta.is_some() { 3 } else { 0 }
+            + usize::from(self.outside_schedule);
+        let mut instant = serializer.serialize_struct("ChangeInstant", len)?;
+        instant.serialize_field("commit", &self.commit)?;
+        instant.serialize_field(
+            "timestamp",
+            &FormattedTimestamp(self.timestamp, &self.timestamp_format),
+        )?;
+        if self.trailers.is_empty() {
+            instant.skip_field("trailers")?;
+        } else {
+            instant.serialize_field("trailers", &self.trailers)?;
+        }
+        match &self.meta {
+            Some(meta) => {
+                instant.serialize_field("author", &meta.author)?;
+                instant.serialize_field("committer", &meta.committer)?;
+                instant.serialize_field("summary", &meta.summary)?;
+            }
+            None => {
+                for field in ["author", "committer", "summary"] {
+                    instant.skip_field(field)?;
+                }
+            }
+        }
+        if self.outside_schedule {
+            instant.serialize_field("outside_schedule", &true)?;
+        } else {
+            instant.skip_field("outside_schedule")?;
+        }
+        instant.end()
+    }
+}
+
+/// Who made a commit and what it says, attached to its change instants under
+/// `--with-commit-meta` so consumers can show "changed by" without opening the repository.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitMeta {
+    pub author: Person,
+    pub committer: Person,
+    /// First line of the commit message
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Person {
+    pub name: String,
+    pub email: String,
+}
+
+/// How the timestamps of changes are written, from `--timestamp-format`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// RFC 3339 in UTC, e.g. `2023-01-02T00:00:00+00:00`
+    #[default]
+    Rfc3339,
+    /// Seconds since the Unix epoch, as a number
+    Unix,
+    /// Milliseconds since the Unix epoch, as a number
+    UnixMillis,
+    /// A strftime pattern, in UTC
+    Strftime(String),
+}
+
+impl FromStr for TimestampFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rfc3339" => Ok(TimestampFormat::Rfc3339),
+            "unix" => Ok(TimestampFormat::Unix),
+            "unix-millis" => Ok(TimestampFormat::UnixMillis),
+            // A pattern without any specifier would write the same string for every change.
+            pattern if pattern.contains('%') && StrftimeItems::new(pattern).parse().is_ok() => {
+                Ok(TimestampFormat::Strftime(pattern.to_string()))
+            }
+            _ => Err(format!(
+                "Expected rfc3339, unix, unix-millis, or a strftime pattern such as %Y-%m-%d, \
+                 got {s}"
+            )),
+        }
+    }
+}
+
+/// A timestamp in seconds, serialized in a [`TimestampFormat`].
+pub(crate) struct FormattedTimestamp<'a>(pub(crate) i64, pub(crate) &'a TimestampFormat);
+
+impl Serialize for FormattedTimestamp<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let FormattedTimestamp(timestamp, format) = *self;
+        let dt = DateTime::from_timestamp(timestamp, 0).unwrap();
+        match format {
+            TimestampFormat::Rfc3339 => serializer.collect_str(&dt.format("%+")),
+            TimestampFormat::Unix => serializer.serialize_i64(timestamp),
+            TimestampFormat::UnixMillis => serializer.serialize_i64(timestamp * 1000),
+            TimestampFormat::Strftime(pattern) => serializer.collect_str(&dt.format(pattern)),
+        }
+    }
+}
+
+/// One change to a record: the instant, shared by all changes in the same commit, and the
+/// `--capture`d fields of the record's version at that instant.
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    /// The [`change_id`], under `--change-ids`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(flatten)]
+    pub instant: Arc<ChangeInstant>,
+    /// Captured values keyed by JSON pointer; fields missing from the record are left out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub captured: Option<BTreeMap<String, Value>>,
+    /// For modifications under `--patches`, the JSON Patch from the previous version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch: Option<Vec<PatchOperation>>,
+    /// For modifications under `--changed-fields`, the top-level fields whose values changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed_fields: Option<Vec<String>>,
+    /// Versions of the record around the change, under `--include-values`
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub values: Option<ChangeValues>,
+}
+
+/// Which versions of a record `--include-values` embeds in its changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum IncludeValues {
+    /// Only the top-level fields that changed, for modifications; whole records otherwise
+    Fields,
+    /// Whole records
+    Full,
+}
+
+/// The version of a record before a change (`old`, absent for additions) and after it (`new`,
+/// absent for removals).
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeValues {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<Value>,
+}
+
+/// The changes to one record, each list stored newest first and serialized in `order`.
+pub struct ChangeRecord {
+    pub added: Vec<Change>,
+    pub removed: Vec<Change>,
+    pub modified: Vec<Change>,
+    pub order: ChangeOrder,
+}
+
+impl Serialize for ChangeRecord {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut record = serializer.serialize_struct("ChangeRecord", 3)?;
+        record.serialize_field("added", &OrderedChanges(&self.added, self.order))?;
+        record.serialize_field("removed", &OrderedChanges(&self.removed, self.order))?;
+        record.serialize_field("modified", &OrderedChanges(&self.modified, self.order))?;
+        record.end()
+    }
+}
+
+/// The order of the `added`, `removed`, and `modified` lists of each record, from `--order`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeOrder {
+    /// Oldest first
+    #[default]
+    Asc,
+    /// Newest first
+    Desc,
+}
+
+/// Changes stored newest first, serialized in the given order.
+struct OrderedChanges<'a>(&'a [Change], ChangeOrder);
+
+impl Serialize for OrderedChanges<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.1 {
+            ChangeOrder::Asc => serializer.collect_seq(self.0.iter().rev()),
+            ChangeOrder::Desc => serializer.collect_seq(self.0),
+        }
+    }
+}
+
+/// The ID of the `change_type` change to the record `primary_key` of `path` in `commit`: the git
+/// blob hash (as `git hash-object` computes it) of the four, each followed by a NUL byte. It only
+/// depends on what changed, so repeated and incremental runs give a change the same ID.
+pub fn change_id(path: &Path, primary_key: &str, commit: &str, change_type: ChangeType) -> String {
+    let mut content = Vec::new();
+    for part in [
+        path.to_string_lossy().as_ref(),
+        primary_key,
+        commit,
+        &change_type.to_string(),
+    ] {
+        content.extend_from_slice(part.as_bytes());
+        content.push(0);
+    }
+    git2::Oid::hash_object(git2::ObjectType::Blob, &content)
+        .expect("Failed to hash change")
+        .to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeType {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl ChangeType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChangeType::Added => "added",
+            ChangeType::Removed => "removed",
+            ChangeType::Modified => "modified",
+        }
+    }
+}
+
+impl fmt::Display for ChangeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
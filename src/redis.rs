@@ -0,0 +1,44 @@
+//! Redis sink (`--redis redis://host/`): the change record of every record, as in the JSON
+//! output, stored as a string under `history:<path>:<primary key>`, so that a web backend can look
+//! up when a record changed without reading output files. Built with the `redis` feature.
+//!
+//! ```text
+//! GET history:parsed_courses/202301.json:12345
+//! {"added":[...],"removed":[...],"modified":[...]}
+//! ```
+
+use crate::extract::Extraction;
+use ::redis::RedisResult;
+use itertools::Itertools;
+use std::path::Path;
+
+/// Commands sent per pipeline, which bounds how much is buffered before a round trip.
+const BATCH_SIZE: usize = 1000;
+
+/// The key of the history of the record `primary_key` of `path`.
+pub fn key(path: &Path, primary_key: &str) -> String {
+    format!("history:{}:{primary_key}", path.display())
+}
+
+/// Stores the change record of every record in `extraction` in the Redis server at `url`,
+/// replacing any existing history of the same record.
+pub fn write(url: &str, extraction: &Extraction) -> RedisResult<()> {
+    let mut connection = ::redis::Client::open(url)?.get_connection()?;
+    let records = extraction
+        .change_records
+        .iter()
+        .flat_map(|(path, records)| {
+            records
+                .iter()
+                .map(move |(primary_key, record)| (path, primary_key, record))
+        });
+    for batch in &records.chunks(BATCH_SIZE) {
+        let mut pipeline = ::redis::pipe();
+        for (path, primary_key, record) in batch {
+            let history = serde_json::to_string(record).expect("Failed to serialize record");
+            pipeline.set(key(path, primary_key), history).ignore();
+        }
+        pipeline.exec(&mut connection)?;
+    }
+    Ok(())
+}
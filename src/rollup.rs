@@ -0,0 +1,81 @@
+//! Per-record counts of each change type, bucketed by calendar period (`--rollup`).
+
+use crate::record::{Change, ChangeRecord};
+use chrono::DateTime;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+pub enum RollupPeriod {
+    /// Buckets like 2024-09-01
+    Daily,
+    /// ISO weeks, like 2024-W36
+    Weekly,
+    /// Buckets like 2024-09
+    Monthly,
+    /// Buckets like 2024
+    Yearly,
+}
+
+impl RollupPeriod {
+    /// The bucket a timestamp falls into, in UTC.
+    pub fn bucket(self, timestamp: i64) -> String {
+        let format = match self {
+            RollupPeriod::Daily => "%Y-%m-%d",
+            RollupPeriod::Weekly => "%G-W%V",
+            RollupPeriod::Monthly => "%Y-%m",
+            RollupPeriod::Yearly => "%Y",
+        };
+        DateTime::from_timestamp(timestamp, 0)
+            .unwrap()
+            .format(format)
+            .to_string()
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ChangeCounts {
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+}
+
+/// Counts a record's changes per bucket, in chronological bucket order.
+pub fn rollup(record: &ChangeRecord, period: RollupPeriod) -> BTreeMap<String, ChangeCounts> {
+    let mut buckets: BTreeMap<String, ChangeCounts> = BTreeMap::new();
+    let mut count = |changes: &[Change], field: fn(&mut ChangeCounts) -> &mut usize| {
+        for change in changes {
+            *field(
+                buckets
+                    .entry(period.bucket(change.instant.timestamp))
+                    .or_default(),
+            ) += 1;
+        }
+    };
+    count(&record.added, |counts| &mut counts.added);
+    count(&record.removed, |counts| &mut counts.removed);
+    count(&record.modified, |counts| &mut counts.modified);
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2021-01-01T23:30:00Z, a Friday in the last ISO week of 2020
+    const NEW_YEARS_DAY: i64 = 1609543800;
+
+    #[test]
+    fn buckets_by_period_in_utc() {
+        assert_eq!(RollupPeriod::Daily.bucket(NEW_YEARS_DAY), "2021-01-01");
+        assert_eq!(RollupPeriod::Monthly.bucket(NEW_YEARS_DAY), "2021-01");
+        assert_eq!(RollupPeriod::Yearly.bucket(NEW_YEARS_DAY), "2021");
+    }
+
+    #[test]
+    fn weeks_are_iso_weeks_of_the_iso_year() {
+        assert_eq!(RollupPeriod::Weekly.bucket(NEW_YEARS_DAY), "2020-W53");
+        // 2024-09-02, a Monday
+        assert_eq!(RollupPeriod::Weekly.bucket(1725235200), "2024-W36");
+    }
+}
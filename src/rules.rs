@@ -0,0 +1,109 @@
+//! Notification rules (`--rules`): conditions on changes, such as "field /times_by_day changed"
+//! or "record removed where /school is YC", whose matches are streamed to `--rules-output`.
+//! A rule with `promote_to` also turns the changes it matches into events of a custom change type
+//! in the `--output` stream, such as `closed` for `/enrollment_status` going from `open` to
+//! `closed`.
+//!
+//! A rules file is a YAML list of rules:
+//!
+//! ```yaml
+//! - name: schedule-changed
+//!   change_type: modified
+//!   changed: /times_by_day
+//! - name: yc-course-removed
+//!   change_type: removed
+//!   where:
+//!     /school: YC
+//! - name: enrollment-closed
+//!   changed: /enrollment_status
+//!   from: open
+//!   to: closed
+//!   promote_to: closed
+//! ```
+
+use crate::record::ChangeType;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Identifies the rule in matched events
+    pub name: String,
+    /// Only match changes of this type
+    #[serde(default)]
+    pub change_type: Option<ChangeType>,
+    /// Only match modifications where the value at this JSON pointer differs
+    #[serde(default)]
+    pub changed: Option<String>,
+    /// Only match records whose values at these JSON pointers equal the given values. Removed
+    /// records are matched on their last version, others on their new version.
+    #[serde(default, rename = "where")]
+    pub conditions: HashMap<String, Value>,
+    /// Only match modifications where the value at `changed` was this before
+    #[serde(default)]
+    pub from: Option<Value>,
+    /// Only match modifications where the value at `changed` is this after
+    #[serde(default)]
+    pub to: Option<Value>,
+    /// The change type of matching changes in the `--output` stream, in place of their own. The
+    /// first matching rule with one wins.
+    #[serde(default)]
+    pub promote_to: Option<String>,
+}
+
+impl Rule {
+    /// Whether a change from `old` to `new` matches. `old` is `None` for additions, and `new`
+    /// is `None` for removals.
+    pub fn matches(
+        &self,
+        change_type: ChangeType,
+        old: Option<&Value>,
+        new: Option<&Value>,
+    ) -> bool {
+        if self
+            .change_type
+            .is_some_and(|expected| expected != change_type)
+        {
+            return false;
+        }
+        if let Some(pointer) = &self.changed {
+            let (Some(old), Some(new)) = (old, new) else {
+                return false;
+            };
+            let (old, new) = (old.pointer(pointer), new.pointer(pointer));
+            if old == new
+                || self.from.as_ref().is_some_and(|from| old != Some(from))
+                || self.to.as_ref().is_some_and(|to| new != Some(to))
+            {
+                return false;
+            }
+        }
+        let Some(record) = new.or(old) else {
+            return false;
+        };
+        self.conditions
+            .iter()
+            .all(|(pointer, expected)| record.pointer(pointer) == Some(expected))
+    }
+}
+
+pub fn load(path: &Path) -> io::Result<Vec<Rule>> {
+    let content = fs::read_to_string(path)?;
+    let rules: Vec<Rule> = serde_yaml::from_str(&content)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if let Some(rule) = rules
+        .iter()
+        .find(|rule| rule.changed.is_none() && (rule.from.is_some() || rule.to.is_some()))
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Rule {} has `from` or `to` without `changed`", rule.name),
+        ));
+    }
+    Ok(rules)
+}
@@ -0,0 +1,35 @@
+//! CourseTable-oriented summary of change counts per season and ISO week (`--season-summary`).
+
+use crate::extract::Extraction;
+use crate::rollup::{self, ChangeCounts, RollupPeriod};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The season of records in `path` that have no season field: the file name up to its first
+/// dot, so both `202301.json` and `202301.json.gz` give `202301`.
+fn path_season(path: &Path) -> String {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    file_name.split('.').next().unwrap_or_default().to_string()
+}
+
+/// Sums the weekly rollups of every record by season, in season and week order.
+pub fn season_summary(extraction: &Extraction) -> BTreeMap<String, BTreeMap<String, ChangeCounts>> {
+    let mut summary: BTreeMap<String, BTreeMap<String, ChangeCounts>> = BTreeMap::new();
+    for (path, records) in &extraction.change_records {
+        let path_seasons = extraction.seasons.get(path);
+        for (key, record) in records {
+            let season = path_seasons
+                .and_then(|seasons| seasons.get(key))
+                .cloned()
+                .unwrap_or_else(|| path_season(path));
+            let weeks = summary.entry(season).or_default();
+            for (week, counts) in rollup::rollup(record, RollupPeriod::Weekly) {
+                let total = weeks.entry(week).or_default();
+                total.added += counts.added;
+                total.removed += counts.removed;
+                total.modified += counts.modified;
+            }
+        }
+    }
+    summary
+}
@@ -0,0 +1,111 @@
+//! `delorean selftest`: builds a fixture repository covering additions, removals, modifications
+//! and file deletions, runs an extraction over it, and compares the result against the golden
+//! outputs in `src/selftest/golden`.
+
+use crate::extract::{self, ExtractArgs};
+use crate::fixtures::{self, FixtureCommit, FixtureRepo};
+use crate::output;
+use clap::Parser;
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+
+const GOLDEN: &[(&str, &str)] = &[
+    (
+        "output/parsed_courses/202301.json",
+        include_str!("selftest/golden/output/parsed_courses/202301.json"),
+    ),
+    (
+        "output/parsed_courses/202302.json",
+        include_str!("selftest/golden/output/parsed_courses/202302.json"),
+    ),
+    (
+        "graveyard/parsed_courses/202301.json",
+        include_str!("selftest/golden/graveyard/parsed_courses/202301.json"),
+    ),
+    (
+        "graveyard/parsed_courses/202302.json",
+        include_str!("selftest/golden/graveyard/parsed_courses/202302.json"),
+    ),
+];
+
+fn course(crn: &str, title: &str) -> serde_json::Value {
+    json!({ "crn": crn, "title": title, "school": "YC" })
+}
+
+/// Builds the fixture repository the golden outputs were generated from.
+pub fn build_fixture(path: &Path) -> Result<FixtureRepo, git2::Error> {
+    let mut fixture = FixtureRepo::init(path)?;
+    fixture.commit(FixtureCommit::new("Initial sync").write_json(
+        "parsed_courses/202301.json",
+        &json!([course("1", "A"), course("2", "B")]),
+    ))?;
+    fixture.commit(FixtureCommit::new("Sync 1").write_json(
+        "parsed_courses/202301.json",
+        &json!([course("1", "A2"), course("2", "B"), course("3", "C")]),
+    ))?;
+    fixture.commit(
+        FixtureCommit::new("Sync 2")
+            .write_json(
+                "parsed_courses/202301.json",
+                &json!([course("1", "A2"), course("3", "C")]),
+            )
+            .write_json("parsed_courses/202302.json", &json!([course("9", "Z")])),
+    )?;
+    // Commits by other authors are skipped by the selftest configuration.
+    fixture.commit(
+        FixtureCommit::new("Manual edit")
+            .author("Someone Else", "someone@delorean.invalid")
+            .write_json(
+                "parsed_courses/202301.json",
+                &json!([course("1", "manual"), course("3", "C")]),
+            ),
+    )?;
+    fixture.commit(FixtureCommit::new("Remove 202302").remove("parsed_courses/202302.json"))?;
+    fixture.commit(FixtureCommit::new("Sync 3").write_json(
+        "parsed_courses/202301.json",
+        &json!([course("1", "A3"), course("3", "C"), course("2", "B")]),
+    ))?;
+    fixture.commit(
+        FixtureCommit::new("Unrelated file").write("README.md", "Not tracked by the selftest\n"),
+    )?;
+    Ok(fixture)
+}
+
+/// Runs the selftest inside `work_dir`, which must be empty or not exist yet. The fixture,
+/// the actual output, and the golden output are left in `repo/`, `actual/`, and `expected/`
+/// for inspection. Returns a description of every mismatch.
+pub fn run(work_dir: &Path) -> Result<(), Vec<String>> {
+    let fixture = build_fixture(&work_dir.join("repo"))
+        .map_err(|e| vec![format!("Failed to build fixture: {e}")])?;
+    let actual_dir = work_dir.join("actual");
+    let args = ExtractArgs::parse_from([
+        "delorean",
+        fixture.path().to_str().unwrap(),
+        actual_dir.join("output").to_str().unwrap(),
+        "--primary-key",
+        "crn",
+        "--include",
+        "parsed_courses/*.json",
+        "--include-authors",
+        "fixture@delorean.invalid",
+        "--graveyard",
+        actual_dir.join("graveyard").to_str().unwrap(),
+        "--quiet",
+    ]);
+    let extraction = extract::run(&args).map_err(|report| vec![report.to_string()])?;
+    output::write(&args, &extraction);
+
+    let expected_dir = work_dir.join("expected");
+    for (path, content) in GOLDEN {
+        let path = expected_dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).expect("Failed to create directory");
+        fs::write(path, content).expect("Failed to write golden file");
+    }
+    let mismatches = fixtures::compare_dirs(&actual_dir, &expected_dir);
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(mismatches)
+    }
+}
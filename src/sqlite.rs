@@ -0,0 +1,118 @@
+//! SQLite output (`--output-format sqlite`): all change records in a single database, so the
+//! history can be queried with SQL. Built with the `sqlite` feature.
+//!
+//! ```sql
+//! CREATE TABLE files (id INTEGER PRIMARY KEY, path TEXT NOT NULL UNIQUE);
+//! CREATE TABLE records (
+//!     id INTEGER PRIMARY KEY,
+//!     file_id INTEGER NOT NULL REFERENCES files (id),
+//!     primary_key TEXT NOT NULL,
+//!     UNIQUE (file_id, primary_key)
+//! );
+//! CREATE TABLE changes (
+//!     record_id INTEGER NOT NULL REFERENCES records (id),
+//!     change_type TEXT NOT NULL, -- added, removed, or modified
+//!     commit_hash TEXT NOT NULL,
+//!     timestamp INTEGER NOT NULL, -- Unix seconds
+//!     trailers TEXT,             -- JSON object of `--trailer`s, if any
+//!     captured TEXT,             -- JSON object of `--capture`d values, if any
+//!     id TEXT                    -- under `--change-ids`
+//! );
+//! ```
+
+use crate::extract::Extraction;
+use crate::record::{Change, ChangeType};
+use itertools::Itertools;
+use rusqlite::{params, Connection};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE files (id INTEGER PRIMARY KEY, path TEXT NOT NULL UNIQUE);
+CREATE TABLE records (
+    id INTEGER PRIMARY KEY,
+    file_id INTEGER NOT NULL REFERENCES files (id),
+    primary_key TEXT NOT NULL,
+    UNIQUE (file_id, primary_key)
+);
+CREATE TABLE changes (
+    record_id INTEGER NOT NULL REFERENCES records (id),
+    change_type TEXT NOT NULL,
+    commit_hash TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    trailers TEXT,
+    captured TEXT,
+    id TEXT
+);
+CREATE INDEX changes_record ON changes (record_id);
+CREATE INDEX changes_commit ON changes (commit_hash);
+";
+
+fn to_sql_error(e: serde_json::Error) -> rusqlite::Error {
+    rusqlite::Error::ToSqlConversionFailure(Box::new(e))
+}
+
+/// Writes the change records of `extraction` to a new database at `path`, replacing any existing
+/// file. Files and records are inserted in path and key order, and changes oldest first.
+pub fn write(path: &Path, extraction: &Extraction) -> rusqlite::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+            panic!("Failed to remove {}: {e}", path.display())
+        }
+        _ => {}
+    }
+    let mut connection = Connection::open(path)?;
+    connection.execute_batch(SCHEMA)?;
+    let transaction = connection.transaction()?;
+    {
+        let mut insert_file = transaction.prepare("INSERT INTO files (path) VALUES (?1)")?;
+        let mut insert_record =
+            transaction.prepare("INSERT INTO records (file_id, primary_key) VALUES (?1, ?2)")?;
+        let mut insert_change = transaction.prepare(
+            "INSERT INTO changes (record_id, change_type, commit_hash, timestamp, trailers, \
+             captured, id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        for (path, records) in extraction
+            .change_records
+            .iter()
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+        {
+            insert_file.execute([path.to_string_lossy()])?;
+            let file_id = transaction.last_insert_rowid();
+            for (primary_key, record) in records.iter().sorted_by(|(a, _), (b, _)| a.cmp(b)) {
+                insert_record.execute(params![file_id, primary_key])?;
+                let record_id = transaction.last_insert_rowid();
+                let changes: [(ChangeType, &[Change]); 3] = [
+                    (ChangeType::Added, &record.added),
+                    (ChangeType::Removed, &record.removed),
+                    (ChangeType::Modified, &record.modified),
+                ];
+                for (change_type, changes) in changes {
+                    for change in changes.iter().rev() {
+                        let trailers = (!change.instant.trailers.is_empty())
+                            .then(|| serde_json::to_string(&change.instant.trailers))
+                            .transpose()
+                            .map_err(to_sql_error)?;
+                        let captured = change
+                            .captured
+                            .as_ref()
+                            .map(serde_json::to_string)
+                            .transpose()
+                            .map_err(to_sql_error)?;
+                        insert_change.execute(params![
+                            record_id,
+                            change_type.to_string(),
+                            change.instant.commit,
+                            change.instant.timestamp,
+                            trailers,
+                            captured,
+                            change.id,
+                        ])?;
+                    }
+                }
+            }
+        }
+    }
+    transaction.commit()
+}
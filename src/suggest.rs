@@ -0,0 +1,40 @@
+//! Suggestions for `--ignore-revs`: commits that touched tracked files but change no record once
+//! array order, number formatting, and field names are normalized away.
+
+use crate::errors::ErrorReport;
+use crate::extract::{self, ExtractArgs, Extraction};
+use crate::record::ChangeType;
+use chrono::DateTime;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Runs the extraction described by `args` with every normalization enabled and every record and
+/// change type tracked. The commits it reports as no-ops are the suggestions.
+pub fn run(args: &ExtractArgs) -> Result<Extraction, ErrorReport> {
+    let mut args = args.clone();
+    // A commit whose changes these would leave out still changes the data.
+    args.changes = vec![ChangeType::Added, ChangeType::Removed, ChangeType::Modified];
+    args.predicates.clear();
+    args.only_keys.clear();
+    args.sample = None;
+    args.ignore_array_order = true;
+    args.normalize_numbers = true;
+    args.ignore_field_names = true;
+    extract::run(&args)
+}
+
+/// Writes the suggested commits of `extraction` in the format read by `--ignore-revs-file`, each
+/// preceded by a comment with its date and summary.
+pub fn write_ignore_revs(path: &Path, extraction: &Extraction) -> io::Result<()> {
+    let mut content = String::new();
+    for commit in &extraction.noop_commits {
+        let date = DateTime::from_timestamp(commit.instant.timestamp, 0)
+            .map(|date| date.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        writeln!(content, "# {date} {}", commit.message).unwrap();
+        writeln!(content, "{}", commit.instant.commit).unwrap();
+    }
+    fs::write(path, content)
+}
@@ -0,0 +1,307 @@
+//! Interactive explorer (`delorean tui <OUTPUT_DIR>`), built with the `tui` feature: browse the
+//! JSON files of an output directory, search their primary keys, and view the timeline of a
+//! record, with the fields each modification changed where the output has them
+//! (`--changed-fields`, `--patches`, or `--include-values`).
+//!
+//! `Tab` switches between the file and record lists, `↑`/`↓` (or `k`/`j`) move, `/` searches the
+//! primary keys of the file, `Enter` keeps the search and `Esc` clears it, and `q` quits.
+
+use crate::diff::{self, DiffOptions};
+use crate::fixtures::list_files;
+use crate::record::ChangeType;
+use crate::{index, manifest, meta};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListState, Paragraph};
+use ratatui::Frame;
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The list that moves with `↑`/`↓`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    Files,
+    Records,
+}
+
+/// One change in a record's timeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineEntry {
+    pub timestamp: String,
+    pub commit: String,
+    pub change_type: ChangeType,
+    /// For modifications, the changed fields, if the output has them
+    pub fields: Vec<String>,
+}
+
+/// The state of the explorer: the selected file and record, and the search.
+pub struct Explorer {
+    dir: PathBuf,
+    files: Vec<PathBuf>,
+    file: usize,
+    records: Map<String, Value>,
+    query: String,
+    searching: bool,
+    keys: Vec<String>,
+    key: usize,
+    focus: Pane,
+}
+
+/// Orders timestamps written with any `--timestamp-format`: numbers by value, strings as text.
+fn compare_timestamps(a: &Value, b: &Value) -> Ordering {
+    match (a.as_i64(), b.as_i64()) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+/// The fields a modification changed: its `changed_fields`, the fields its `patch` touches, or
+/// those that differ between its `old` and `new` versions.
+fn changed_fields(change: &Value) -> Vec<String> {
+    if let Some(fields) = change["changed_fields"].as_array() {
+        return fields
+            .iter()
+            .filter_map(|field| field.as_str().map(str::to_string))
+            .collect();
+    }
+    if let Some(patch) = change["patch"].as_array() {
+        return patch
+            .iter()
+            .filter_map(|operation| operation["path"].as_str().map(str::to_string))
+            .collect();
+    }
+    match (&change["old"], &change["new"]) {
+        (Value::Null, _) | (_, Value::Null) => vec![],
+        (old, new) => diff::changed_fields(old, new, &DiffOptions::default()),
+    }
+}
+
+impl Explorer {
+    /// Opens the output directory `dir`, selecting its first file.
+    pub fn open(dir: &Path) -> io::Result<Explorer> {
+        let mut files = BTreeSet::new();
+        list_files(dir, dir, &mut files);
+        for name in [meta::FILE_NAME, manifest::FILE_NAME, index::FILE_NAME] {
+            files.remove(Path::new(name));
+        }
+        let mut explorer = Explorer {
+            dir: dir.to_path_buf(),
+            files: files
+                .into_iter()
+                .filter(|file| {
+                    file.extension()
+                        .is_some_and(|extension| extension == "json")
+                })
+                .collect(),
+            file: 0,
+            records: Map::new(),
+            query: String::new(),
+            searching: false,
+            keys: vec![],
+            key: 0,
+            focus: Pane::Files,
+        };
+        explorer.load_file()?;
+        Ok(explorer)
+    }
+
+    fn load_file(&mut self) -> io::Result<()> {
+        self.records = match self.files.get(self.file) {
+            Some(file) => {
+                serde_json::from_slice(&fs::read(self.dir.join(file))?).unwrap_or_default()
+            }
+            None => Map::new(),
+        };
+        self.filter_keys();
+        Ok(())
+    }
+
+    fn filter_keys(&mut self) {
+        let query = self.query.to_lowercase();
+        self.keys = self
+            .records
+            .keys()
+            .filter(|key| key.to_lowercase().contains(&query))
+            .cloned()
+            .collect();
+        self.key = 0;
+    }
+
+    /// The JSON files of the output directory, relative to it.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// The primary keys of the selected file that match the search.
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    pub fn selected_file(&self) -> Option<&Path> {
+        self.files.get(self.file).map(PathBuf::as_path)
+    }
+
+    pub fn selected_key(&self) -> Option<&str> {
+        self.keys.get(self.key).map(String::as_str)
+    }
+
+    /// The changes to the selected record, oldest first.
+    pub fn timeline(&self) -> Vec<TimelineEntry> {
+        let Some(record) = self.selected_key().and_then(|key| self.records.get(key)) else {
+            return vec![];
+        };
+        let mut changes = [
+            (ChangeType::Added, "added"),
+            (ChangeType::Removed, "removed"),
+            (ChangeType::Modified, "modified"),
+        ]
+        .into_iter()
+        .flat_map(|(change_type, field)| {
+            record[field]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(move |change| (change_type, change))
+        })
+        .collect::<Vec<_>>();
+        changes.sort_by(|(_, a), (_, b)| compare_timestamps(&a["timestamp"], &b["timestamp"]));
+        changes
+            .into_iter()
+            .map(|(change_type, change)| TimelineEntry {
+                timestamp: match &change["timestamp"] {
+                    Value::String(timestamp) => timestamp.clone(),
+                    timestamp => timestamp.to_string(),
+                },
+                commit: change["commit"].as_str().unwrap_or_default().to_string(),
+                change_type,
+                fields: match change_type {
+                    ChangeType::Modified => changed_fields(change),
+                    _ => vec![],
+                },
+            })
+            .collect()
+    }
+
+    /// Handles a key press. Returns `false` once the explorer should quit.
+    pub fn handle(&mut self, code: KeyCode) -> io::Result<bool> {
+        if self.searching {
+            match code {
+                KeyCode::Char(c) => self.query.push(c),
+                KeyCode::Backspace => {
+                    self.query.pop();
+                }
+                KeyCode::Enter => self.searching = false,
+                KeyCode::Esc => {
+                    self.searching = false;
+                    self.query.clear();
+                }
+                _ => return Ok(true),
+            }
+            self.filter_keys();
+            return Ok(true);
+        }
+        match (code, self.focus) {
+            (KeyCode::Char('q'), _) => return Ok(false),
+            (KeyCode::Tab, Pane::Files) | (KeyCode::Enter, Pane::Files) => {
+                self.focus = Pane::Records
+            }
+            (KeyCode::Tab, Pane::Records) => self.focus = Pane::Files,
+            (KeyCode::Char('/'), _) => {
+                self.focus = Pane::Records;
+                self.searching = true;
+            }
+            (KeyCode::Up | KeyCode::Char('k'), Pane::Files) if self.file > 0 => {
+                self.file -= 1;
+                self.load_file()?;
+            }
+            (KeyCode::Down | KeyCode::Char('j'), Pane::Files)
+                if self.file + 1 < self.files.len() =>
+            {
+                self.file += 1;
+                self.load_file()?;
+            }
+            (KeyCode::Up | KeyCode::Char('k'), Pane::Records) => {
+                self.key = self.key.saturating_sub(1)
+            }
+            (KeyCode::Down | KeyCode::Char('j'), Pane::Records)
+                if self.key + 1 < self.keys.len() =>
+            {
+                self.key += 1
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [files_area, records_area, timeline_area] = Layout::horizontal([
+            Constraint::Percentage(25),
+            Constraint::Percentage(20),
+            Constraint::Percentage(55),
+        ])
+        .areas(frame.area());
+        let highlight = |pane| {
+            if self.focus == pane {
+                Style::new().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::new().add_modifier(Modifier::BOLD)
+            }
+        };
+        let files = List::new(self.files.iter().map(|file| file.display().to_string()))
+            .block(Block::bordered().title("Files"))
+            .highlight_style(highlight(Pane::Files));
+        let mut files_state =
+            ListState::default().with_selected(self.selected_file().map(|_| self.file));
+        frame.render_stateful_widget(files, files_area, &mut files_state);
+
+        let title = match (self.searching, self.query.is_empty()) {
+            (true, _) => format!("Records /{}_", self.query),
+            (false, false) => format!("Records /{}", self.query),
+            (false, true) => "Records".to_string(),
+        };
+        let records = List::new(self.keys.iter().map(String::as_str))
+            .block(Block::bordered().title(title))
+            .highlight_style(highlight(Pane::Records));
+        let mut records_state =
+            ListState::default().with_selected(self.selected_key().map(|_| self.key));
+        frame.render_stateful_widget(records, records_area, &mut records_state);
+
+        let mut lines = vec![];
+        for entry in self.timeline() {
+            lines.push(Line::from(format!(
+                "{} {:<8} {}",
+                entry.timestamp, entry.change_type, entry.commit
+            )));
+            for field in entry.fields {
+                lines.push(Line::from(format!("    {field}")));
+            }
+        }
+        let title = self.selected_key().unwrap_or("Timeline").to_string();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(title)),
+            timeline_area,
+        );
+    }
+}
+
+/// Runs the explorer over the output directory `dir` until `q` is pressed.
+pub fn run(dir: &Path) -> io::Result<()> {
+    let mut explorer = Explorer::open(dir)?;
+    let mut terminal = ratatui::init();
+    let result = (|| loop {
+        terminal.draw(|frame| explorer.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && !explorer.handle(key.code)? {
+                return Ok(());
+            }
+        }
+    })();
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,192 @@
+//! `delorean verify`: replays a sample of the record histories in an output directory against
+//! the repository, to catch extraction bugs before the output is deployed.
+
+use crate::compare::{read_records, RecordEntry};
+use crate::diff::diff_records;
+use crate::errors::{ErrorBudget, ErrorReport, RecoverableError};
+use crate::extract::{self, ExtractArgs};
+use crate::fixtures::list_files;
+use crate::record::ChangeType;
+use crate::{index, manifest, meta};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// The outcome of `delorean verify`.
+#[derive(Debug)]
+pub struct Verification {
+    /// Number of change instants replayed
+    pub checked: usize,
+    /// Change instants that git does not bear out, as human-readable descriptions
+    pub mismatches: Vec<String>,
+    pub errors: Vec<RecoverableError>,
+    pub warnings: Vec<RecoverableError>,
+}
+
+/// Records of one file at one commit, keyed by primary key. `None` if the file is missing or
+/// could not be parsed.
+type Snapshot = Option<HashMap<String, serde_json::Value>>;
+
+struct Replayer<'a> {
+    repo: &'a git2::Repository,
+    args: &'a ExtractArgs,
+    keys: HashSet<String>,
+    snapshots: HashMap<(git2::Oid, PathBuf), Snapshot>,
+    errors: ErrorBudget,
+}
+
+impl Replayer<'_> {
+    /// The records of `path` at `commit`, restricted to the sampled keys.
+    fn snapshot(&mut self, commit: &git2::Commit, path: &Path) -> Result<&Snapshot, ErrorReport> {
+        let cache_key = (commit.id(), path.to_path_buf());
+        if !self.snapshots.contains_key(&cache_key) {
+            let tree = commit.tree().expect("Failed to get commit tree");
+            let snapshot = if tree.get_path(path).is_ok() {
+                extract::get_json_data(
+                    self.repo,
+                    &tree,
+                    path,
+                    self.args,
+                    Some(&self.keys),
+                    &commit.id().to_string(),
+                    &mut self.errors,
+                )?
+            } else {
+                None
+            };
+            self.snapshots.insert(cache_key.clone(), snapshot);
+        }
+        Ok(&self.snapshots[&cache_key])
+    }
+
+    /// The version of record `key` in `path` at `commit`, if it exists there.
+    fn version(
+        &mut self,
+        commit: &git2::Commit,
+        path: &Path,
+        key: &str,
+    ) -> Result<Option<serde_json::Value>, ErrorReport> {
+        Ok(self
+            .snapshot(commit, path)?
+            .as_ref()
+            .and_then(|records| records.get(key))
+            .cloned())
+    }
+
+    /// Checks one change instant of record `key` in `path`, returning why it is wrong, if it is.
+    fn check(
+        &mut self,
+        path: &Path,
+        key: &str,
+        change_type: ChangeType,
+        commit: &str,
+    ) -> Result<Option<String>, ErrorReport> {
+        let Some(commit) = git2::Oid::from_str(commit)
+            .ok()
+            .and_then(|oid| self.repo.find_commit(oid).ok())
+        else {
+            return Ok(Some(format!("{commit} is not a commit in the repository")));
+        };
+        let Ok(parent) = commit.parent(0) else {
+            return Ok(Some(format!("{} has no parent", commit.id())));
+        };
+        let before = self.version(&parent, path, key)?;
+        let after = self.version(&commit, path, key)?;
+        let diff_options = extract::diff_options(self.args);
+        let consistent = match change_type {
+            ChangeType::Added => before.is_none() && after.is_some(),
+            ChangeType::Removed => before.is_some() && after.is_none(),
+            ChangeType::Modified => match (&before, &after) {
+                (Some(before), Some(after)) => diff_records(before, after, &diff_options).changed,
+                _ => false,
+            },
+        };
+        Ok((!consistent).then(|| {
+            format!(
+                "{} {key}: {change_type} in {} is not borne out by git (present before: {}, after: {})",
+                path.display(),
+                commit.id(),
+                before.is_some(),
+                after.is_some(),
+            )
+        }))
+    }
+}
+
+/// The tracked path that an output file was written for. Compressed inputs lose their `.gz`
+/// extension in the output, so that is tried too.
+fn tracked_path(repo: &git2::Repository, output_file: &Path) -> PathBuf {
+    let head_tree = repo
+        .head()
+        .and_then(|head| head.peel_to_tree())
+        .expect("Failed to get HEAD tree");
+    let mut compressed = output_file.as_os_str().to_owned();
+    compressed.push(".gz");
+    let compressed = PathBuf::from(compressed);
+    if head_tree.get_path(output_file).is_err() && head_tree.get_path(&compressed).is_ok() {
+        compressed
+    } else {
+        output_file.to_path_buf()
+    }
+}
+
+/// Replays up to `sample` records of the output directory in `args`, spread evenly over all
+/// records in path and key order.
+///
+/// Panics if a file in the output directory is not a file of change records; the CLI rejects
+/// the options that write such files.
+pub fn run(args: &ExtractArgs, sample: usize) -> Result<Verification, ErrorReport> {
+    let output_dir = Path::new(
+        args.output_path
+            .as_deref()
+            .expect("An output directory is required"),
+    );
+    let mut files = BTreeSet::new();
+    list_files(output_dir, output_dir, &mut files);
+    files.remove(Path::new(meta::FILE_NAME));
+    files.remove(Path::new(manifest::FILE_NAME));
+    files.remove(Path::new(index::FILE_NAME));
+    let mut records: Vec<(PathBuf, String, RecordEntry)> = vec![];
+    for file in files {
+        let mut entries = read_records(&output_dir.join(&file))
+            .unwrap_or_else(|e| panic!("{e}"))
+            .into_iter()
+            .collect::<Vec<_>>();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        records.extend(
+            entries
+                .into_iter()
+                .map(|(key, record)| (file.clone(), key, record)),
+        );
+    }
+    let step = records.len().div_ceil(sample.max(1)).max(1);
+    let sampled = records.into_iter().step_by(step).collect::<Vec<_>>();
+
+    let repo = git2::Repository::open(&args.repo_path).expect("Failed to open repository");
+    let mut replayer = Replayer {
+        keys: sampled.iter().map(|(_, key, _)| key.clone()).collect(),
+        repo: &repo,
+        args,
+        snapshots: HashMap::new(),
+        errors: ErrorBudget::new(args.max_errors),
+    };
+    let mut checked = 0;
+    let mut mismatches = vec![];
+    for (file, key, record) in &sampled {
+        let path = tracked_path(&repo, file);
+        for change_type in [ChangeType::Added, ChangeType::Removed, ChangeType::Modified] {
+            for instant in record.instants(change_type) {
+                checked += 1;
+                if let Some(mismatch) = replayer.check(&path, key, change_type, &instant.commit)? {
+                    mismatches.push(mismatch);
+                }
+            }
+        }
+    }
+    let (errors, warnings) = replayer.errors.finish();
+    Ok(Verification {
+        checked,
+        mismatches,
+        errors,
+        warnings,
+    })
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use clap::Parser;
+use common::WorkDir;
+use delorean::extract::{self, ExtractArgs};
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use delorean::output;
+use serde_json::{json, Value};
+use std::fs;
+
+#[test]
+fn appending_a_later_run_matches_a_full_run() {
+    let work_dir = WorkDir::new("append");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("courses.json", &json!([])))
+        .unwrap();
+    let commit = |fixture: &mut FixtureRepo, i: usize, records: Value| {
+        fixture
+            .commit(FixtureCommit::new(format!("Sync {i}")).write_json("courses.json", &records))
+            .unwrap()
+    };
+    commit(&mut fixture, 1, json!([{ "crn": "1", "title": "Intro" }]));
+    let first_head = commit(
+        &mut fixture,
+        2,
+        json!([{ "crn": "1", "title": "Intro to CS" }]),
+    );
+    let repo_path = fixture.path().to_path_buf();
+    let extract_to = |output: &str, extra: &[&str]| {
+        let args = ExtractArgs::parse_from(
+            [
+                "delorean",
+                repo_path.to_str().unwrap(),
+                work_dir.join(output).to_str().unwrap(),
+                "--primary-key",
+                "crn",
+                "--include",
+                "*.json",
+                "--order",
+                "desc",
+                "--quiet",
+            ]
+            .iter()
+            .chain(extra),
+        );
+        output::write(&args, &extract::run(&args).unwrap());
+    };
+    extract_to("incremental", &[]);
+    commit(
+        &mut fixture,
+        3,
+        json!([
+            { "crn": "1", "title": "Intro to Computer Science" },
+            { "crn": "2", "title": "Data" },
+        ]),
+    );
+    commit(&mut fixture, 4, json!([{ "crn": "2", "title": "Data" }]));
+    let first_head = first_head.to_string();
+    extract_to("incremental", &["--append", "--until", &first_head]);
+    // Appending the same run again adds nothing
+    extract_to("incremental", &["--append", "--until", &first_head]);
+    extract_to("full", &[]);
+    let read =
+        |output: &str| fs::read_to_string(work_dir.join(output).join("courses.json")).unwrap();
+    let (incremental, full) = (read("incremental"), read("full"));
+
+    assert_eq!(incremental, full);
+    let full = serde_json::from_str::<Value>(&full).unwrap();
+    assert_eq!(full["1"]["modified"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn appending_merges_the_graveyard_noop_commits_and_presence_index() {
+    let work_dir = WorkDir::new("append-reports");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let repo_path = fixture.path().to_path_buf();
+    let mut commit = |i: usize, records: Value, pretty: bool| {
+        let content = if pretty {
+            serde_json::to_string_pretty(&records).unwrap()
+        } else {
+            records.to_string()
+        };
+        fixture
+            .commit(FixtureCommit::new(format!("Sync {i}")).write("courses.json", content))
+            .unwrap()
+    };
+    let (intro, data, stats) = (
+        json!({ "crn": "1", "title": "Intro" }),
+        json!({ "crn": "2", "title": "Data" }),
+        json!({ "crn": "3", "title": "Stats" }),
+    );
+    commit(0, json!([]), true);
+    commit(1, json!([intro, stats]), true);
+    // Reformatted only
+    commit(2, json!([intro, stats]), false);
+    let first_head = commit(3, json!([intro]), true).to_string();
+    let extract_to = |output: &str, extra: &[&str]| {
+        let output_dir = work_dir.join(output);
+        let reports = [
+            ("--graveyard", output_dir.join("graveyard")),
+            ("--noop-commits", output_dir.join("noop.json")),
+            ("--emit-presence-index", output_dir.join("presence")),
+        ];
+        let mut all = vec![
+            "delorean",
+            repo_path.to_str().unwrap(),
+            output_dir.to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--quiet",
+        ];
+        for (flag, path) in &reports {
+            all.extend([*flag, path.to_str().unwrap()]);
+        }
+        all.extend(extra);
+        let args = ExtractArgs::parse_from(all);
+        output::write(&args, &extract::run(&args).unwrap());
+    };
+    extract_to("incremental", &[]);
+    commit(4, json!([intro, data]), true);
+    commit(5, json!([intro, data]), false);
+    commit(6, json!([intro]), true);
+    extract_to("incremental", &["--append", "--until", &first_head]);
+    extract_to("full", &[]);
+    let read = |output: &str, path: &str| {
+        let content = fs::read_to_string(work_dir.join(output).join(path)).unwrap();
+        serde_json::from_str::<Value>(&content).unwrap()
+    };
+
+    // Entries read back from the earlier run have their fields in sorted order, so the files are
+    // compared as JSON
+    for path in [
+        "graveyard/courses.json",
+        "noop.json",
+        "presence/courses.json",
+    ] {
+        assert_eq!(read("incremental", path), read("full", path), "{path}");
+    }
+    assert_eq!(
+        read("full", "graveyard/courses.json"),
+        json!({ "2": data, "3": stats })
+    );
+    assert_eq!(read("full", "noop.json").as_array().unwrap().len(), 2);
+    assert!(read("full", "presence/courses.json")["1"]["commit"].is_string());
+}
+
+#[test]
+fn append_rejects_reports_of_the_run_only() {
+    for report in ["--record-counts", "--season-summary", "--group-output"] {
+        let result = ExtractArgs::try_parse_from([
+            "delorean",
+            "repo",
+            "out",
+            "--append",
+            "--group-by",
+            "dept",
+            report,
+            "report",
+        ]);
+        assert_eq!(
+            result.unwrap_err().kind(),
+            clap::error::ErrorKind::ArgumentConflict,
+            "{report}"
+        );
+    }
+}
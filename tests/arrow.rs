@@ -0,0 +1,53 @@
+#![cfg(feature = "arrow")]
+
+mod common;
+
+use arrow_array::{Array, StringArray};
+use arrow_ipc::reader::FileReader;
+use common::WorkDir;
+use delorean::extract;
+use delorean::output;
+use std::fs::File;
+
+#[test]
+fn arrow_output_has_a_row_per_change() {
+    let work_dir = WorkDir::new("arrow");
+    let fixture = work_dir.selftest_fixture();
+    let output_file = work_dir.join("changes.arrow");
+    let args = common::selftest_args(
+        &fixture,
+        &[output_file.to_str().unwrap(), "--output-format", "arrow"],
+    );
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    let reader = FileReader::try_new(File::open(&output_file).unwrap(), None).unwrap();
+    let batches = reader.map(|batch| batch.unwrap()).collect::<Vec<_>>();
+
+    let changes = extraction
+        .change_records
+        .values()
+        .flat_map(|records| records.values())
+        .map(|record| record.added.len() + record.removed.len() + record.modified.len())
+        .sum::<usize>();
+    assert_eq!(
+        batches.iter().map(|batch| batch.num_rows()).sum::<usize>(),
+        changes
+    );
+    let column = |name| {
+        batches[0]
+            .column_by_name(name)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap()
+            .value(0)
+            .to_string()
+    };
+    assert_eq!(column("path"), "parsed_courses/202301.json");
+    assert_eq!(column("primary_key"), "1");
+    assert_eq!(column("change_type"), "modified");
+    assert_eq!(
+        batches[0].column_by_name("timestamp").unwrap().null_count(),
+        0
+    );
+}
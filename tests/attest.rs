@@ -0,0 +1,57 @@
+mod common;
+
+use clap::Parser;
+use common::WorkDir;
+use delorean::extract::{self, ExtractArgs};
+use delorean::{attest, output};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use std::fs;
+
+fn unhex<const N: usize>(s: &str) -> [u8; N] {
+    let mut bytes = [0; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+    }
+    bytes
+}
+
+#[test]
+fn attestation_is_signed_and_hashes_the_output() {
+    let work_dir = WorkDir::new("attest");
+    let fixture = work_dir.selftest_fixture();
+    let key_path = work_dir.join("key.hex");
+    fs::write(&key_path, format!("{}\n", "2a".repeat(32))).unwrap();
+    let attest_path = work_dir.join("attestation.json");
+    let arguments = vec![
+        "delorean".to_string(),
+        fixture.path().to_str().unwrap().to_string(),
+        work_dir.join("output").to_str().unwrap().to_string(),
+        "--primary-key".to_string(),
+        "crn".to_string(),
+        "--include".to_string(),
+        "parsed_courses/*.json".to_string(),
+        "--attest".to_string(),
+        attest_path.to_str().unwrap().to_string(),
+        "--attest-key".to_string(),
+        key_path.to_str().unwrap().to_string(),
+        "--quiet".to_string(),
+    ];
+    let args = ExtractArgs::parse_from(&arguments);
+    output::write(&args, &extract::run(&args).unwrap());
+    attest::write(&args, arguments.clone()).unwrap();
+    let head = fixture.repository().head().unwrap().target().unwrap();
+    let attestation: serde_json::Value =
+        serde_json::from_slice(&fs::read(&attest_path).unwrap()).unwrap();
+
+    let payload = attestation["payload"].as_str().unwrap();
+    let public_key =
+        VerifyingKey::from_bytes(&unhex(attestation["public_key"].as_str().unwrap())).unwrap();
+    let signature = Signature::from_bytes(&unhex(attestation["signature"].as_str().unwrap()));
+    public_key
+        .verify(payload.as_bytes(), &signature)
+        .expect("Signature does not match the payload");
+    let statement: serde_json::Value = serde_json::from_str(payload).unwrap();
+    assert_eq!(statement["arguments"], serde_json::json!(arguments));
+    assert_eq!(statement["head"], head.to_string());
+    assert_eq!(statement["output"].as_str().unwrap().len(), 40);
+}
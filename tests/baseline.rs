@@ -0,0 +1,84 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn baseline_is_diffed_against_the_file_at_a_revision() {
+    let work_dir = WorkDir::new("baseline");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let first = fixture
+        .commit(FixtureCommit::new("Initial sync").write_json(
+            "courses.json",
+            &json!([{ "crn": "1", "seats": 1 }, { "crn": "2" }]),
+        ))
+        .unwrap()
+        .to_string();
+    fixture
+        .commit(FixtureCommit::new("Sync").write_json(
+            "courses.json",
+            &json!([{ "crn": "1", "seats": 2 }, { "crn": "2" }]),
+        ))
+        .unwrap();
+    let baseline = work_dir.join("scraped.json");
+    fs::write(
+        &baseline,
+        json!([{ "crn": "1", "seats": 2 }, { "crn": "3" }]).to_string(),
+    )
+    .unwrap();
+    let changes = |rev: &str| {
+        let extraction = extract::run(&common::extract_args(
+            &fixture,
+            &[
+                work_dir.join("output").to_str().unwrap(),
+                "--primary-key",
+                "crn",
+                "--baseline",
+                baseline.to_str().unwrap(),
+                "--baseline-path",
+                "courses.json",
+                "--baseline-rev",
+                rev,
+                "--quiet",
+            ],
+        ))
+        .unwrap();
+        let records = &extraction.change_records[Path::new("courses.json")];
+        let mut changes = records
+            .iter()
+            .flat_map(|(key, record)| {
+                [
+                    ("added", &record.added),
+                    ("removed", &record.removed),
+                    ("modified", &record.modified),
+                ]
+                .into_iter()
+                .flat_map(move |(change_type, changes)| {
+                    changes.iter().map(move |change| {
+                        assert_eq!(change.instant.commit, "baseline");
+                        (key.clone(), change_type)
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        changes.sort();
+        changes
+    };
+
+    assert_eq!(
+        changes("HEAD"),
+        [("2".to_string(), "removed"), ("3".to_string(), "added")]
+    );
+    assert_eq!(
+        changes(&first),
+        [
+            ("1".to_string(), "modified"),
+            ("2".to_string(), "removed"),
+            ("3".to_string(), "added"),
+        ]
+    );
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::{bigquery, output};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+
+#[test]
+fn rows_are_flat_and_match_the_schema() {
+    let work_dir = WorkDir::new("bigquery");
+    let fixture = work_dir.selftest_fixture();
+    let rows_path = work_dir.join("changes.ndjson");
+    let args = common::selftest_args(
+        &fixture,
+        &[
+            rows_path.to_str().unwrap(),
+            "--output-format",
+            "bigquery",
+            "--timestamp-format",
+            "unix",
+            "--change-ids",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    let rows = fs::read_to_string(&rows_path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    let schema: Value =
+        serde_json::from_slice(&fs::read(bigquery::schema_path(&rows_path)).unwrap()).unwrap();
+
+    assert_eq!(schema, bigquery::schema());
+    let columns = schema
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|field| field["name"].as_str().unwrap())
+        .collect::<HashSet<_>>();
+    assert_eq!(rows.len(), output::change_rows(&extraction).count());
+    for row in &rows {
+        for (column, value) in row.as_object().unwrap() {
+            assert!(
+                columns.contains(column.as_str()),
+                "{column} is not in the schema"
+            );
+            // Nothing nests deeper than the trailers' key-value records
+            if let Value::Array(values) = value {
+                assert!(values
+                    .iter()
+                    .flat_map(|value| value.as_object().into_iter().flat_map(|v| v.values()))
+                    .all(Value::is_string));
+            }
+        }
+        assert!(row["id"].is_string());
+        // RFC 3339 regardless of --timestamp-format
+        let timestamp = row["timestamp"].as_str().unwrap();
+        assert!(
+            chrono::DateTime::parse_from_rfc3339(timestamp).is_ok(),
+            "{timestamp}"
+        );
+    }
+}
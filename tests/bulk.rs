@@ -0,0 +1,67 @@
+mod common;
+
+use clap::Parser;
+use common::WorkDir;
+use delorean::extract::{self, ExtractArgs};
+use delorean::output;
+use serde_json::Value;
+use std::fs;
+
+#[test]
+fn bulk_output_pairs_an_action_with_each_change() {
+    let work_dir = WorkDir::new("bulk");
+    let fixture = work_dir.selftest_fixture();
+    let bulk_path = work_dir.join("changes.ndjson");
+    let lines = |extra: &[&str]| {
+        let mut arguments = vec![
+            "delorean",
+            fixture.path().to_str().unwrap(),
+            bulk_path.to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "parsed_courses/*.json",
+            "--output-format",
+            "elasticsearch",
+            "--quiet",
+        ];
+        arguments.extend(extra);
+        let args = ExtractArgs::parse_from(arguments);
+        let extraction = extract::run(&args).unwrap();
+        output::write(&args, &extraction);
+        let changes = output::change_rows(&extraction).count();
+        let lines = fs::read_to_string(&bulk_path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2 * changes);
+        lines
+    };
+    let with_ids = lines(&["--change-ids", "--bulk-index", "courses-{{change_type}}"]);
+    let templated = lines(&["--bulk-id", "{{commit}}-{{primary_key}}"]);
+
+    for pair in with_ids.chunks(2) {
+        let (action, document) = (&pair[0]["index"], &pair[1]);
+        assert_eq!(
+            action["_index"],
+            format!("courses-{}", document["change_type"].as_str().unwrap())
+        );
+        assert_eq!(action["_id"], document["id"]);
+        assert!(document["path"]
+            .as_str()
+            .unwrap()
+            .starts_with("parsed_courses/"));
+        assert!(document["timestamp"].is_string());
+    }
+    let (action, document) = (&templated[0]["index"], &templated[1]);
+    assert_eq!(action["_index"], "delorean-changes");
+    assert_eq!(
+        action["_id"],
+        format!(
+            "{}-{}",
+            document["commit"].as_str().unwrap(),
+            document["primary_key"].as_str().unwrap()
+        )
+    );
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::WorkDir;
+use delorean::calendar::{self, UnexpectedReason, Window};
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::fs;
+
+/// 2024-09-02, a Monday, at midnight UTC
+const MONDAY: i64 = 1725235200;
+const HOUR: i64 = 3600;
+const DAY: i64 = 24 * HOUR;
+
+#[test]
+fn missing_syncs_and_unexpected_changes_are_reported() {
+    let work_dir = WorkDir::new("calendar");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Initial sync")
+                .at(MONDAY - DAY)
+                .write_json("courses.json", &json!([])),
+        )
+        .unwrap();
+    // Monday 02:30, a sync that changed no records
+    fixture
+        .commit(
+            FixtureCommit::new("Reformat")
+                .at(MONDAY + 5 * HOUR / 2)
+                .write("courses.json", "[ ]\n"),
+        )
+        .unwrap();
+    let versions = [
+        // Wednesday 02:30, after no sync on Tuesday
+        (MONDAY + 2 * DAY + 5 * HOUR / 2, json!([{ "crn": "1" }])),
+        // Friday 02:30, a holiday
+        (
+            MONDAY + 4 * DAY + 5 * HOUR / 2,
+            json!([{ "crn": "1" }, { "crn": "2" }]),
+        ),
+        // Saturday noon, a manual fix
+        (
+            MONDAY + 5 * DAY + 12 * HOUR,
+            json!([{ "crn": "1", "title": "Intro" }, { "crn": "2" }]),
+        ),
+    ];
+    for (i, (time, version)) in versions.iter().enumerate() {
+        fixture
+            .commit(
+                FixtureCommit::new(format!("Sync {i}"))
+                    .at(*time)
+                    .write_json("courses.json", version),
+            )
+            .unwrap();
+    }
+    let calendar_path = work_dir.join("calendar.yaml");
+    fs::write(
+        &calendar_path,
+        "windows:\n  - Mon-Fri 02:00-04:00\nblackouts:\n  - 2024-09-06\n",
+    )
+    .unwrap();
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--calendar",
+            calendar_path.to_str().unwrap(),
+            "--calendar-report",
+            work_dir.join("report.json").to_str().unwrap(),
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+    let calendar = calendar::load(&calendar_path).unwrap();
+
+    let report = calendar::report(&calendar, &extraction);
+    assert_eq!(
+        report.missing_syncs,
+        [DAY, 3 * DAY].map(|day| Window {
+            start: MONDAY + day + 2 * HOUR,
+            end: MONDAY + day + 4 * HOUR,
+        })
+    );
+    let unexpected = report
+        .unexpected_changes
+        .iter()
+        .map(|change| (change.instant.timestamp, &change.reason))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        unexpected,
+        [
+            (versions[1].0, &UnexpectedReason::Blackout),
+            (versions[2].0, &UnexpectedReason::OutsideWindows),
+        ]
+    );
+}
+
+#[test]
+fn invalid_calendars_are_rejected() {
+    let work_dir = WorkDir::new("calendar-invalid");
+    let path = work_dir.join("calendar.yaml");
+    fs::write(&path, "windows:\n  - Mon-Fri\n").unwrap();
+    let invalid_window = calendar::load(&path);
+    fs::write(&path, "windows: []\nblackouts:\n  - 2024-12-23..Jan 1\n").unwrap();
+    let invalid_blackout = calendar::load(&path);
+
+    assert!(invalid_window.is_err());
+    assert!(invalid_blackout.is_err());
+}
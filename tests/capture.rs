@@ -0,0 +1,75 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[test]
+fn captured_fields_are_taken_from_the_version_at_each_change() {
+    let work_dir = WorkDir::new("capture");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let mut sync = |message: &str, records: Value| {
+        fixture
+            .commit(FixtureCommit::new(message).write_json("courses.json", &records))
+            .unwrap();
+    };
+    sync("Initial sync", json!([]));
+    sync(
+        "Add",
+        json!([{ "crn": "1", "title": "Intro", "meta": { "seats": 1 } }]),
+    );
+    sync(
+        "Modify",
+        json!([{ "crn": "1", "title": "Intro to CS", "meta": { "seats": 1 } }]),
+    );
+    // The title is missing from the last version, so it is not captured on removal
+    sync(
+        "Drop title",
+        json!([{ "crn": "1", "meta": { "seats": 2 } }]),
+    );
+    sync("Remove", json!([]));
+    let extraction = extract::run(&common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--capture",
+            "/title",
+            "--capture",
+            "/meta/seats",
+            "--quiet",
+        ],
+    ))
+    .unwrap();
+    let record = &extraction.change_records[Path::new("courses.json")]["1"];
+    let captured = |changes: &[delorean::record::Change]| {
+        changes
+            .iter()
+            .map(|change| change.captured.clone().unwrap())
+            .collect::<Vec<_>>()
+    };
+    let fields = |fields: Value| serde_json::from_value::<BTreeMap<String, Value>>(fields).unwrap();
+
+    assert_eq!(
+        captured(&record.added),
+        [fields(json!({ "/title": "Intro", "/meta/seats": 1 }))]
+    );
+    // Newest first
+    assert_eq!(
+        captured(&record.modified),
+        [
+            fields(json!({ "/meta/seats": 2 })),
+            fields(json!({ "/title": "Intro to CS", "/meta/seats": 1 })),
+        ]
+    );
+    assert_eq!(
+        captured(&record.removed),
+        [fields(json!({ "/meta/seats": 2 }))]
+    );
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::output;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Read;
+
+#[test]
+fn cdn_mode_writes_brotli_files_named_after_their_content() {
+    let work_dir = WorkDir::new("cdn-mode");
+    let fixture = work_dir.selftest_fixture();
+    let extract_to = |output: &str, extra: &[&str]| {
+        let output_dir = work_dir.join(output);
+        let mut arguments = vec![output_dir.to_str().unwrap()];
+        arguments.extend(extra);
+        let args = common::selftest_args(&fixture, &arguments);
+        output::write(&args, &extract::run(&args).unwrap());
+        output_dir
+    };
+    let plain_dir = extract_to("plain", &[]);
+    let cdn_dir = extract_to("cdn", &["--cdn-mode"]);
+    let manifest = fs::read_to_string(cdn_dir.join("manifest.json")).unwrap();
+    let manifest = serde_json::from_str::<Value>(&manifest).unwrap();
+    let manifest = manifest.as_object().unwrap();
+
+    assert!(!manifest.is_empty());
+    for (path, cdn_path) in manifest {
+        let cdn_path = cdn_path.as_str().unwrap();
+        assert!(cdn_path.ends_with(".json.br"), "{cdn_path}");
+        let mut content = vec![];
+        brotli::Decompressor::new(fs::File::open(cdn_dir.join(cdn_path)).unwrap(), 4096)
+            .read_to_end(&mut content)
+            .unwrap();
+        // Minified
+        assert!(!content.contains(&b'\n'));
+        let plain = fs::read_to_string(plain_dir.join(path)).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&content).unwrap(),
+            serde_json::from_str::<Value>(&plain).unwrap(),
+            "{path}"
+        );
+        let hash = git2::Oid::hash_object(git2::ObjectType::Blob, &content).unwrap();
+        assert!(cdn_path.contains(&hash.to_string()[..12]), "{cdn_path}");
+    }
+}
+
+#[test]
+fn cdn_mode_removes_files_of_the_previous_run() {
+    let work_dir = WorkDir::new("cdn-mode-rerun");
+    let fixture = work_dir.selftest_fixture();
+    let cdn_dir = work_dir.join("cdn");
+    let extract = |extra: &[&str]| {
+        let mut arguments = vec![cdn_dir.to_str().unwrap(), "--cdn-mode"];
+        arguments.extend(extra);
+        let args = common::selftest_args(&fixture, &arguments);
+        output::write(&args, &extract::run(&args).unwrap());
+    };
+    let cdn_files = || {
+        fs::read_dir(cdn_dir.join("parsed_courses"))
+            .unwrap()
+            .map(|entry| {
+                format!(
+                    "parsed_courses/{}",
+                    entry.unwrap().file_name().to_str().unwrap()
+                )
+            })
+            .collect::<BTreeSet<_>>()
+    };
+    extract(&[]);
+    let first_run = cdn_files();
+    // Only additions, so every file's content and name changes.
+    extract(&["--changes", "added"]);
+    let manifest = fs::read_to_string(cdn_dir.join("manifest.json")).unwrap();
+    let manifest = serde_json::from_str::<BTreeMap<String, String>>(&manifest).unwrap();
+
+    assert!(!first_run.is_empty());
+    assert!(first_run.is_disjoint(&manifest.values().cloned().collect()));
+    assert_eq!(cdn_files(), manifest.into_values().collect());
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use clap::Parser;
+use common::WorkDir;
+use delorean::extract::{self, ExtractArgs};
+use delorean::output;
+use delorean::record::{change_id, ChangeType};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn change_ids_are_stable_and_unique() {
+    let work_dir = WorkDir::new("change-ids");
+    let fixture = work_dir.selftest_fixture();
+    let args_for = |output_path: &Path, extra: &[&str]| {
+        let mut arguments = vec![
+            "delorean",
+            fixture.path().to_str().unwrap(),
+            output_path.to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "parsed_courses/*.json",
+            "--change-ids",
+            "--quiet",
+        ];
+        arguments.extend(extra);
+        ExtractArgs::parse_from(arguments)
+    };
+    let csv_file = work_dir.join("changes.csv");
+    let args = args_for(&csv_file, &["--output-format", "csv"]);
+    output::write(&args, &extract::run(&args).unwrap());
+    let rerun_file = work_dir.join("rerun.csv");
+    let args = args_for(&rerun_file, &["--output-format", "csv"]);
+    output::write(&args, &extract::run(&args).unwrap());
+    let output_dir = work_dir.join("output");
+    let args = args_for(&output_dir, &[]);
+    output::write(&args, &extract::run(&args).unwrap());
+    let csv = fs::read_to_string(&csv_file).unwrap();
+    let rerun = fs::read_to_string(&rerun_file).unwrap();
+    let records: serde_json::Value =
+        serde_json::from_slice(&fs::read(output_dir.join("parsed_courses/202301.json")).unwrap())
+            .unwrap();
+
+    assert_eq!(csv, rerun);
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("path,primary_key,change_type,commit,iso_timestamp,id")
+    );
+    let ids = lines
+        .map(|line| line.rsplit(',').next().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(ids.iter().collect::<HashSet<_>>().len(), ids.len());
+    let added = &records["2"]["added"][0];
+    let id = change_id(
+        Path::new("parsed_courses/202301.json"),
+        "2",
+        added["commit"].as_str().unwrap(),
+        ChangeType::Added,
+    );
+    assert_eq!(added["id"], id);
+    assert!(ids.contains(&id.as_str()));
+}
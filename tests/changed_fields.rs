@@ -0,0 +1,69 @@
+mod common;
+
+use clap::Parser;
+use common::WorkDir;
+use delorean::extract::{self, ExtractArgs};
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn modifications_list_changed_top_level_fields() {
+    let work_dir = WorkDir::new("changed-fields");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("courses.json", &json!([])))
+        .unwrap();
+    let versions = [
+        json!([{
+            "crn": "1",
+            "title": "Intro to CS",
+            "times_summary": "MW 1-2",
+            "professors": [{ "name": "A" }],
+            "last_updated": 1
+        }]),
+        json!([{
+            "crn": "1",
+            "title": "Intro to CS",
+            "times_summary": "TTh 1-2",
+            "professors": [{ "name": "B" }],
+            "location": "Room 101",
+            "last_updated": 2
+        }]),
+    ];
+    for (i, version) in versions.iter().enumerate() {
+        fixture
+            .commit(FixtureCommit::new(format!("Sync {i}")).write_json("courses.json", version))
+            .unwrap();
+    }
+    let output_dir = work_dir.join("output");
+    let record = |extra: &[&str]| {
+        let mut arguments = vec![
+            "delorean",
+            fixture.path().to_str().unwrap(),
+            output_dir.to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--ignore-field",
+            "/last_updated",
+            "--quiet",
+        ];
+        arguments.extend(extra);
+        let extraction = extract::run(&ExtractArgs::parse_from(arguments)).unwrap();
+        let record = &extraction.change_records[Path::new("courses.json")]["1"];
+        serde_json::to_value(record).unwrap()
+    };
+    let with_fields = record(&["--changed-fields"]);
+    let without_fields = record(&[]);
+
+    assert_eq!(
+        with_fields["modified"][0]["changed_fields"],
+        json!(["location", "professors", "times_summary"])
+    );
+    assert!(with_fields["added"][0].get("changed_fields").is_none());
+    assert!(without_fields["modified"][0]
+        .get("changed_fields")
+        .is_none());
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn changes_keeps_only_the_requested_change_types() {
+    let work_dir = WorkDir::new("changes");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let mut sync = |message: &str, records: serde_json::Value| {
+        fixture
+            .commit(FixtureCommit::new(message).write_json("courses.json", &records))
+            .unwrap();
+    };
+    sync("Initial sync", json!([{ "crn": "1", "seats": 1 }]));
+    sync("Add", json!([{ "crn": "1", "seats": 1 }, { "crn": "2" }]));
+    sync(
+        "Modify",
+        json!([{ "crn": "1", "seats": 2 }, { "crn": "2" }]),
+    );
+    sync("Remove", json!([{ "crn": "1", "seats": 2 }]));
+    let output_dir = work_dir.join("output");
+    let counts = |changes: &str| {
+        let extraction = extract::run(&common::extract_args(
+            &fixture,
+            &[
+                output_dir.to_str().unwrap(),
+                "--primary-key",
+                "crn",
+                "--include",
+                "*.json",
+                "--changes",
+                changes,
+                "--quiet",
+            ],
+        ))
+        .unwrap();
+        let mut counts = extraction.change_records[Path::new("courses.json")]
+            .iter()
+            .map(|(key, record)| {
+                let counts = (
+                    record.added.len(),
+                    record.removed.len(),
+                    record.modified.len(),
+                );
+                (key.clone(), counts)
+            })
+            .collect::<Vec<_>>();
+        counts.sort();
+        counts
+    };
+
+    assert_eq!(
+        counts("added,removed,modified"),
+        [("1".to_string(), (0, 0, 1)), ("2".to_string(), (1, 1, 0))]
+    );
+    // Records left without changes are left out
+    assert_eq!(counts("added,removed"), [("2".to_string(), (1, 1, 0))]);
+    assert_eq!(counts("modified"), [("1".to_string(), (0, 0, 1))]);
+}
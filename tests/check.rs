@@ -0,0 +1,51 @@
+mod common;
+
+use common::WorkDir;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn check_fails_when_too_many_records_would_be_removed() {
+    let work_dir = WorkDir::new("check");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let courses = (1..=4)
+        .map(|crn| json!({ "crn": crn.to_string() }))
+        .collect::<Vec<_>>();
+    fixture
+        .commit(FixtureCommit::new("Sync").write_json("courses.json", &json!(courses)))
+        .unwrap();
+    // Half of the records are gone from the working tree, but nothing is staged yet
+    fs::write(
+        fixture.path().join("courses.json"),
+        json!(courses[..2]).to_string(),
+    )
+    .unwrap();
+    let check = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_delorean"))
+            .args([
+                "check",
+                fixture.path().to_str().unwrap(),
+                "--primary-key",
+                "crn",
+                "--include",
+                "*.json",
+            ])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let working_tree = check(&[]);
+    assert!(!working_tree.status.success());
+    let report = String::from_utf8(working_tree.stderr).unwrap();
+    assert!(report.contains("courses.json"), "{report}");
+    assert!(check(&["--max-removed-percent", "60"]).status.success());
+    assert!(check(&["--staged"]).status.success());
+    let mut index = fixture.repository().index().unwrap();
+    index.add_path(Path::new("courses.json")).unwrap();
+    index.write().unwrap();
+    assert!(!check(&["--staged"]).status.success());
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use delorean::output;
+use serde_json::{json, Map, Value};
+use std::fs;
+
+#[test]
+fn large_files_are_split_into_indexed_chunks() {
+    let work_dir = WorkDir::new("chunk-size");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let courses = (10..60)
+        .map(|crn| json!({ "crn": crn.to_string(), "title": "Intro" }))
+        .collect::<Vec<_>>();
+    fixture
+        .commit(
+            FixtureCommit::new("Initial sync")
+                .write_json("courses.json", &json!([]))
+                .write_json("seasons.json", &json!([])),
+        )
+        .unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Sync")
+                .write_json("courses.json", &json!(courses))
+                .write_json("seasons.json", &json!([{ "crn": "1" }])),
+        )
+        .unwrap();
+    let extract_to = |output: &str, extra: &[&str]| {
+        let output_dir = work_dir.join(output);
+        let mut arguments = vec![
+            output_dir.to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--quiet",
+        ];
+        arguments.extend(extra);
+        let args = common::extract_args(&fixture, &arguments);
+        output::write(&args, &extract::run(&args).unwrap());
+        output_dir
+    };
+    let whole_dir = extract_to("whole", &[]);
+    let chunked_dir = extract_to("chunked", &["--chunk-size", "2000"]);
+    let read = |path: &std::path::Path| {
+        serde_json::from_str::<Value>(&fs::read_to_string(path).unwrap()).unwrap()
+    };
+
+    let index = read(&chunked_dir.join("courses.json.index"));
+    let index = index.as_array().unwrap();
+    assert!(index.len() > 1);
+    let mut joined = Map::new();
+    for entry in index {
+        let chunk_path = chunked_dir.join(entry["chunk"].as_str().unwrap());
+        assert!(fs::metadata(&chunk_path).unwrap().len() <= 2000);
+        let chunk = read(&chunk_path);
+        let chunk = chunk.as_object().unwrap();
+        assert_eq!(chunk.keys().next().unwrap(), &entry["first_key"]);
+        assert_eq!(chunk.keys().next_back().unwrap(), &entry["last_key"]);
+        // Chunks cover consecutive key ranges
+        assert!(joined
+            .keys()
+            .next_back()
+            .is_none_or(|last| last.as_str() < entry["first_key"].as_str().unwrap()));
+        joined.extend(chunk.clone());
+    }
+    assert_eq!(Value::Object(joined), read(&whole_dir.join("courses.json")));
+    assert!(!chunked_dir.join("courses.json").exists());
+    // Files within the chunk size are written whole
+    assert_eq!(
+        read(&chunked_dir.join("seasons.json")),
+        read(&whole_dir.join("seasons.json"))
+    );
+    assert!(!chunked_dir.join("seasons.json.index").exists());
+}
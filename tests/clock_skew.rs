@@ -0,0 +1,76 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn commits_dated_before_their_parent_are_kept_clamped_or_reported() {
+    let work_dir = WorkDir::new("clock-skew");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let parent_time = 1672531200;
+    fixture
+        .commit(
+            FixtureCommit::new("Initial sync")
+                .at(parent_time - 60)
+                .write_json("courses.json", &json!([])),
+        )
+        .unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Add")
+                .at(parent_time)
+                .write_json("courses.json", &json!([{ "crn": "1", "seats": 1 }])),
+        )
+        .unwrap();
+    let skewed = fixture
+        .commit(
+            FixtureCommit::new("Modify")
+                .at(parent_time - 3600)
+                .write_json("courses.json", &json!([{ "crn": "1", "seats": 2 }])),
+        )
+        .unwrap()
+        .to_string();
+    let output_dir = work_dir.join("output");
+    let run = |clock_skew: &str| {
+        let extraction = extract::run(&common::extract_args(
+            &fixture,
+            &[
+                output_dir.to_str().unwrap(),
+                "--primary-key",
+                "crn",
+                "--include",
+                "*.json",
+                "--clock-skew",
+                clock_skew,
+                "--quiet",
+            ],
+        ))
+        .unwrap();
+        let record = &extraction.change_records[Path::new("courses.json")]["1"];
+        // The skewed commit is still walked, before its parent
+        assert_eq!(record.added.len(), 1);
+        assert_eq!(record.modified[0].instant.commit, skewed);
+        let warnings = extraction
+            .warnings
+            .iter()
+            .map(|warning| (warning.commit.clone(), warning.message.clone()))
+            .collect::<Vec<_>>();
+        (record.modified[0].instant.timestamp, warnings)
+    };
+
+    assert_eq!(run("keep"), (parent_time - 3600, vec![]));
+    assert_eq!(run("clamp"), (parent_time, vec![]));
+    assert_eq!(
+        run("warn"),
+        (
+            parent_time - 3600,
+            vec![(
+                skewed.clone(),
+                "Commit time is 3600s before its parent's".to_string()
+            )]
+        )
+    );
+}
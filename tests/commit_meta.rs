@@ -0,0 +1,51 @@
+mod common;
+
+use clap::Parser;
+use common::WorkDir;
+use delorean::extract::{self, ExtractArgs};
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn commit_meta_is_attached_to_each_change() {
+    let work_dir = WorkDir::new("commit-meta");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("courses.json", &json!([])))
+        .unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Add Intro to CS\n\nRequested by the registrar.")
+                .author("Ada Lovelace", "ada@example.com")
+                .write_json("courses.json", &json!([{ "crn": "1" }])),
+        )
+        .unwrap();
+    let output_dir = work_dir.join("output");
+    let added = |extra: &[&str]| {
+        let mut arguments = vec![
+            "delorean",
+            fixture.path().to_str().unwrap(),
+            output_dir.to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--quiet",
+        ];
+        arguments.extend(extra);
+        let extraction = extract::run(&ExtractArgs::parse_from(arguments)).unwrap();
+        let record = &extraction.change_records[Path::new("courses.json")]["1"];
+        serde_json::to_value(record).unwrap()["added"][0].clone()
+    };
+    let with_meta = added(&["--with-commit-meta"]);
+    let without_meta = added(&[]);
+
+    let author = json!({ "name": "Ada Lovelace", "email": "ada@example.com" });
+    assert_eq!(with_meta["author"], author);
+    // Fixture commits are committed by their author.
+    assert_eq!(with_meta["committer"], author);
+    assert_eq!(with_meta["summary"], "Add Intro to CS");
+    assert!(without_meta.get("author").is_none());
+    assert!(without_meta.get("summary").is_none());
+}
@@ -0,0 +1,72 @@
+//! Helpers shared by the integration tests: a work directory that cleans up after itself, and
+//! extraction arguments for fixture repositories.
+
+// Each test crate uses only some of the helpers.
+#![allow(dead_code)]
+
+use clap::Parser;
+use delorean::extract::ExtractArgs;
+use delorean::fixtures::FixtureRepo;
+use delorean::selftest;
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// A directory under the system temporary directory, removed when dropped, so that it is cleaned
+/// up even when an assertion fails.
+pub struct WorkDir(PathBuf);
+
+impl WorkDir {
+    /// Creates `delorean-<name>-test-<pid>`, removing what an aborted run left there. Tests of a
+    /// crate run in one process, so `name` must be unique among them.
+    pub fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("delorean-{name}-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).expect("Failed to create work directory");
+        WorkDir(path)
+    }
+
+    /// Builds [`selftest::build_fixture`] in `repo/`.
+    pub fn selftest_fixture(&self) -> FixtureRepo {
+        selftest::build_fixture(&self.join("repo")).expect("Failed to build fixture")
+    }
+}
+
+impl Deref for WorkDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Parses the extraction command line `args` over `fixture`, which goes before them as
+/// `<REPO_PATH>`.
+pub fn extract_args(fixture: &FixtureRepo, args: &[&str]) -> ExtractArgs {
+    ExtractArgs::parse_from(
+        ["delorean", fixture.path().to_str().unwrap()]
+            .iter()
+            .chain(args),
+    )
+}
+
+/// Like [`extract_args`], for the selftest fixture's `parsed_courses/*.json` keyed by `crn`,
+/// quietly.
+pub fn selftest_args(fixture: &FixtureRepo, args: &[&str]) -> ExtractArgs {
+    let mut all = vec![
+        "--primary-key",
+        "crn",
+        "--include",
+        "parsed_courses/*.json",
+        "--quiet",
+    ];
+    all.extend(args);
+    extract_args(fixture, &all)
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use chrono::DateTime;
+use clap::Parser;
+use common::WorkDir;
+use delorean::extract::{self, ExtractArgs};
+use delorean::output;
+use serde_json::Value;
+use std::fs;
+
+#[test]
+fn compact_commits_resolve_to_the_same_records() {
+    let work_dir = WorkDir::new("compact-commits");
+    let fixture = work_dir.selftest_fixture();
+    let args_for = |output_dir: &str, extra: &[&str]| {
+        let mut arguments = vec![
+            "delorean",
+            fixture.path().to_str().unwrap(),
+            output_dir,
+            "--primary-key",
+            "crn",
+            "--include",
+            "parsed_courses/*.json",
+            "--quiet",
+        ];
+        arguments.extend(extra);
+        ExtractArgs::parse_from(arguments)
+    };
+    let compact_dir = work_dir.join("compact");
+    let plain_dir = work_dir.join("plain");
+    let args = args_for(compact_dir.to_str().unwrap(), &["--compact-commits"]);
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    output::write(&args_for(plain_dir.to_str().unwrap(), &[]), &extraction);
+    let read = |dir: &std::path::Path| -> Value {
+        serde_json::from_slice(&fs::read(dir.join("parsed_courses/202301.json")).unwrap()).unwrap()
+    };
+    let mut compact = read(&compact_dir);
+    let plain = read(&plain_dir);
+
+    let commits = compact["commits"].as_array().unwrap().clone();
+    let timestamps = compact["timestamps"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .scan(0, |time, delta| {
+            *time += delta.as_i64().unwrap();
+            Some(*time)
+        })
+        .collect::<Vec<_>>();
+    assert!(timestamps.windows(2).all(|pair| pair[0] <= pair[1]));
+    let mut unique_commits = commits.clone();
+    unique_commits.sort_by_key(|commit| commit.to_string());
+    unique_commits.dedup();
+    assert_eq!(unique_commits.len(), commits.len());
+    for record in compact["records"].as_object_mut().unwrap().values_mut() {
+        for change_type in ["added", "removed", "modified"] {
+            for change in record[change_type].as_array_mut().unwrap() {
+                let index = change["commit"].as_u64().unwrap() as usize;
+                change["commit"] = commits[index].clone();
+                let timestamp = DateTime::from_timestamp(timestamps[index], 0).unwrap();
+                change["timestamp"] = timestamp.format("%+").to_string().into();
+            }
+        }
+    }
+    assert_eq!(compact["records"], plain);
+}
+
+#[test]
+fn compact_commits_store_commit_meta_once() {
+    let work_dir = WorkDir::new("compact-commits-meta");
+    let fixture = work_dir.selftest_fixture();
+    let output_dir = work_dir.join("output");
+    let args = common::selftest_args(
+        &fixture,
+        &[
+            output_dir.to_str().unwrap(),
+            "--compact-commits",
+            "--with-commit-meta",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    let compact: Value =
+        serde_json::from_slice(&fs::read(output_dir.join("parsed_courses/202301.json")).unwrap())
+            .unwrap();
+
+    let commits = compact["commits"].as_array().unwrap();
+    let meta = compact["meta"].as_array().unwrap();
+    assert_eq!(meta.len(), commits.len());
+    assert!(meta.iter().all(|meta| meta["author"]["email"].is_string()));
+    assert!(compact.get("trailers").is_none());
+    for record in compact["records"].as_object().unwrap().values() {
+        for change_type in ["added", "removed", "modified"] {
+            for change in record[change_type].as_array().unwrap() {
+                assert!(change["commit"].is_u64());
+                assert!(change.get("author").is_none() && change.get("summary").is_none());
+            }
+        }
+    }
+}
+
+#[test]
+fn compact_commits_conflict_with_timestamps_from_the_data() {
+    let result = ExtractArgs::try_parse_from([
+        "delorean",
+        ".",
+        "output",
+        "--primary-key",
+        "crn",
+        "--compact-commits",
+        "--timestamp-field",
+        "/last_updated",
+    ]);
+    assert_eq!(
+        result.unwrap_err().kind(),
+        clap::error::ErrorKind::ArgumentConflict
+    );
+}
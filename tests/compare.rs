@@ -0,0 +1,73 @@
+mod common;
+
+use common::WorkDir;
+use delorean::compare::compare_outputs;
+use delorean::record::ChangeType;
+use delorean::{extract, output};
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+
+fn write_output(dir: &Path, records: serde_json::Value) {
+    fs::create_dir_all(dir).unwrap();
+    fs::write(dir.join("courses.json"), records.to_string()).unwrap();
+}
+
+#[test]
+fn compare_outputs_reports_gained_and_lost_instants() {
+    let work_dir = WorkDir::new("compare");
+    let instant =
+        |commit: &str| json!({ "commit": commit, "timestamp": "2023-01-01T00:00:00+00:00" });
+    write_output(
+        &work_dir.join("a"),
+        json!({
+            "1": { "added": [instant("c1")], "removed": [], "modified": [instant("c2")] },
+            "2": { "added": [], "removed": [], "modified": [] },
+        }),
+    );
+    write_output(
+        &work_dir.join("b"),
+        json!({
+            "1": { "added": [instant("c1")], "removed": [], "modified": [instant("c3")] },
+            "3": { "added": [], "removed": [], "modified": [] },
+        }),
+    );
+    let comparison = compare_outputs(&work_dir.join("a"), &work_dir.join("b")).unwrap();
+
+    assert_eq!(comparison.records_only_in_a.len(), 1);
+    assert_eq!(comparison.records_only_in_b.len(), 1);
+    assert_eq!(comparison.changed.len(), 1);
+    let delta = &comparison.changed[0];
+    assert_eq!(delta.primary_key, "1");
+    assert_eq!(delta.gained.len(), 1);
+    assert_eq!(delta.gained[0].change_type, ChangeType::Modified);
+    assert_eq!(delta.gained[0].commit, "c3");
+    assert_eq!(delta.lost[0].commit, "c2");
+}
+
+#[test]
+fn compressed_and_compact_outputs_are_errors() {
+    let work_dir = WorkDir::new("compare-layouts");
+    let fixture = work_dir.selftest_fixture();
+    let plain_dir = work_dir.join("plain");
+    let args = common::selftest_args(&fixture, &[plain_dir.to_str().unwrap()]);
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    for (name, options) in [
+        ("compressed", ["--compress", "gzip"].as_slice()),
+        ("compact", ["--compact-commits"].as_slice()),
+    ] {
+        let dir = work_dir.join(name);
+        let mut arguments = vec![dir.to_str().unwrap()];
+        arguments.extend(options);
+        output::write(&common::selftest_args(&fixture, &arguments), &extraction);
+        let error = compare_outputs(&dir, &dir).unwrap_err();
+
+        assert!(
+            error.starts_with(&dir.join("parsed_courses").display().to_string()),
+            "{error}"
+        );
+        assert!(error.contains("not a file of change records"), "{error}");
+    }
+    assert!(compare_outputs(&plain_dir, &plain_dir).unwrap().is_empty());
+}
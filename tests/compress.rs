@@ -0,0 +1,49 @@
+mod common;
+
+use clap::Parser;
+use common::WorkDir;
+use delorean::extract::{self, ExtractArgs};
+use delorean::output;
+use std::fs;
+use std::io::Read;
+
+#[test]
+fn compressed_outputs_decompress_to_the_plain_output() {
+    let work_dir = WorkDir::new("compress");
+    let fixture = work_dir.selftest_fixture();
+    let extract_to = |output_path: &str, extra: &[&str]| {
+        let args = ExtractArgs::parse_from(
+            [
+                "delorean",
+                fixture.path().to_str().unwrap(),
+                work_dir.join(output_path).to_str().unwrap(),
+                "--primary-key",
+                "crn",
+                "--include",
+                "parsed_courses/*.json",
+                "--quiet",
+            ]
+            .iter()
+            .chain(extra),
+        );
+        output::write(&args, &extract::run(&args).unwrap());
+    };
+    extract_to("plain", &[]);
+    extract_to("gzip", &["--compress", "gzip"]);
+    extract_to("zstd", &["--compress", "zstd"]);
+    let file = "parsed_courses/202301.json";
+    let plain = fs::read(work_dir.join("plain").join(file)).unwrap();
+    let mut gzip = vec![];
+    flate2::read::GzDecoder::new(
+        fs::File::open(work_dir.join("gzip").join(file).with_extension("json.gz")).unwrap(),
+    )
+    .read_to_end(&mut gzip)
+    .unwrap();
+    let zstd = zstd::decode_all(
+        fs::File::open(work_dir.join("zstd").join(file).with_extension("json.zst")).unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(gzip, plain);
+    assert_eq!(zstd, plain);
+}
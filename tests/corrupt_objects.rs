@@ -0,0 +1,75 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+
+/// Overwrites the loose object `id` of `fixture` with bytes that do not inflate.
+fn corrupt(fixture: &FixtureRepo, id: git2::Oid) {
+    let hex = id.to_string();
+    let object_path = fixture
+        .repository()
+        .path()
+        .join("objects")
+        .join(&hex[..2])
+        .join(&hex[2..]);
+    // Loose objects are read-only.
+    fs::remove_file(&object_path).unwrap();
+    fs::write(&object_path, b"not zlib").unwrap();
+}
+
+#[test]
+fn commits_with_corrupt_objects_are_skipped_with_a_warning() {
+    let work_dir = WorkDir::new("corrupt-objects");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("courses.json", &json!([])))
+        .unwrap();
+    let mut commits = vec![];
+    for crns in [vec!["1"], vec!["1", "2"], vec!["1", "2", "3"]] {
+        let records = crns
+            .iter()
+            .map(|crn| json!({ "crn": crn }))
+            .collect::<Vec<_>>();
+        let commit = fixture
+            .commit(
+                FixtureCommit::new(format!("Add {}", crns.last().unwrap()))
+                    .write_json("courses.json", &json!(records)),
+            )
+            .unwrap();
+        commits.push(commit);
+    }
+    let corrupt_tree = fixture
+        .repository()
+        .find_commit(commits[1])
+        .unwrap()
+        .tree_id();
+    corrupt(&fixture, corrupt_tree);
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+
+    // The tree is read by the commit that made it and by the next one, as its parent's.
+    let skipped = extraction
+        .warnings
+        .iter()
+        .filter(|warning| warning.message.starts_with("Skipped commit"))
+        .map(|warning| warning.commit.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(skipped, [commits[2].to_string(), commits[1].to_string()]);
+    let records = &extraction.change_records[Path::new("courses.json")];
+    assert!(records.contains_key("1"));
+    assert!(!records.contains_key("2") && !records.contains_key("3"));
+}
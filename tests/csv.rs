@@ -0,0 +1,30 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::output;
+use std::fs;
+
+#[test]
+fn csv_output_has_a_row_per_change() {
+    let work_dir = WorkDir::new("csv");
+    let fixture = work_dir.selftest_fixture();
+    let output_file = work_dir.join("changes.csv");
+    let args = common::selftest_args(
+        &fixture,
+        &[output_file.to_str().unwrap(), "--output-format", "csv"],
+    );
+    output::write(&args, &extract::run(&args).unwrap());
+    let content = fs::read_to_string(&output_file).unwrap();
+
+    let mut lines = content.lines();
+    assert_eq!(
+        lines.next(),
+        Some("path,primary_key,change_type,commit,iso_timestamp")
+    );
+    let rows = lines
+        .map(|line| line.split(',').take(3).collect::<Vec<_>>().join(","))
+        .collect::<Vec<_>>();
+    assert!(rows.contains(&"parsed_courses/202301.json,2,removed".to_string()));
+    assert!(rows.contains(&"parsed_courses/202302.json,9,added".to_string()));
+}
@@ -0,0 +1,195 @@
+use delorean::diff::{diff_records, json_patch, DiffOptions, PatchOperation};
+use proptest::prelude::*;
+use serde_json::{Map, Value};
+
+fn arb_json() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::from),
+        "[a-z]{0,4}".prop_map(Value::String),
+    ];
+    leaf.prop_recursive(3, 32, 4, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+            prop::collection::btree_map("[a-c]", inner, 0..4)
+                .prop_map(|m| Value::Object(m.into_iter().collect::<Map<_, _>>())),
+        ]
+    })
+}
+
+/// Applies a JSON Patch as RFC 6902 describes, for the operations `json_patch` emits.
+fn apply_patch(mut value: Value, patch: &[PatchOperation]) -> Value {
+    fn parent_and_key<'a>(root: &'a mut Value, path: &str) -> (&'a mut Value, String) {
+        let (parent, key) = path.rsplit_once('/').unwrap();
+        let key = key.replace("~1", "/").replace("~0", "~");
+        (root.pointer_mut(parent).unwrap(), key)
+    }
+    for operation in patch {
+        match operation {
+            PatchOperation::Replace { path, value: new } if path.is_empty() => value = new.clone(),
+            PatchOperation::Replace { path, value: new } => {
+                *value.pointer_mut(path).unwrap() = new.clone()
+            }
+            PatchOperation::Add { path, value: new } => match parent_and_key(&mut value, path) {
+                (Value::Array(items), key) => items.insert(key.parse().unwrap(), new.clone()),
+                (Value::Object(fields), key) => {
+                    fields.insert(key, new.clone());
+                }
+                _ => panic!("Cannot add at {path}"),
+            },
+            PatchOperation::Remove { path } => match parent_and_key(&mut value, path) {
+                (Value::Array(items), key) => {
+                    items.remove(key.parse().unwrap());
+                }
+                (Value::Object(fields), key) => {
+                    fields.remove(&key);
+                }
+                _ => panic!("Cannot remove at {path}"),
+            },
+        }
+    }
+    value
+}
+
+fn arb_record() -> impl Strategy<Value = Map<String, Value>> {
+    prop::collection::btree_map("[a-d]", arb_json(), 0..5).prop_map(|m| m.into_iter().collect())
+}
+
+proptest! {
+    #[test]
+    fn identical_records_are_unchanged(a in arb_json()) {
+        prop_assert!(!diff_records(&a, &a, &DiffOptions::default()).changed);
+    }
+
+    #[test]
+    fn diff_is_symmetric(a in arb_json(), b in arb_json()) {
+        let options = DiffOptions::default();
+        prop_assert_eq!(
+            diff_records(&a, &b, &options),
+            diff_records(&b, &a, &options)
+        );
+    }
+
+    #[test]
+    fn diff_matches_value_equality(a in arb_json(), b in arb_json()) {
+        prop_assert_eq!(diff_records(&a, &b, &DiffOptions::default()).changed, a != b);
+    }
+
+    #[test]
+    fn ignored_field_changes_are_unchanged(
+        record in arb_record(),
+        field in "[a-d]",
+        new_value in proptest::option::of(arb_json()),
+    ) {
+        let old = Value::Object(record.clone());
+        let mut record = record;
+        match new_value {
+            Some(value) => record.insert(field.clone(), value),
+            None => record.remove(&field),
+        };
+        let new = Value::Object(record);
+        let options = DiffOptions {
+            ignore_fields: vec![format!("/{field}")],
+            ..Default::default()
+        };
+        prop_assert!(!diff_records(&old, &new, &options).changed);
+        prop_assert!(!diff_records(&new, &old, &options).changed);
+    }
+
+    #[test]
+    fn ignored_trailing_array_elements_are_unchanged(
+        items in prop::collection::vec(arb_json(), 0..4),
+        extra in arb_json(),
+    ) {
+        let old = serde_json::json!({ "a": items.clone() });
+        let mut items = items;
+        items.push(extra);
+        let new = serde_json::json!({ "a": items.clone() });
+        let options = DiffOptions {
+            ignore_fields: vec![format!("/a/{}", items.len() - 1)],
+            ..Default::default()
+        };
+        prop_assert!(!diff_records(&old, &new, &options).changed);
+        prop_assert!(!diff_records(&new, &old, &options).changed);
+        prop_assert!(json_patch(&old, &new, &options).is_empty());
+        prop_assert!(json_patch(&new, &old, &options).is_empty());
+    }
+
+    #[test]
+    fn ignoring_one_field_still_detects_others(
+        record in arb_record(),
+        field in "[a-d]",
+        other in "[e-f]",
+        other_old in arb_json(),
+        other_new in arb_json(),
+    ) {
+        let mut old = record.clone();
+        old.insert(other.clone(), other_old.clone());
+        let mut new = record;
+        new.insert(other, other_new.clone());
+        let options = DiffOptions {
+            ignore_fields: vec![format!("/{field}")],
+            ..Default::default()
+        };
+        prop_assert_eq!(
+            diff_records(&Value::Object(old), &Value::Object(new), &options).changed,
+            other_old != other_new
+        );
+    }
+
+    #[test]
+    fn reordered_arrays_are_unchanged_when_ignoring_order(
+        items in prop::collection::vec(arb_json(), 0..6),
+        seed in any::<u64>(),
+    ) {
+        let mut shuffled = items.clone();
+        let len = shuffled.len().max(1);
+        shuffled.rotate_left(seed as usize % len);
+        let options = DiffOptions {
+            ignore_array_order: true,
+            ..Default::default()
+        };
+        prop_assert!(!diff_records(&Value::Array(items), &Value::Array(shuffled), &options).changed);
+    }
+
+    #[test]
+    fn normalized_diff_is_symmetric(a in arb_json(), b in arb_json()) {
+        let options = DiffOptions {
+            ignore_array_order: true,
+            normalize_numbers: true,
+            ignore_field_names: true,
+            ..Default::default()
+        };
+        prop_assert_eq!(
+            diff_records(&a, &b, &options),
+            diff_records(&b, &a, &options)
+        );
+    }
+
+    #[test]
+    fn patch_turns_old_into_new(a in arb_json(), b in arb_json()) {
+        let patch = json_patch(&a, &b, &DiffOptions::default());
+        prop_assert_eq!(apply_patch(a, &patch), b);
+    }
+
+    #[test]
+    fn patch_is_empty_exactly_when_unchanged(
+        a in arb_json(),
+        b in arb_json(),
+        ignore_array_order in any::<bool>(),
+        normalize_numbers in any::<bool>(),
+        ignore_field_names in any::<bool>(),
+    ) {
+        let options = DiffOptions {
+            ignore_array_order,
+            normalize_numbers,
+            ignore_field_names,
+            ..Default::default()
+        };
+        prop_assert_eq!(
+            json_patch(&a, &b, &options).is_empty(),
+            !diff_records(&a, &b, &options).changed
+        );
+    }
+}
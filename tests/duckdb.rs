@@ -0,0 +1,41 @@
+#![cfg(feature = "duckdb")]
+
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::output;
+
+#[test]
+fn duckdb_output_holds_every_change() {
+    let work_dir = WorkDir::new("duckdb");
+    let fixture = work_dir.selftest_fixture();
+    let database = work_dir.join("history.duckdb");
+    let args = common::selftest_args(
+        &fixture,
+        &[database.to_str().unwrap(), "--output-format", "duckdb"],
+    );
+    output::write(&args, &extract::run(&args).unwrap());
+    let connection = duckdb::Connection::open(&database).unwrap();
+    let change_types = connection
+        .prepare(
+            "SELECT change_type FROM changes \
+             WHERE path = 'parsed_courses/202301.json' AND primary_key = '2' ORDER BY timestamp",
+        )
+        .unwrap()
+        .query_map([], |row| row.get::<_, String>(0))
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let indexes = connection
+        .query_row(
+            "SELECT count(*) FROM duckdb_indexes() WHERE table_name = 'changes'",
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+        .unwrap();
+    drop(connection);
+
+    assert_eq!(change_types, ["removed", "added"]);
+    assert_eq!(indexes, 2);
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use std::path::Path;
+
+fn utf16(content: &str, bom: bool, to_bytes: fn(u16) -> [u8; 2]) -> Vec<u8> {
+    let bom = bom.then_some(0xFEFF);
+    bom.into_iter()
+        .chain(content.encode_utf16())
+        .flat_map(to_bytes)
+        .collect()
+}
+
+#[test]
+fn byte_order_marks_and_utf16_are_read_as_the_same_records() {
+    let work_dir = WorkDir::new("encodings");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let courses = r#"[{"crn": "1", "title": "Intro à l'informatique"}]"#;
+    let versions: [(&str, Vec<u8>); 5] = [
+        ("Initial sync", b"[]".to_vec()),
+        ("UTF-8", courses.as_bytes().to_vec()),
+        (
+            "UTF-8 with BOM",
+            [b"\xEF\xBB\xBF".as_slice(), courses.as_bytes()].concat(),
+        ),
+        ("UTF-16LE with BOM", utf16(courses, true, u16::to_le_bytes)),
+        ("UTF-16BE", utf16(courses, false, u16::to_be_bytes)),
+    ];
+    for (message, content) in versions {
+        fixture
+            .commit(FixtureCommit::new(message).write("courses.json", content))
+            .unwrap();
+    }
+    fixture
+        .commit(FixtureCommit::new("Rename").write(
+            "courses.json",
+            utf16(
+                r#"[{"crn": "1", "title": "Intro à l'info"}]"#,
+                false,
+                u16::to_le_bytes,
+            ),
+        ))
+        .unwrap();
+    let extraction = extract::run(&common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--quiet",
+        ],
+    ))
+    .unwrap();
+
+    assert!(extraction.errors.is_empty());
+    // Re-encoding the same records is not a modification
+    let record = &extraction.change_records[Path::new("courses.json")]["1"];
+    assert_eq!(record.added.len(), 1);
+    assert_eq!(record.modified.len(), 1);
+}
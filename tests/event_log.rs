@@ -0,0 +1,42 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::output;
+use serde_json::Value;
+use std::fs;
+
+#[test]
+fn event_log_is_oldest_first() {
+    let work_dir = WorkDir::new("event-log");
+    let fixture = work_dir.selftest_fixture();
+    let log_path = work_dir.join("events.ndjson");
+    let args = common::selftest_args(
+        &fixture,
+        &[
+            "--event-log",
+            log_path.to_str().unwrap(),
+            "--timestamp-format",
+            "unix",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    let events = fs::read_to_string(&log_path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(events.len(), output::change_rows(&extraction).count());
+    let timestamps = events
+        .iter()
+        .map(|event| event["timestamp"].as_i64().unwrap())
+        .collect::<Vec<_>>();
+    assert!(timestamps.is_sorted());
+    assert!(timestamps.first() < timestamps.last());
+    for event in &events {
+        let keys = event.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(keys, ["commit", "path", "pk", "timestamp", "type"]);
+    }
+}
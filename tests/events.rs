@@ -0,0 +1,198 @@
+mod common;
+
+use common::WorkDir;
+use itertools::Itertools;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// 2023-01-01T00:00:00Z
+const START_TIME: i64 = 1672531200;
+const DAY: i64 = 24 * 60 * 60;
+
+/// Builds a repository at `path` whose root commit holds only a README, followed by one commit
+/// per version of `courses.json`, a day apart. Returns the hashes of those commits.
+fn build_repo(path: &Path, versions: &[Value]) -> Vec<String> {
+    let repo = git2::Repository::init(path).unwrap();
+    let mut commits = vec![];
+    let files = [("README.md", "courses\n".to_string())].into_iter().chain(
+        versions
+            .iter()
+            .map(|version| ("courses.json", version.to_string())),
+    );
+    for (day, (file, content)) in files.enumerate() {
+        fs::write(path.join(file), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(file)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let time = git2::Time::new(START_TIME + day as i64 * DAY, 0);
+        let signature = git2::Signature::new("Fixture", "fixture@delorean.invalid", &time).unwrap();
+        let parents = match repo.head() {
+            Ok(head) => vec![head.peel_to_commit().unwrap()],
+            Err(_) => vec![],
+        };
+        let oid = repo
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                &format!("Commit {file}"),
+                &tree,
+                &parents.iter().collect::<Vec<_>>(),
+            )
+            .unwrap();
+        commits.push(oid.to_string());
+    }
+    commits.split_off(1)
+}
+
+/// Two versions of `courses.json`: the second modifies record 1, removes 2, and adds 3.
+fn course_versions() -> [Value; 2] {
+    [
+        json!([{ "crn": "1", "title": "A" }, { "crn": "2", "title": "B" }]),
+        json!([{ "crn": "1", "title": "A2" }, { "crn": "3", "title": "C" }]),
+    ]
+}
+
+/// Runs the binary over `repo` with `--output target`.
+fn stream_to(repo: &Path, target: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_delorean"))
+        .args([
+            repo.to_str().unwrap(),
+            "--output",
+            target,
+            "--primary-key",
+            "crn",
+            "--include",
+            "courses.json",
+        ])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Parses NDJSON events, sorted so that records changed in the same commit compare equal
+/// regardless of the order they were diffed in.
+fn parse_events(stdout: &[u8]) -> Vec<Value> {
+    let mut events = String::from_utf8(stdout.to_vec())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    events.sort_by_key(|event| event.to_string());
+    events
+}
+
+#[test]
+fn dash_streams_one_json_line_per_change_to_stdout() {
+    let work_dir = WorkDir::new("events-stdout");
+    let repo_path = work_dir.join("repo");
+    let commits = build_repo(&repo_path, &course_versions());
+    let run = stream_to(&repo_path, "-", &[]);
+    let event = |primary_key: &str, change_type: &str, commit: usize, date: &str| {
+        json!({
+            "path": "courses.json",
+            "primary_key": primary_key,
+            "change_type": change_type,
+            "commit": commits[commit],
+            "timestamp": format!("2023-01-{date}T00:00:00+00:00"),
+        })
+    };
+    let mut expected = vec![
+        event("1", "added", 0, "02"),
+        event("2", "added", 0, "02"),
+        event("1", "modified", 1, "03"),
+        event("2", "removed", 1, "03"),
+        event("3", "added", 1, "03"),
+    ];
+    expected.sort_by_key(|event| event.to_string());
+
+    assert!(run.status.success());
+    assert_eq!(parse_events(&run.stdout), expected);
+}
+
+#[cfg(unix)]
+#[test]
+fn unix_socket_receives_the_same_events_as_stdout() {
+    use std::io::Read;
+    use std::os::unix::net::UnixListener;
+
+    let work_dir = WorkDir::new("events-unix");
+    let repo_path = work_dir.join("repo");
+    build_repo(&repo_path, &course_versions());
+    let socket_path = work_dir.join("events.sock");
+    let listener = UnixListener::bind(&socket_path).unwrap();
+    let receiver = std::thread::spawn(move || {
+        let mut received = vec![];
+        listener
+            .accept()
+            .unwrap()
+            .0
+            .read_to_end(&mut received)
+            .unwrap();
+        received
+    });
+    let to_socket = stream_to(&repo_path, &format!("unix:{}", socket_path.display()), &[]);
+    let received = receiver.join().unwrap();
+    let to_stdout = stream_to(&repo_path, "-", &[]);
+    let missing = stream_to(&repo_path, "unix:/nonexistent/events.sock", &[]);
+
+    assert!(to_socket.status.success());
+    assert!(to_socket.stdout.is_empty());
+    assert!(!received.is_empty());
+    assert_eq!(parse_events(&received), parse_events(&to_stdout.stdout));
+    assert!(!missing.status.success());
+}
+
+#[test]
+fn template_format_renders_each_event() {
+    let work_dir = WorkDir::new("events-template");
+    let repo_path = work_dir.join("repo");
+    build_repo(&repo_path, &course_versions());
+    let template_path = work_dir.join("event.hbs");
+    fs::write(
+        &template_path,
+        "{{change_type}},{{path}},{{primary_key}},{{commit}}\n",
+    )
+    .unwrap();
+    let rendered = stream_to(
+        &repo_path,
+        "-",
+        &[
+            "--format",
+            "template",
+            "--template",
+            template_path.to_str().unwrap(),
+        ],
+    );
+    let ndjson = stream_to(&repo_path, "-", &[]);
+    let expected = parse_events(&ndjson.stdout)
+        .iter()
+        .map(|event| {
+            let field = |name: &str| event[name].as_str().unwrap().to_string();
+            [
+                field("change_type"),
+                field("path"),
+                field("primary_key"),
+                field("commit"),
+            ]
+            .join(",")
+        })
+        .sorted()
+        .collect::<Vec<_>>();
+    let without_template = stream_to(&repo_path, "-", &["--format", "template"]);
+
+    assert!(rendered.status.success());
+    assert!(!expected.is_empty());
+    assert_eq!(
+        String::from_utf8(rendered.stdout)
+            .unwrap()
+            .lines()
+            .sorted()
+            .collect::<Vec<_>>(),
+        expected
+    );
+    assert!(!without_template.status.success());
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::generate::{self, GenerateArgs};
+
+#[test]
+fn generated_repositories_have_the_requested_shape() {
+    let work_dir = WorkDir::new("generate");
+    let options = GenerateArgs {
+        commits: 30,
+        files: 3,
+        records: 20,
+        record_size: 100,
+        changes_per_commit: 5,
+        rename_every: 10,
+        merge_every: 7,
+        corrupt_every: 9,
+        seed: 42,
+    };
+    let fixture = generate::generate(&work_dir.join("repo"), &options).unwrap();
+    let again = generate::generate(&work_dir.join("again"), &options).unwrap();
+    let head = |fixture: &delorean::fixtures::FixtureRepo| {
+        fixture.repository().head().unwrap().target().unwrap()
+    };
+    let same_head = head(&fixture) == head(&again);
+    let repo = fixture.repository();
+    let mut revwalk = repo.revwalk().unwrap();
+    revwalk.push_head().unwrap();
+    let merges = revwalk
+        .map(|oid| repo.find_commit(oid.unwrap()).unwrap().parent_count())
+        .filter(|&parents| parents > 1)
+        .count();
+    let args_for = |max_errors: &str| {
+        common::extract_args(
+            &fixture,
+            &[
+                work_dir.join("output").to_str().unwrap(),
+                "--primary-key",
+                generate::PRIMARY_KEY,
+                "--include",
+                generate::INCLUDE,
+                "--max-errors",
+                max_errors,
+                "--quiet",
+            ],
+        )
+    };
+    let strict = extract::run(&args_for("0"));
+    let extraction = extract::run(&args_for("100")).unwrap();
+
+    assert!(same_head, "the same seed builds the same commits");
+    assert_eq!(merges, 4);
+    assert!(strict.is_err());
+    assert!(!extraction.errors.is_empty());
+    // The three files, and the new paths of the three renames.
+    assert_eq!(extraction.change_records.len(), 6);
+}
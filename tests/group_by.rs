@@ -0,0 +1,78 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use delorean::output;
+use serde_json::{json, Value};
+use std::fs;
+
+#[test]
+fn group_output_combines_the_history_of_each_group() {
+    let work_dir = WorkDir::new("group-by");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let mut sync = |message: &str, records: Value| {
+        fixture
+            .commit(FixtureCommit::new(message).write_json("courses.json", &records))
+            .unwrap()
+            .to_string()
+    };
+    sync("Initial sync", json!([]));
+    let add = sync(
+        "Add",
+        json!([
+            { "crn": "1", "course_id": 10, "seats": 1 },
+            { "crn": "2", "course_id": 10, "seats": 1 },
+            { "crn": "3", "course_id": 20 },
+            { "crn": "4" },
+        ]),
+    );
+    let modify = sync(
+        "Modify",
+        json!([
+            { "crn": "1", "course_id": 10, "seats": 2 },
+            { "crn": "2", "course_id": 10, "seats": 2 },
+            { "crn": "3", "course_id": 20 },
+            { "crn": "4" },
+        ]),
+    );
+    let group_dir = work_dir.join("groups");
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--group-by",
+            "/course_id",
+            "--group-output",
+            group_dir.to_str().unwrap(),
+            "--quiet",
+        ],
+    );
+    output::write(&args, &extract::run(&args).unwrap());
+    let groups = fs::read_to_string(group_dir.join("courses.json")).unwrap();
+    let groups = serde_json::from_str::<Value>(&groups).unwrap();
+    let commits = |changes: &Value| {
+        changes
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|change| change["commit"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    // Records without the field are left out
+    let mut group_keys = groups.as_object().unwrap().keys().collect::<Vec<_>>();
+    group_keys.sort();
+    assert_eq!(group_keys, ["10", "20"]);
+    assert_eq!(groups["10"]["members"], json!(["1", "2"]));
+    // One change per commit, however many members changed in it
+    assert_eq!(commits(&groups["10"]["added"]), [add.as_str()]);
+    assert_eq!(commits(&groups["10"]["modified"]), [modify.as_str()]);
+    assert_eq!(groups["20"]["members"], json!(["3"]));
+    assert_eq!(commits(&groups["20"]["added"]), [add.as_str()]);
+    assert_eq!(groups["20"]["modified"], json!([]));
+}
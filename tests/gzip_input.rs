@@ -0,0 +1,73 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use delorean::output;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+fn gzip(value: &Value) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    encoder.write_all(value.to_string().as_bytes()).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn gzipped_files_are_read_and_written_uncompressed() {
+    let work_dir = WorkDir::new("gzip-input");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let commit = |fixture: &mut FixtureRepo, message: &str, records: Value| {
+        fixture
+            .commit(
+                FixtureCommit::new(message)
+                    .write("courses.json.gz", gzip(&records))
+                    // Recognized by its content rather than its name
+                    .write("seasons.json", gzip(&records)),
+            )
+            .unwrap()
+    };
+    commit(&mut fixture, "Initial sync", json!([]));
+    commit(
+        &mut fixture,
+        "Add",
+        json!([{ "crn": "1", "title": "Intro" }]),
+    );
+    commit(
+        &mut fixture,
+        "Rename",
+        json!([{ "crn": "1", "title": "Intro to CS" }]),
+    );
+    let output_dir = work_dir.join("output");
+    let args = common::extract_args(
+        &fixture,
+        &[
+            output_dir.to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json*",
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+
+    assert!(extraction.errors.is_empty());
+    for path in ["courses.json.gz", "seasons.json"] {
+        let record = &extraction.change_records[Path::new(path)]["1"];
+        assert_eq!(
+            (record.added.len(), record.modified.len()),
+            (1, 1),
+            "{path}"
+        );
+    }
+    let written = fs::read_to_string(output_dir.join("courses.json")).unwrap();
+    let written = serde_json::from_str::<Value>(&written).unwrap();
+    assert_eq!(written["1"]["modified"].as_array().unwrap().len(), 1);
+    assert!(!output_dir.join("courses.json.gz").exists());
+}
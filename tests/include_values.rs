@@ -0,0 +1,70 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn changes_and_events_embed_record_versions() {
+    let work_dir = WorkDir::new("include-values");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("courses.json", &json!([])))
+        .unwrap();
+    let before = json!({ "crn": "1", "title": "Intro", "room": "101" });
+    let after = json!({ "crn": "1", "title": "Intro to CS", "room": "101" });
+    for (message, version) in [("Add", &before), ("Rename", &after)] {
+        fixture
+            .commit(FixtureCommit::new(message).write_json("courses.json", &json!([version])))
+            .unwrap();
+    }
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--include-values",
+            "full",
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+    let record =
+        serde_json::to_value(&extraction.change_records[Path::new("courses.json")]["1"]).unwrap();
+    let run = Command::new(env!("CARGO_BIN_EXE_delorean"))
+        .args([
+            fixture.path().to_str().unwrap(),
+            "--output",
+            "-",
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--include-values",
+            "fields",
+        ])
+        .output()
+        .unwrap();
+
+    assert_eq!(record["modified"][0]["old"], before);
+    assert_eq!(record["modified"][0]["new"], after);
+    assert!(record["added"][0].get("old").is_none());
+    assert_eq!(record["added"][0]["new"], before);
+
+    assert!(run.status.success());
+    let events = String::from_utf8(run.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    // Newest first
+    assert_eq!(events[0]["old"], json!({ "title": "Intro" }));
+    assert_eq!(events[0]["new"], json!({ "title": "Intro to CS" }));
+    assert_eq!(events[1]["new"], before);
+}
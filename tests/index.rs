@@ -0,0 +1,54 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::{compare, index, output};
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn index_maps_keys_to_their_files() {
+    let work_dir = WorkDir::new("index");
+    let fixture = work_dir.selftest_fixture();
+    let output_dir = work_dir.join("output");
+    let args = common::selftest_args(&fixture, &[output_dir.to_str().unwrap(), "--write-index"]);
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    index::write(&args, &extraction).unwrap();
+    let index: serde_json::Value =
+        serde_json::from_slice(&fs::read(output_dir.join(index::FILE_NAME)).unwrap()).unwrap();
+    let comparison = compare::compare_outputs(&output_dir, &output_dir).unwrap();
+
+    assert!(comparison.files_only_in_a.is_empty());
+    let mut locations = 0;
+    for (path, records) in &extraction.change_records {
+        for (key, record) in records {
+            let changes = record
+                .added
+                .iter()
+                .chain(&record.removed)
+                .chain(&record.modified)
+                .map(|change| (change.instant.timestamp, change.instant.commit.as_str()))
+                .collect::<Vec<_>>();
+            let (Some(first), Some(last)) = (changes.iter().min(), changes.iter().max()) else {
+                continue;
+            };
+            let location = index[key]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|location| Path::new(location["file"].as_str().unwrap()) == path)
+                .unwrap();
+            assert_eq!(location["first_commit"], first.1);
+            assert_eq!(location["last_commit"], last.1);
+            locations += 1;
+        }
+    }
+    let indexed = index
+        .as_object()
+        .unwrap()
+        .values()
+        .map(|locations| locations.as_array().unwrap().len())
+        .sum::<usize>();
+    assert_eq!(indexed, locations);
+}
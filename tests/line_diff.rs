@@ -0,0 +1,93 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Writes `records` as an array with one record per line.
+fn line_per_record(records: &[Value]) -> String {
+    let lines = records
+        .iter()
+        .map(|record| format!("  {record}"))
+        .collect::<Vec<_>>();
+    format!("[\n{}\n]\n", lines.join(",\n"))
+}
+
+#[test]
+fn line_per_record_files_match_full_parses() {
+    let work_dir = WorkDir::new("line-diff");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let versions = [
+        vec![
+            json!({ "crn": "1", "title": "A" }),
+            json!({ "crn": "2", "title": "B" }),
+            json!({ "crn": "3", "title": "C" }),
+        ],
+        // Modifies a record and appends one, which adds a comma to the previous last line
+        vec![
+            json!({ "crn": "1", "title": "A" }),
+            json!({ "crn": "2", "title": "B2" }),
+            json!({ "crn": "3", "title": "C" }),
+            json!({ "crn": "4", "title": "D" }),
+        ],
+        // Removes a record and reorders the rest
+        vec![
+            json!({ "crn": "3", "title": "C" }),
+            json!({ "crn": "2", "title": "B2" }),
+            json!({ "crn": "4", "title": "D" }),
+        ],
+        // Duplicates a primary key
+        vec![
+            json!({ "crn": "3", "title": "C" }),
+            json!({ "crn": "2", "title": "B2" }),
+            json!({ "crn": "4", "title": "D" }),
+            json!({ "crn": "4", "title": "D2" }),
+        ],
+        vec![
+            json!({ "crn": "3", "title": "C3" }),
+            json!({ "crn": "2", "title": "B2" }),
+            json!({ "crn": "4", "title": "D2" }),
+            json!({ "crn": "5", "title": "E" }),
+        ],
+    ];
+    for (i, records) in versions.iter().enumerate() {
+        fixture
+            .commit(
+                FixtureCommit::new(format!("Sync {i}"))
+                    .at(1_700_000_000 + i as i64 * 60)
+                    .write_json("pretty/courses.json", &Value::from(records.clone()))
+                    .write("lines/courses.json", line_per_record(records)),
+            )
+            .unwrap();
+    }
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*/courses.json",
+            "--patches",
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+
+    let changes =
+        |path: &str| serde_json::to_value(&extraction.change_records[Path::new(path)]).unwrap();
+    assert_eq!(
+        changes("lines/courses.json"),
+        changes("pretty/courses.json")
+    );
+    assert_eq!(
+        changes("lines/courses.json")["4"]["modified"]
+            .as_array()
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(extraction.warnings.len(), 2);
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::{manifest, output};
+use std::fs;
+
+#[test]
+fn manifest_lists_every_output_file() {
+    let work_dir = WorkDir::new("manifest");
+    let fixture = work_dir.selftest_fixture();
+    let output_dir = work_dir.join("output");
+    let args = common::selftest_args(
+        &fixture,
+        &[
+            output_dir.to_str().unwrap(),
+            "--compress",
+            "gzip",
+            "--write-manifest",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    manifest::write(&args, &extraction).unwrap();
+    let manifest: serde_json::Value =
+        serde_json::from_slice(&fs::read(output_dir.join(manifest::FILE_NAME)).unwrap()).unwrap();
+    let files = manifest["files"].as_object().unwrap();
+    let hashes = files
+        .keys()
+        .map(|file| {
+            let content = fs::read(output_dir.join(file)).unwrap();
+            git2::Oid::hash_object(git2::ObjectType::Blob, &content)
+                .unwrap()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(files.len(), extraction.change_records.len());
+    for ((file, entry), hash) in files.iter().zip(hashes) {
+        assert!(file.ends_with(".json.gz"), "{file}");
+        assert_eq!(entry["hash"], hash);
+        let path = file.strip_suffix(".gz").unwrap();
+        let records = &extraction.change_records[std::path::Path::new(path)];
+        assert_eq!(entry["records"], records.len());
+        let mut changes = records
+            .values()
+            .flat_map(|record| {
+                record
+                    .added
+                    .iter()
+                    .chain(&record.removed)
+                    .chain(&record.modified)
+            })
+            .map(|change| (change.instant.timestamp, change.instant.commit.as_str()))
+            .collect::<Vec<_>>();
+        changes.sort();
+        assert_eq!(entry["first_commit"], changes[0].1);
+        assert_eq!(entry["last_commit"], changes.last().unwrap().1);
+    }
+}
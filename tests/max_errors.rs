@@ -0,0 +1,73 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn recoverable_errors_are_skipped_within_the_budget() {
+    let work_dir = WorkDir::new("max-errors");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("courses.json", &json!([])))
+        .unwrap();
+    fixture
+        .commit(FixtureCommit::new("Truncated sync").write("courses.json", r#"[{"crn": "1""#))
+        .unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Sync")
+                .write_json("courses.json", &json!([{ "crn": "1", "title": "Intro" }])),
+        )
+        .unwrap();
+    fixture
+        .commit(FixtureCommit::new("Rename").write_json(
+            "courses.json",
+            &json!([{ "crn": "1", "title": "Intro to CS" }, { "crn": 2, "title": "Data" }]),
+        ))
+        .unwrap();
+    let output_dir = work_dir.join("output");
+    let run = |max_errors: &str| {
+        extract::run(&common::extract_args(
+            &fixture,
+            &[
+                output_dir.to_str().unwrap(),
+                "--primary-key",
+                "crn",
+                "--include",
+                "*.json",
+                "--max-errors",
+                max_errors,
+                "--quiet",
+            ],
+        ))
+    };
+
+    // The truncated version fails to parse as the new side of one diff and the old side of the
+    // next.
+    let Err(report) = run("2") else {
+        panic!("Three errors did not exceed --max-errors 2");
+    };
+    assert_eq!(report.max_errors, 2);
+    assert_eq!(report.errors.len(), 3);
+    let extraction = run("3").unwrap();
+    let messages = extraction
+        .errors
+        .iter()
+        .map(|error| error.message.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(messages.len(), 3);
+    assert!(messages
+        .iter()
+        .any(|message| message.contains("Primary key of record 1 is not a string")));
+    assert!(extraction
+        .errors
+        .iter()
+        .all(|error| error.path == Path::new("courses.json")));
+    // The records around the errors are still extracted
+    let records = &extraction.change_records[Path::new("courses.json")];
+    assert_eq!(records.keys().collect::<Vec<_>>(), ["1"]);
+    assert_eq!(records["1"].modified.len(), 1);
+}
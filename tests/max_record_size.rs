@@ -0,0 +1,67 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn oversized_records_are_compared_by_hash() {
+    let work_dir = WorkDir::new("max-record-size");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("courses.json", &json!([])))
+        .unwrap();
+    let versions = [
+        json!([
+            { "crn": "1", "description": "a".repeat(1000) },
+            { "crn": "2", "title": "Data" },
+        ]),
+        json!([
+            { "crn": "1", "description": "b".repeat(1000) },
+            { "crn": "2", "title": "Data" },
+        ]),
+    ];
+    for (i, version) in versions.iter().enumerate() {
+        fixture
+            .commit(FixtureCommit::new(format!("Sync {i}")).write_json("courses.json", version))
+            .unwrap();
+    }
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--max-record-size",
+            "100",
+            "--include-values",
+            "full",
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+
+    let records = &extraction.change_records[Path::new("courses.json")];
+    let modified = serde_json::to_value(&records["1"]).unwrap()["modified"][0].clone();
+    assert_eq!(modified["old"]["crn"], "1");
+    assert_eq!(modified["old"]["$oversized"]["bytes"], 1028);
+    assert!(modified["old"].get("description").is_none());
+    assert_ne!(
+        modified["old"]["$oversized"]["hash"],
+        modified["new"]["$oversized"]["hash"]
+    );
+    let record = serde_json::to_value(&records["2"]).unwrap();
+    assert_eq!(
+        record["added"][0]["new"],
+        json!({ "crn": "2", "title": "Data" })
+    );
+    assert!(extraction
+        .warnings
+        .iter()
+        .all(|warning| warning.message.starts_with("Record 1 is 1028 bytes")));
+    assert!(!extraction.warnings.is_empty());
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::memory::{CountingAllocator, MemStats};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn mem_stats_count_the_allocations_of_each_stage() {
+    let work_dir = WorkDir::new("mem-stats");
+    let fixture = work_dir.selftest_fixture();
+    let args = common::selftest_args(&fixture, &[work_dir.join("output").to_str().unwrap()]);
+    let mut mem_stats = MemStats::default();
+    let extraction = mem_stats.stage("extract", || extract::run(&args).unwrap());
+    let kept = mem_stats.stage("keep", || vec![0u8; 1 << 20]);
+
+    let [extract, keep] = &mem_stats.stages[..] else {
+        panic!("Expected two stages, got {mem_stats:?}");
+    };
+    assert!(!extraction.change_records.is_empty());
+    assert!(extract.allocations > 0);
+    assert!(extract.peak_bytes >= extract.live_bytes);
+    assert_eq!(keep.allocations, 1);
+    assert_eq!(keep.allocated_bytes, kept.len());
+    assert!(keep.live_bytes >= kept.len());
+    assert!(mem_stats
+        .to_string()
+        .contains("keep: 1 allocations (1.0 MiB)"));
+}
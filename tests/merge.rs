@@ -0,0 +1,50 @@
+mod common;
+
+use clap::Parser;
+use common::WorkDir;
+use delorean::extract::{self, ExtractArgs};
+use delorean::output;
+use std::fs;
+
+#[test]
+fn merged_output_matches_per_path_output() {
+    let work_dir = WorkDir::new("merge");
+    let fixture = work_dir.selftest_fixture();
+    let extract_to = |output_path: &str, extra: &[&str]| {
+        let args = ExtractArgs::parse_from(
+            [
+                "delorean",
+                fixture.path().to_str().unwrap(),
+                work_dir.join(output_path).to_str().unwrap(),
+                "--primary-key",
+                "crn",
+                "--include",
+                "parsed_courses/*.json",
+                "--quiet",
+            ]
+            .iter()
+            .chain(extra),
+        );
+        output::write(&args, &extract::run(&args).unwrap());
+    };
+    extract_to("output", &[]);
+    extract_to("merged.json", &["--merge-output"]);
+    extract_to("merged.ndjson", &["--merge-output", "ndjson"]);
+    let read_json = |path: &str| {
+        serde_json::from_slice::<serde_json::Value>(&fs::read(work_dir.join(path)).unwrap())
+            .unwrap()
+    };
+    let merged = read_json("merged.json");
+    let lines = fs::read_to_string(work_dir.join("merged.ndjson")).unwrap();
+    for path in ["parsed_courses/202301.json", "parsed_courses/202302.json"] {
+        assert_eq!(merged[path], read_json(&format!("output/{path}")));
+    }
+    let lines = lines
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[1]["path"], "parsed_courses/202302.json");
+    assert_eq!(lines[1]["records"], merged["parsed_courses/202302.json"]);
+}
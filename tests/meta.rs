@@ -0,0 +1,42 @@
+mod common;
+
+use clap::Parser;
+use common::WorkDir;
+use delorean::extract::{self, ExtractArgs};
+use delorean::{compare, meta, output};
+use std::fs;
+
+#[test]
+fn metadata_is_written_next_to_the_output() {
+    let work_dir = WorkDir::new("meta");
+    let fixture = work_dir.selftest_fixture();
+    let output_dir = work_dir.join("output");
+    let arguments = [
+        "delorean",
+        fixture.path().to_str().unwrap(),
+        output_dir.to_str().unwrap(),
+        "--primary-key",
+        "crn",
+        "--include",
+        "parsed_courses/*.json",
+        "--write-meta",
+        "--quiet",
+    ]
+    .map(String::from)
+    .to_vec();
+    let args = ExtractArgs::parse_from(&arguments);
+    output::write(&args, &extract::run(&args).unwrap());
+    meta::write(&args, arguments.clone()).unwrap();
+    let head = fixture.repository().head().unwrap().target().unwrap();
+    let metadata: serde_json::Value =
+        serde_json::from_slice(&fs::read(output_dir.join(meta::FILE_NAME)).unwrap()).unwrap();
+    let comparison = compare::compare_outputs(&output_dir, &output_dir).unwrap();
+
+    assert_eq!(metadata["schema_version"], meta::SCHEMA_VERSION);
+    assert_eq!(metadata["tool"]["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(metadata["arguments"], serde_json::json!(arguments));
+    assert_eq!(metadata["head"], head.to_string());
+    assert!(metadata["generated_at"].as_str().unwrap().ends_with('Z'));
+    assert_eq!(metadata["order"], "asc");
+    assert!(comparison.files_only_in_a.is_empty() && comparison.changed.is_empty());
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use clap::Parser;
+use common::WorkDir;
+use delorean::extract::{self, ExtractArgs};
+use delorean::output;
+use std::fs;
+
+#[test]
+fn msgpack_output_decodes_to_the_merged_json() {
+    let work_dir = WorkDir::new("msgpack");
+    let fixture = work_dir.selftest_fixture();
+    let msgpack_file = work_dir.join("changes.msgpack");
+    let json_file = work_dir.join("changes.json");
+    let args_for = |output_file: &str, extra: &[&str]| {
+        let mut arguments = vec![
+            "delorean",
+            fixture.path().to_str().unwrap(),
+            output_file,
+            "--primary-key",
+            "crn",
+            "--include",
+            "parsed_courses/*.json",
+            "--quiet",
+        ];
+        arguments.extend(extra);
+        ExtractArgs::parse_from(arguments)
+    };
+    let args = args_for(
+        msgpack_file.to_str().unwrap(),
+        &["--output-format", "msgpack"],
+    );
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    output::write(
+        &args_for(json_file.to_str().unwrap(), &["--merge-output"]),
+        &extraction,
+    );
+    let msgpack: serde_json::Value =
+        rmp_serde::from_slice(&fs::read(&msgpack_file).unwrap()).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&fs::read(&json_file).unwrap()).unwrap();
+    let sizes = [msgpack_file, json_file].map(|file| fs::metadata(file).unwrap().len());
+
+    assert_eq!(msgpack, json);
+    assert!(sizes[0] < sizes[1]);
+}
+
+#[test]
+fn compact_msgpack_shares_one_instant_table() {
+    let work_dir = WorkDir::new("msgpack-compact");
+    let fixture = work_dir.selftest_fixture();
+    let msgpack_file = work_dir.join("changes.msgpack");
+    let args = common::selftest_args(
+        &fixture,
+        &[
+            msgpack_file.to_str().unwrap(),
+            "--output-format",
+            "msgpack",
+            "--compact-commits",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    let msgpack: serde_json::Value =
+        rmp_serde::from_slice(&fs::read(&msgpack_file).unwrap()).unwrap();
+
+    let commits = msgpack["commits"].as_array().unwrap();
+    assert_eq!(
+        commits.len(),
+        msgpack["timestamps"].as_array().unwrap().len()
+    );
+    let paths = msgpack["records"].as_object().unwrap();
+    assert_eq!(paths.len(), extraction.change_records.len());
+    let change = &paths["parsed_courses/202301.json"]["2"]["added"][0];
+    assert!(change["commit"].as_u64().unwrap() < commits.len() as u64);
+    assert!(change.get("timestamp").is_none());
+}
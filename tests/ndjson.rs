@@ -0,0 +1,63 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn json_lines_files_are_diffed_like_arrays() {
+    let work_dir = WorkDir::new("ndjson");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Initial sync")
+                .write("courses.jsonl", "")
+                .write("detected.json", "[]")
+                .write_json("courses.json", &json!([])),
+        )
+        .unwrap();
+    let versions = [
+        (
+            "{\"crn\": \"1\", \"title\": \"Intro\"}\n{\"crn\": \"2\", \"title\": \"Data\"}\n",
+            json!([{ "crn": "1", "title": "Intro" }, { "crn": "2", "title": "Data" }]),
+        ),
+        (
+            "{\"crn\": \"1\", \"title\": \"Intro to CS\"}\n\n{\"crn\": \"3\"}\n",
+            json!([{ "crn": "1", "title": "Intro to CS" }, { "crn": "3" }]),
+        ),
+    ];
+    for (i, (lines, array)) in versions.iter().enumerate() {
+        fixture
+            .commit(
+                FixtureCommit::new(format!("Sync {i}"))
+                    .write("courses.jsonl", *lines)
+                    .write("detected.json", *lines)
+                    .write_json("courses.json", array),
+            )
+            .unwrap();
+    }
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+
+    let history =
+        |path: &str| serde_json::to_value(&extraction.change_records[Path::new(path)]).unwrap();
+    assert_eq!(history("courses.jsonl"), history("courses.json"));
+    assert_eq!(history("detected.json"), history("courses.json"));
+    assert_eq!(
+        history("courses.json")["1"]["modified"]
+            .as_array()
+            .unwrap()
+            .len(),
+        1
+    );
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+
+#[test]
+fn changes_left_out_by_filters_are_not_no_ops() {
+    let work_dir = WorkDir::new("noop-commits");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("courses.json", &json!([])))
+        .unwrap();
+    fixture
+        .commit(FixtureCommit::new("Add").write_json(
+            "courses.json",
+            &json!([{ "crn": "1", "seats": 1, "school": "YC" }]),
+        ))
+        .unwrap();
+    fixture
+        .commit(FixtureCommit::new("Modify").write_json(
+            "courses.json",
+            &json!([{ "crn": "1", "seats": 2, "school": "YC" }]),
+        ))
+        .unwrap();
+    fixture
+        .commit(FixtureCommit::new("Reformat").write(
+            "courses.json",
+            r#"[{"crn": "1", "seats": 2, "school": "YC"}]"#,
+        ))
+        .unwrap();
+    let output_dir = work_dir.join("output");
+    let noop_messages = |extra: &[&str]| {
+        let mut arguments = vec![
+            output_dir.to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--quiet",
+        ];
+        arguments.extend(extra);
+        let mut messages = extract::run(&common::extract_args(&fixture, &arguments))
+            .unwrap()
+            .noop_commits
+            .into_iter()
+            .map(|noop| noop.message)
+            .collect::<Vec<_>>();
+        messages.sort();
+        messages
+    };
+
+    assert_eq!(noop_messages(&[]), ["Reformat"]);
+    assert_eq!(noop_messages(&["--changes", "added,removed"]), ["Reformat"]);
+    assert_eq!(
+        noop_messages(&["--where", "/school == \"SOM\""]),
+        ["Reformat"]
+    );
+}
+
+#[test]
+fn commits_with_unreadable_files_are_not_no_ops() {
+    let work_dir = WorkDir::new("noop-commits-unreadable");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let courses = json!([{ "crn": "1", "seats": 1 }]);
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("courses.json", &courses))
+        .unwrap();
+    fixture
+        .commit(FixtureCommit::new("Truncate").write("courses.json", r#"[{"crn": "1""#))
+        .unwrap();
+    fixture
+        .commit(FixtureCommit::new("Restore").write_json("courses.json", &courses))
+        .unwrap();
+    fixture
+        .commit(FixtureCommit::new("Wrap").write_json("courses.json", &json!({ "courses": [] })))
+        .unwrap();
+    let extraction = extract::run(&common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--max-errors",
+            "2",
+            "--quiet",
+        ],
+    ))
+    .unwrap();
+
+    assert_eq!(extraction.errors.len(), 2);
+    assert!(extraction.noop_commits.is_empty());
+}
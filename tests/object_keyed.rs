@@ -0,0 +1,69 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn files_that_are_not_arrays_are_skipped_with_a_warning_unless_object_keyed() {
+    let work_dir = WorkDir::new("object-keyed");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Initial sync")
+                .write_json("courses.json", &json!([]))
+                .write_json("seasons.json", &json!({}))
+                .write_json("count.json", &json!(0)),
+        )
+        .unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Sync")
+                .write_json("courses.json", &json!([{ "crn": "1", "title": "Intro" }]))
+                .write_json(
+                    "seasons.json",
+                    &json!({ "202301": { "name": "Spring 2023" } }),
+                )
+                .write_json("count.json", &json!(1)),
+        )
+        .unwrap();
+    let output_dir = work_dir.join("output");
+    let run = |extra: &[&str]| {
+        let mut arguments = vec![
+            output_dir.to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--quiet",
+        ];
+        arguments.extend(extra);
+        extract::run(&common::extract_args(&fixture, &arguments)).unwrap()
+    };
+    let warned_paths = |extraction: &extract::Extraction| {
+        let mut paths = extraction
+            .warnings
+            .iter()
+            .map(|warning| warning.path.to_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.dedup();
+        paths
+    };
+
+    let array_only = run(&[]);
+    assert!(array_only.errors.is_empty());
+    assert_eq!(warned_paths(&array_only), ["count.json", "seasons.json"]);
+    assert!(array_only.change_records[Path::new("courses.json")].contains_key("1"));
+    assert!(array_only
+        .change_records
+        .get(Path::new("seasons.json"))
+        .is_none_or(|records| records.is_empty()));
+    let object_keyed = run(&["--object-keyed"]);
+    assert_eq!(warned_paths(&object_keyed), ["count.json"]);
+    let seasons = &object_keyed.change_records[Path::new("seasons.json")];
+    assert_eq!(seasons["202301"].added.len(), 1);
+    assert!(object_keyed.change_records[Path::new("courses.json")].contains_key("1"));
+}
@@ -0,0 +1,36 @@
+#![cfg(feature = "object_store")]
+
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::compare_dirs;
+use delorean::object_store::Upload;
+use delorean::output;
+use std::path::Path;
+
+#[test]
+fn url_output_is_uploaded_under_its_prefix() {
+    let work_dir = WorkDir::new("object-store");
+    let fixture = work_dir.selftest_fixture();
+    let args_for =
+        |output_path: &str| common::selftest_args(&fixture, &[output_path, "--write-meta"]);
+    let bucket = work_dir.join("bucket");
+    let mut args = args_for(&format!("file://{}/history", bucket.display()));
+    let upload = Upload::stage(&mut args).unwrap().unwrap();
+    let staged = args.output_path.clone().unwrap();
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    delorean::meta::write(&args, vec![]).unwrap();
+    upload.finish().unwrap();
+    let local = work_dir.join("local");
+    output::write(&args_for(local.to_str().unwrap()), &extraction);
+    let differences = compare_dirs(&bucket.join("history"), &local);
+    let staged_exists = Path::new(&staged).exists();
+
+    assert!(!staged.starts_with("file://"));
+    assert!(!staged_exists);
+    // Only the metadata, which the local output was written without.
+    assert_eq!(differences.len(), 1, "{differences:?}");
+    assert!(differences[0].contains(delorean::meta::FILE_NAME));
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::fs;
+
+#[test]
+fn missing_objects_count_against_the_error_budget_offline() {
+    let work_dir = WorkDir::new("offline");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("courses.json", &json!([])))
+        .unwrap();
+    let first = fixture
+        .commit(FixtureCommit::new("Add 1").write_json("courses.json", &json!([{ "crn": "1" }])))
+        .unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Add 2")
+                .write_json("courses.json", &json!([{ "crn": "1" }, { "crn": "2" }])),
+        )
+        .unwrap();
+    // As in a partial clone that never fetched the first version.
+    let missing = fixture
+        .repository()
+        .find_commit(first)
+        .unwrap()
+        .tree()
+        .unwrap()
+        .get_name("courses.json")
+        .unwrap()
+        .id()
+        .to_string();
+    let objects = fixture.repository().path().join("objects");
+    fs::remove_file(objects.join(&missing[..2]).join(&missing[2..])).unwrap();
+    let run = |max_errors: &str| {
+        extract::run(&common::extract_args(
+            &fixture,
+            &[
+                work_dir.join("output").to_str().unwrap(),
+                "--primary-key",
+                "crn",
+                "--include",
+                "*.json",
+                "--offline",
+                "--max-errors",
+                max_errors,
+                "--quiet",
+            ],
+        ))
+    };
+
+    // The version is the old side of one commit and the new side of the other.
+    let Err(report) = run("1") else {
+        panic!("A missing object did not count against --max-errors");
+    };
+    assert_eq!(report.errors.len(), 2);
+    assert!(report.errors[0]
+        .message
+        .contains("--offline forbids fetching it"));
+    let extraction = run("2").unwrap();
+    assert_eq!(extraction.errors.len(), 2);
+    assert!(extraction
+        .change_records
+        .values()
+        .all(|records| records.is_empty()));
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use delorean::record::ChangeType;
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn keys_found_in_one_file_stop_being_tracked_in_others() {
+    let work_dir = WorkDir::new("only-key");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Initial sync")
+                .write_json("a.json", &json!([]))
+                .write_json("b.json", &json!([{ "crn": "1", "seats": 0 }])),
+        )
+        .unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Sync 1").write_json("b.json", &json!([{ "crn": "1", "seats": 1 }])),
+        )
+        .unwrap();
+    // Finds the addition of 1 while b.json, unchanged for 1, is parsed with 1 still pending.
+    fixture
+        .commit(
+            FixtureCommit::new("Sync 2")
+                .write_json("a.json", &json!([{ "crn": "1" }]))
+                .write_json(
+                    "b.json",
+                    &json!([{ "crn": "1", "seats": 1 }, { "crn": "3", "seats": 0 }]),
+                ),
+        )
+        .unwrap();
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            // Never added, so that the walk goes on after 1 is found.
+            "--only-key",
+            "1",
+            "--only-key",
+            "2",
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+
+    let changes = extraction
+        .change_records
+        .iter()
+        .flat_map(|(path, records)| {
+            records.iter().flat_map(move |(pk, record)| {
+                [
+                    (ChangeType::Added, &record.added),
+                    (ChangeType::Removed, &record.removed),
+                    (ChangeType::Modified, &record.modified),
+                ]
+                .into_iter()
+                .flat_map(move |(change_type, changes)| {
+                    changes
+                        .iter()
+                        .map(move |_| (path.as_path(), pk.as_str(), change_type))
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(changes, [(Path::new("a.json"), "1", ChangeType::Added)]);
+}
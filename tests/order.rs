@@ -0,0 +1,39 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use std::path::Path;
+
+#[test]
+fn change_lists_follow_order() {
+    let work_dir = WorkDir::new("order");
+    let fixture = work_dir.selftest_fixture();
+    let output_dir = work_dir.join("output");
+    let modified = |order: &str| {
+        let args =
+            common::selftest_args(&fixture, &[output_dir.to_str().unwrap(), "--order", order]);
+        let extraction = extract::run(&args).unwrap();
+        let record = &extraction.change_records[Path::new("parsed_courses/202301.json")]["1"];
+        serde_json::to_value(record).unwrap()["modified"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|change| change["timestamp"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    let ascending = modified("asc");
+    let descending = modified("desc");
+
+    assert_eq!(
+        ascending,
+        [
+            "2023-01-02T00:00:00+00:00",
+            "2023-01-04T00:00:00+00:00",
+            "2023-01-06T00:00:00+00:00",
+        ]
+    );
+    assert_eq!(
+        descending,
+        ascending.iter().rev().cloned().collect::<Vec<_>>()
+    );
+}
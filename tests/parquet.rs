@@ -0,0 +1,39 @@
+#![cfg(feature = "parquet")]
+
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::output;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use std::fs::File;
+
+#[test]
+fn parquet_output_has_a_row_per_change() {
+    let work_dir = WorkDir::new("parquet");
+    let fixture = work_dir.selftest_fixture();
+    let output_file = work_dir.join("changes.parquet");
+    let args = common::selftest_args(
+        &fixture,
+        &[output_file.to_str().unwrap(), "--output-format", "parquet"],
+    );
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    let reader = SerializedFileReader::new(File::open(&output_file).unwrap()).unwrap();
+    let rows = reader
+        .get_row_iter(None)
+        .unwrap()
+        .map(|row| row.unwrap().to_string())
+        .collect::<Vec<_>>();
+
+    let changes = extraction
+        .change_records
+        .values()
+        .flat_map(|records| records.values())
+        .map(|record| record.added.len() + record.removed.len() + record.modified.len())
+        .sum::<usize>();
+    assert_eq!(rows.len(), changes);
+    assert!(rows[0].starts_with(
+        "{path: \"parsed_courses/202301.json\", primary_key: \"1\", change_type: \"modified\""
+    ));
+}
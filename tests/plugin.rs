@@ -0,0 +1,65 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::WorkDir;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Command;
+
+fn write_plugin(dir: &Path, name: &str, script: &str) {
+    let path = dir.join(format!("delorean-{name}"));
+    fs::write(&path, format!("#!/bin/sh\n{script}\n")).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[test]
+fn plugins_run_as_subcommands_and_read_events() {
+    let work_dir = WorkDir::new("plugin");
+    let fixture = work_dir.selftest_fixture();
+    let plugin_dir = work_dir.join("bin");
+    fs::create_dir_all(&plugin_dir).unwrap();
+    let received = work_dir.join("received");
+    write_plugin(&plugin_dir, "echo", "echo \"$@\"; test -x \"$DELOREAN\"");
+    write_plugin(
+        &plugin_dir,
+        "capture",
+        &format!("cat > '{}'", received.display()),
+    );
+    let path = format!(
+        "{}:{}",
+        plugin_dir.display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let delorean = |arguments: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_delorean"))
+            .args(arguments)
+            .env("PATH", &path)
+            .output()
+            .unwrap()
+    };
+    let echo = delorean(&["echo", "--flag", "value"]);
+    let extract_to = |target: &str| {
+        delorean(&[
+            fixture.path().to_str().unwrap(),
+            "--output",
+            target,
+            "--primary-key",
+            "crn",
+            "--include",
+            "parsed_courses/*.json",
+        ])
+    };
+    let to_plugin = extract_to("plugin:capture");
+    let to_stdout = extract_to("-");
+    let missing = extract_to("plugin:missing");
+    let events = fs::read(&received).unwrap();
+
+    assert!(echo.status.success());
+    assert_eq!(echo.stdout, b"--flag value\n");
+    assert!(to_plugin.status.success());
+    assert!(!events.is_empty());
+    assert_eq!(events, to_stdout.stdout);
+    assert!(!missing.status.success());
+}
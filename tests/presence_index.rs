@@ -0,0 +1,50 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use delorean::output;
+use serde_json::{json, Value};
+use std::fs;
+
+#[test]
+fn presence_index_lists_keys_at_head_with_when_they_were_added() {
+    let work_dir = WorkDir::new("presence-index");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let mut sync = |message: &str, crns: &[&str]| {
+        let records = crns.iter().map(|crn| json!({ "crn": crn })).collect();
+        fixture
+            .commit(FixtureCommit::new(message).write_json("courses.json", &Value::Array(records)))
+            .unwrap()
+    };
+    let before_walk = sync("Initial sync", &["1"]);
+    sync("Add 2", &["1", "2"]);
+    let added_3 = sync("Add 3", &["1", "2", "3"]);
+    sync("Remove 2", &["1", "3"]);
+    let presence_dir = work_dir.join("presence");
+    let before_walk = before_walk.to_string();
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--until",
+            &before_walk,
+            "--emit-presence-index",
+            presence_dir.to_str().unwrap(),
+            "--quiet",
+        ],
+    );
+    output::write(&args, &extract::run(&args).unwrap());
+    let presence = fs::read_to_string(presence_dir.join("courses.json")).unwrap();
+    let presence = serde_json::from_str::<Value>(&presence).unwrap();
+
+    let keys = presence.as_object().unwrap().keys().collect::<Vec<_>>();
+    assert_eq!(keys, ["1", "3"]);
+    // Added before the walked history
+    assert_eq!(presence["1"], Value::Null);
+    assert_eq!(presence["3"]["commit"], added_3.to_string());
+}
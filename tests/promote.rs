@@ -0,0 +1,55 @@
+mod common;
+
+use common::WorkDir;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn field_transitions_are_promoted_to_custom_change_types() {
+    let work_dir = WorkDir::new("promote");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    for (status, title) in [
+        ("open", "Intro"),
+        ("closed", "Intro"),
+        ("open", "Intro to CS"),
+    ] {
+        fixture
+            .commit(FixtureCommit::new(status).write_json(
+                "courses.json",
+                &json!([{ "crn": "1", "enrollment_status": status, "title": title }]),
+            ))
+            .unwrap();
+    }
+    let rules_path = work_dir.join("rules.yaml");
+    fs::write(
+        &rules_path,
+        "- name: enrollment-closed\n  changed: /enrollment_status\n  from: open\n  to: closed\n  \
+         promote_to: closed\n",
+    )
+    .unwrap();
+    let run = Command::new(env!("CARGO_BIN_EXE_delorean"))
+        .args([
+            fixture.path().to_str().unwrap(),
+            "--output",
+            "-",
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--rules",
+            rules_path.to_str().unwrap(),
+        ])
+        .output()
+        .unwrap();
+
+    assert!(run.status.success());
+    let change_types = String::from_utf8(run.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["change_type"].clone())
+        .collect::<Vec<_>>();
+    // Newest first: reopening is an ordinary modification.
+    assert_eq!(change_types, ["modified", "closed"]);
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "proto")]
+
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::output;
+use delorean::proto::Extraction;
+use prost::Message;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn proto_output_decodes_to_the_change_records() {
+    let work_dir = WorkDir::new("proto");
+    let fixture = work_dir.selftest_fixture();
+    let proto_file = work_dir.join("changes.pb");
+    let args = common::selftest_args(
+        &fixture,
+        &[
+            proto_file.to_str().unwrap(),
+            "--output-format",
+            "proto",
+            "--change-ids",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    let decoded = Extraction::decode(fs::read(&proto_file).unwrap().as_slice()).unwrap();
+
+    assert_eq!(decoded.files.len(), extraction.change_records.len());
+    for file in &decoded.files {
+        let records = &extraction.change_records[Path::new(&file.path)];
+        assert_eq!(file.records.len(), records.len());
+        for (key, record) in &file.records {
+            let expected = serde_json::to_value(&records[key]).unwrap();
+            for (changes, change_type) in [
+                (&record.added, "added"),
+                (&record.removed, "removed"),
+                (&record.modified, "modified"),
+            ] {
+                let expected = expected[change_type].as_array().unwrap();
+                assert_eq!(changes.len(), expected.len());
+                for (change, expected) in changes.iter().zip(expected) {
+                    let instant = change.instant.as_ref().unwrap();
+                    assert_eq!(instant.commit, expected["commit"]);
+                    assert_eq!(change.id.as_deref(), expected["id"].as_str());
+                }
+            }
+        }
+    }
+}
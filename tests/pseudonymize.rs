@@ -0,0 +1,125 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn pseudonymized_values_are_hashed_consistently() {
+    let work_dir = WorkDir::new("pseudonymize");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("students.json", &json!([])))
+        .unwrap();
+    let versions = [
+        json!([
+            { "netid": "abc123", "advisor": "xyz9", "major": "CPSC" },
+            { "netid": "def456", "advisor": "xyz9", "major": "MATH" },
+        ]),
+        json!([
+            { "netid": "abc123", "advisor": "xyz9", "major": "ECON" },
+            { "netid": "def456", "advisor": "xyz9", "major": "MATH" },
+        ]),
+    ];
+    for (i, version) in versions.iter().enumerate() {
+        fixture
+            .commit(FixtureCommit::new(format!("Sync {i}")).write_json("students.json", version))
+            .unwrap();
+    }
+    let run = |salt: &str| {
+        let args = common::extract_args(
+            &fixture,
+            &[
+                work_dir.join("output").to_str().unwrap(),
+                "--primary-key",
+                "netid",
+                "--include",
+                "*.json",
+                "--pseudonymize",
+                "/netid,/advisor",
+                "--pseudonymize-salt",
+                salt,
+                "--include-values",
+                "full",
+                "--quiet",
+            ],
+        );
+        extract::run(&args).unwrap()
+    };
+    let extraction = run("pepper");
+    let resalted = run("salt");
+
+    let records = &extraction.change_records[Path::new("students.json")];
+    let output = serde_json::to_string(records).unwrap();
+    for netid in ["abc123", "def456", "xyz9"] {
+        assert!(!output.contains(netid));
+    }
+    assert_eq!(records.len(), 2);
+    let modified = records
+        .values()
+        .find(|record| !record.modified.is_empty())
+        .unwrap();
+    let new = &serde_json::to_value(modified).unwrap()["modified"][0]["new"];
+    assert_eq!(new["major"], "ECON");
+    assert!(records.contains_key(new["netid"].as_str().unwrap()));
+    // Both students share an advisor, and so a pseudonym
+    let advisors = records
+        .values()
+        .map(|record| serde_json::to_value(record).unwrap()["added"][0]["new"]["advisor"].clone())
+        .collect::<Vec<_>>();
+    assert_eq!(advisors[0], advisors[1]);
+
+    let resalted = &resalted.change_records[Path::new("students.json")];
+    assert!(records.keys().all(|key| !resalted.contains_key(key)));
+}
+
+#[test]
+fn object_keys_are_pseudonymized_like_primary_keys() {
+    let work_dir = WorkDir::new("pseudonymize-object-keyed");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Initial sync")
+                .write_json("list.json", &json!([]))
+                .write_json("map.json", &json!({})),
+        )
+        .unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Sync")
+                .write_json(
+                    "list.json",
+                    &json!([{ "netid": "abc123", "major": "CPSC" }]),
+                )
+                .write_json("map.json", &json!({ "abc123": { "major": "CPSC" } })),
+        )
+        .unwrap();
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "netid",
+            "--include",
+            "*.json",
+            "--object-keyed",
+            "--pseudonymize",
+            "/netid",
+            "--pseudonymize-salt",
+            "pepper",
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+
+    let keys = |path: &str| {
+        extraction.change_records[Path::new(path)]
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(keys("map.json"), keys("list.json"));
+    assert_ne!(keys("map.json"), ["abc123"]);
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use delorean::query::ExtractionResult;
+use delorean::record::ChangeType;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+#[test]
+fn query_handle_looks_up_changes_by_key_and_time() {
+    let work_dir = WorkDir::new("query");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Initial sync")
+                .at(1_000)
+                .write_json("fall.json", &json!([]))
+                .write_json("spring.json", &json!([])),
+        )
+        .unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Add courses")
+                .at(2_000)
+                .write_json("fall.json", &json!([{ "crn": "1", "title": "A" }]))
+                .write_json("spring.json", &json!([{ "crn": "2", "title": "B" }])),
+        )
+        .unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Rename a course")
+                .at(3_000)
+                .write_json("fall.json", &json!([{ "crn": "1", "title": "A2" }])),
+        )
+        .unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Move a course")
+                .at(4_000)
+                .write_json("fall.json", &json!([]))
+                .write_json(
+                    "spring.json",
+                    &json!([{ "crn": "1", "title": "A2" }, { "crn": "2", "title": "B" }]),
+                ),
+        )
+        .unwrap();
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--quiet",
+        ],
+    );
+    let result = ExtractionResult::new(extract::run(&args).unwrap());
+
+    assert_eq!(
+        result.files(),
+        [PathBuf::from("fall.json"), PathBuf::from("spring.json")]
+    );
+    let history = result
+        .history("1")
+        .map(|change| {
+            (
+                change.path,
+                change.change_type,
+                change.change.instant.timestamp,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        history,
+        [
+            (Path::new("fall.json"), ChangeType::Added, 2_000),
+            (Path::new("fall.json"), ChangeType::Modified, 3_000),
+            (Path::new("fall.json"), ChangeType::Removed, 4_000),
+            (Path::new("spring.json"), ChangeType::Added, 4_000),
+        ]
+    );
+    assert_eq!(result.history("3").count(), 0);
+    let between = result
+        .changes_between(2_000, 4_000)
+        .map(|change| (change.primary_key, change.change_type))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        between,
+        [
+            ("1", ChangeType::Added),
+            ("2", ChangeType::Added),
+            ("1", ChangeType::Modified),
+        ]
+    );
+    assert_eq!(result.changes_between(4_000, 3_000).count(), 0);
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn recent_changes_are_flushed_before_older_history() {
+    let work_dir = WorkDir::new("recent-first-flush");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Initial sync")
+                .at(1672531200) // 2023-01-01
+                .write_json("courses.json", &json!([])),
+        )
+        .unwrap();
+    let versions = [
+        // 2023-06-01
+        (1685577600, json!([{ "crn": "1", "title": "Intro" }])),
+        // 2024-05-01
+        (
+            1714521600,
+            json!([{ "crn": "1", "title": "Intro" }, { "crn": "2" }]),
+        ),
+        // 2024-06-01
+        (
+            1717200000,
+            json!([{ "crn": "1", "title": "Intro to CS" }, { "crn": "2" }]),
+        ),
+    ];
+    for (i, (time, version)) in versions.iter().enumerate() {
+        fixture
+            .commit(
+                FixtureCommit::new(format!("Sync {i}"))
+                    .at(*time)
+                    .write_json("courses.json", version),
+            )
+            .unwrap();
+    }
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--recent-first-flush",
+            "2",
+            "--quiet",
+        ],
+    );
+    let mut flushes = vec![];
+    let extraction = extract::run_with_flush(&args, |recent| {
+        flushes.push(serde_json::to_value(&recent.change_records).unwrap());
+    })
+    .unwrap();
+
+    assert_eq!(flushes.len(), 1);
+    let recent = &flushes[0]["courses.json"];
+    assert_eq!(recent["1"]["added"], json!([]));
+    assert_eq!(recent["1"]["modified"].as_array().unwrap().len(), 1);
+    assert_eq!(recent["2"]["added"].as_array().unwrap().len(), 1);
+    let records = &extraction.change_records[Path::new("courses.json")];
+    assert_eq!(records["1"].added.len(), 1);
+    assert_eq!(records["1"].modified.len(), 1);
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use delorean::output;
+use serde_json::{json, Value};
+use std::fs;
+
+#[test]
+fn record_counts_follow_each_commit_that_changed_the_file() {
+    let work_dir = WorkDir::new("record-counts");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let mut sync = |message: &str, path: &str, records: Value| {
+        fixture
+            .commit(FixtureCommit::new(message).write_json(path, &records))
+            .unwrap()
+            .to_string()
+    };
+    sync("Initial sync", "courses.json", json!([]));
+    let add = sync(
+        "Add",
+        "courses.json",
+        json!([{ "crn": "1" }, { "crn": "2" }]),
+    );
+    sync("Add season", "seasons.json", json!([{ "crn": "1" }]));
+    let remove = sync("Remove", "courses.json", json!([{ "crn": "1" }]));
+    let counts_dir = work_dir.join("counts");
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--record-counts",
+            counts_dir.to_str().unwrap(),
+            "--quiet",
+        ],
+    );
+    output::write(&args, &extract::run(&args).unwrap());
+    let read = |path: &str| {
+        let counts = fs::read_to_string(counts_dir.join(path)).unwrap();
+        serde_json::from_str::<Value>(&counts)
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|count| {
+                let commit = count["commit"].as_str().unwrap().to_string();
+                (commit, count["count"].as_u64().unwrap())
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(read("courses.json"), [(add, 2), (remove, 1)]);
+    assert_eq!(read("seasons.json").len(), 1);
+}
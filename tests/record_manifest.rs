@@ -0,0 +1,52 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::output;
+use serde_json::Value;
+use std::fs;
+
+#[test]
+fn record_manifest_points_at_each_records_entry() {
+    let work_dir = WorkDir::new("record-manifest");
+    let fixture = work_dir.selftest_fixture();
+    let output_dir = work_dir.join("output");
+    let manifest_path = work_dir.join("manifest.json");
+    let args = common::selftest_args(
+        &fixture,
+        &[
+            output_dir.to_str().unwrap(),
+            "--record-manifest",
+            manifest_path.to_str().unwrap(),
+        ],
+    );
+    output::write(&args, &extract::run(&args).unwrap());
+    let manifest = fs::read_to_string(&manifest_path).unwrap();
+    let manifest = serde_json::from_str::<Value>(&manifest).unwrap();
+
+    let mut located = 0;
+    for (key, locations) in manifest.as_object().unwrap() {
+        for location in locations.as_array().unwrap() {
+            let file = output_dir.join(location["file"].as_str().unwrap());
+            let content = fs::read(&file).unwrap();
+            let (start, end) = (
+                location["start"].as_u64().unwrap() as usize,
+                location["end"].as_u64().unwrap() as usize,
+            );
+            let entry = serde_json::from_slice::<Value>(&content[start..end]).unwrap();
+            let file = serde_json::from_slice::<Value>(&content).unwrap();
+            assert_eq!(entry, file[key], "{key}");
+            located += 1;
+        }
+    }
+    let entries = fs::read_dir(output_dir.join("parsed_courses"))
+        .unwrap()
+        .map(|file| {
+            let content = fs::read(file.unwrap().path()).unwrap();
+            let file = serde_json::from_slice::<Value>(&content).unwrap();
+            file.as_object().unwrap().len()
+        })
+        .sum::<usize>();
+    assert!(entries > 0);
+    assert_eq!(located, entries);
+}
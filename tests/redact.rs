@@ -0,0 +1,70 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn redacted_fields_never_reach_the_output() {
+    let work_dir = WorkDir::new("redact");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("courses.json", &json!([])))
+        .unwrap();
+    let versions = [
+        json!([
+            { "crn": "1", "title": "Intro", "instructor_email": "a@example.com", "notes": "x" },
+            { "crn": "2", "title": "Data", "instructor_email": "b@example.com" },
+        ]),
+        // Only a redacted field changes
+        json!([
+            { "crn": "1", "title": "Intro", "instructor_email": "c@example.com", "notes": "x" },
+            { "crn": "2", "title": "Data", "instructor_email": "b@example.com" },
+        ]),
+        json!([
+            { "crn": "1", "title": "Intro to CS", "instructor_email": "c@example.com", "notes": "y" },
+        ]),
+    ];
+    for (i, version) in versions.iter().enumerate() {
+        fixture
+            .commit(FixtureCommit::new(format!("Sync {i}")).write_json("courses.json", version))
+            .unwrap();
+    }
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--redact-fields",
+            "/instructor_email,/notes",
+            "--include-values",
+            "full",
+            "--patches",
+            "--capture",
+            "/instructor_email",
+            "--graveyard",
+            work_dir.join("graveyard").to_str().unwrap(),
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+
+    let records =
+        serde_json::to_string(&extraction.change_records[Path::new("courses.json")]).unwrap();
+    let graveyard = serde_json::to_string(&extraction.graveyard).unwrap();
+    for output in [&records, &graveyard] {
+        assert!(!output.contains("example.com"));
+        assert!(!output.contains("notes"));
+    }
+    let record = &extraction.change_records[Path::new("courses.json")]["1"];
+    assert_eq!(record.modified.len(), 1);
+    assert_eq!(
+        serde_json::to_value(record).unwrap()["modified"][0]["new"],
+        json!({ "crn": "1", "title": "Intro to CS" })
+    );
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "redis")]
+
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::output;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::thread;
+
+/// Reads the RESP length line after `prefix` (`*` for arrays, `$` for bulk strings).
+fn read_length(reader: &mut impl BufRead, prefix: char) -> Option<usize> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).ok()? == 0 {
+        return None;
+    }
+    line.trim_end().strip_prefix(prefix)?.parse().ok()
+}
+
+/// Accepts one connection, answers every command with `+OK`, and returns the commands received.
+fn fake_server(listener: TcpListener) -> Vec<Vec<String>> {
+    let (stream, _) = listener.accept().unwrap();
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut commands = vec![];
+    while let Some(len) = read_length(&mut reader, '*') {
+        let command = (0..len)
+            .map(|_| {
+                let len = read_length(&mut reader, '$').unwrap();
+                let mut arg = vec![0; len + 2];
+                reader.read_exact(&mut arg).unwrap();
+                arg.truncate(len);
+                String::from_utf8(arg).unwrap()
+            })
+            .collect();
+        commands.push(command);
+        writer.write_all(b"+OK\r\n").unwrap();
+    }
+    commands
+}
+
+#[test]
+fn histories_are_stored_under_record_keys() {
+    let work_dir = WorkDir::new("redis");
+    let fixture = work_dir.selftest_fixture();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("redis://{}/", listener.local_addr().unwrap());
+    let server = thread::spawn(move || fake_server(listener));
+    let args = common::selftest_args(&fixture, &["--redis", &url]);
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    let commands = server.join().unwrap();
+
+    let stored = commands
+        .iter()
+        .filter(|command| command[0] == "SET")
+        .map(|command| (command[1].clone(), command[2].clone()))
+        .collect::<HashMap<_, _>>();
+    let record_count = extraction
+        .change_records
+        .values()
+        .map(|records| records.len())
+        .sum::<usize>();
+    assert_eq!(stored.len(), record_count);
+    let record = &extraction.change_records[Path::new("parsed_courses/202301.json")]["1"];
+    assert_eq!(
+        stored["history:parsed_courses/202301.json:1"],
+        serde_json::to_string(record).unwrap()
+    );
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn files_renamed_into_or_out_of_the_included_paths_are_recorded() {
+    let work_dir = WorkDir::new("renames");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let courses = json!([
+        { "crn": "1", "title": "Intro to CS" },
+        { "crn": "2", "title": "Data Structures" },
+    ]);
+    fixture
+        .commit(
+            FixtureCommit::new("Initial sync")
+                .at(1_000)
+                .write_json("staging/202301.json", &courses),
+        )
+        .unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Publish")
+                .at(2_000)
+                .remove("staging/202301.json")
+                .write_json("courses/202301.json", &courses),
+        )
+        .unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Archive")
+                .at(3_000)
+                .remove("courses/202301.json")
+                .write_json("archive/202301.json", &courses),
+        )
+        .unwrap();
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "courses/*.json",
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+
+    assert_eq!(extraction.change_records.len(), 1);
+    let records = &extraction.change_records[Path::new("courses/202301.json")];
+    assert_eq!(records.len(), 2);
+    for record in records.values() {
+        assert_eq!((record.added.len(), record.removed.len()), (1, 1));
+        assert_eq!(record.added[0].instant.timestamp, 2_000);
+        assert_eq!(record.removed[0].instant.timestamp, 3_000);
+    }
+}
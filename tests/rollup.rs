@@ -0,0 +1,56 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use delorean::output;
+use serde_json::{json, Value};
+use std::fs;
+
+#[test]
+fn rollup_counts_each_records_changes_per_period() {
+    let work_dir = WorkDir::new("rollup");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    // 2023-01-30T00:00:00Z, with a commit a day after
+    let mut time = 1675036800;
+    let mut sync = |records: Value| {
+        fixture
+            .commit(
+                FixtureCommit::new("Sync")
+                    .at(time)
+                    .write_json("courses.json", &records),
+            )
+            .unwrap();
+        time += 24 * 60 * 60;
+    };
+    sync(json!([]));
+    sync(json!([{ "crn": "1", "seats": 1 }]));
+    sync(json!([{ "crn": "1", "seats": 2 }]));
+    sync(json!([{ "crn": "1", "seats": 3 }]));
+    sync(json!([]));
+    let output_dir = work_dir.join("output");
+    let args = common::extract_args(
+        &fixture,
+        &[
+            output_dir.to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--rollup",
+            "monthly",
+            "--quiet",
+        ],
+    );
+    output::write(&args, &extract::run(&args).unwrap());
+    let written = fs::read_to_string(output_dir.join("courses.json")).unwrap();
+    let written = serde_json::from_str::<Value>(&written).unwrap();
+
+    assert_eq!(
+        written["1"]["rollup"],
+        json!({
+            "2023-01": { "added": 1, "removed": 0, "modified": 0 },
+            "2023-02": { "added": 0, "removed": 1, "modified": 2 },
+        })
+    );
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use common::WorkDir;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::{json, Value};
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn changes_matching_rules_are_streamed_with_the_rule_name() {
+    let work_dir = WorkDir::new("rules");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let mut sync = |message: &str, records: Value| {
+        fixture
+            .commit(FixtureCommit::new(message).write_json("courses.json", &records))
+            .unwrap()
+            .to_string()
+    };
+    sync(
+        "Initial sync",
+        json!([
+            { "crn": "1", "school": "YC", "times_by_day": "M", "seats": 1 },
+            { "crn": "2", "school": "YC" },
+            { "crn": "3", "school": "SOM" },
+        ]),
+    );
+    let reschedule = sync(
+        "Reschedule",
+        json!([
+            { "crn": "1", "school": "YC", "times_by_day": "T", "seats": 1 },
+            { "crn": "2", "school": "YC" },
+            { "crn": "3", "school": "SOM" },
+        ]),
+    );
+    // Neither a schedule change nor a removal
+    sync(
+        "Seats",
+        json!([
+            { "crn": "1", "school": "YC", "times_by_day": "T", "seats": 2 },
+            { "crn": "2", "school": "YC" },
+            { "crn": "3", "school": "SOM" },
+        ]),
+    );
+    let remove = sync(
+        "Remove",
+        json!([{ "crn": "1", "school": "YC", "times_by_day": "T", "seats": 2 }]),
+    );
+    let rules = work_dir.join("rules.yaml");
+    fs::write(
+        &rules,
+        "\
+- name: schedule-changed
+  change_type: modified
+  changed: /times_by_day
+- name: yc-course-removed
+  change_type: removed
+  where:
+    /school: YC
+",
+    )
+    .unwrap();
+    let run = Command::new(env!("CARGO_BIN_EXE_delorean"))
+        .args([
+            fixture.path().to_str().unwrap(),
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--rules",
+            rules.to_str().unwrap(),
+            "--rules-output",
+            "-",
+        ])
+        .output()
+        .unwrap();
+    let mut matches = String::from_utf8(run.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let event = serde_json::from_str::<Value>(line).unwrap();
+            let field = |name: &str| event[name].as_str().unwrap().to_string();
+            (
+                field("rule"),
+                field("primary_key"),
+                field("change_type"),
+                field("commit"),
+            )
+        })
+        .collect::<Vec<_>>();
+    matches.sort();
+
+    assert!(run.status.success());
+    assert_eq!(
+        matches,
+        [
+            (
+                "schedule-changed".to_string(),
+                "1".to_string(),
+                "modified".to_string(),
+                reschedule
+            ),
+            (
+                "yc-course-removed".to_string(),
+                "2".to_string(),
+                "removed".to_string(),
+                remove
+            ),
+        ]
+    );
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use clap::Parser;
+use common::WorkDir;
+use delorean::extract::{self, ExtractArgs, Extraction};
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn samples_are_deterministic_subsets() {
+    let work_dir = WorkDir::new("sample");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("courses.json", &json!([])))
+        .unwrap();
+    for version in 0..3 {
+        let records = (0..500)
+            .map(|crn| json!({ "crn": crn.to_string(), "seats": crn % (version + 2) }))
+            .collect::<Vec<_>>();
+        fixture
+            .commit(
+                FixtureCommit::new(format!("Sync {version}"))
+                    .write_json("courses.json", &json!(records)),
+            )
+            .unwrap();
+    }
+    let run = |extra: &[&str]| {
+        let args = ExtractArgs::parse_from(
+            [
+                "delorean",
+                fixture.path().to_str().unwrap(),
+                work_dir.join("output").to_str().unwrap(),
+                "--primary-key",
+                "crn",
+                "--include",
+                "*.json",
+                "--quiet",
+            ]
+            .iter()
+            .chain(extra),
+        );
+        extract::run(&args).unwrap()
+    };
+    let full = run(&[]);
+    let small = run(&["--sample", "10%"]);
+    let small_again = run(&["--sample", "10%"]);
+    let large = run(&["--sample", "50%"]);
+
+    let records = |extraction: &Extraction| {
+        serde_json::to_value(&extraction.change_records[Path::new("courses.json")]).unwrap()
+    };
+    let (full, small, large) = (records(&full), records(&small), records(&large));
+    assert_eq!(small, records(&small_again));
+    let count = small.as_object().unwrap().len();
+    assert!((20..=80).contains(&count), "{count} of 500 keys sampled");
+    for (key, record) in small.as_object().unwrap() {
+        assert_eq!(record, &full[key]);
+        assert_eq!(record, &large[key]);
+    }
+    assert!(large.as_object().unwrap().len() > count);
+}
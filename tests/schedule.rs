@@ -0,0 +1,77 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use delorean::predicate::Schedule;
+use serde_json::json;
+use std::path::Path;
+
+/// 2024-09-02, a Monday, at midnight UTC
+const MONDAY: i64 = 1725235200;
+const HOUR: i64 = 3600;
+const DAY: i64 = 24 * HOUR;
+
+#[test]
+fn changes_outside_the_schedule_are_flagged() {
+    let work_dir = WorkDir::new("schedule");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Initial sync")
+                .at(MONDAY - DAY)
+                .write_json("courses.json", &json!([])),
+        )
+        .unwrap();
+    let versions = [
+        // Monday 02:30, during the sync window
+        (MONDAY + 5 * HOUR / 2, json!([{ "crn": "1" }])),
+        // Saturday noon, a manual fix
+        (
+            MONDAY + 5 * DAY + 12 * HOUR,
+            json!([{ "crn": "1" }, { "crn": "2" }]),
+        ),
+    ];
+    for (i, (time, version)) in versions.iter().enumerate() {
+        fixture
+            .commit(
+                FixtureCommit::new(format!("Sync {i}"))
+                    .at(*time)
+                    .write_json("courses.json", version),
+            )
+            .unwrap();
+    }
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--flag-outside-schedule",
+            "Mon-Fri 02:00-04:00",
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+
+    let records =
+        serde_json::to_value(&extraction.change_records[Path::new("courses.json")]).unwrap();
+    assert!(records["1"]["added"][0].get("outside_schedule").is_none());
+    assert_eq!(records["2"]["added"][0]["outside_schedule"], true);
+}
+
+#[test]
+fn windows_can_run_past_midnight() {
+    let schedule: Schedule = "Fri,Sun 22:00-02:00".parse().unwrap();
+    // Friday 23:00 and Saturday 01:00 are in the window Friday starts
+    assert!(schedule.contains(MONDAY + 4 * DAY + 23 * HOUR));
+    assert!(schedule.contains(MONDAY + 5 * DAY + HOUR));
+    // Saturday 23:00 is not
+    assert!(!schedule.contains(MONDAY + 5 * DAY + 23 * HOUR));
+    // Monday 01:00 is in the window Sunday starts
+    assert!(schedule.contains(MONDAY + 7 * DAY + HOUR));
+    assert!("Mon-Fri".parse::<Schedule>().is_err());
+    assert!("Mon-Someday 02:00-04:00".parse::<Schedule>().is_err());
+}
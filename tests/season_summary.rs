@@ -0,0 +1,80 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use delorean::output;
+use serde_json::{json, Value};
+use std::fs;
+
+#[test]
+fn season_summary_sums_weekly_changes_by_season() {
+    let work_dir = WorkDir::new("season-summary");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Initial sync")
+                .write_json("202301.json", &json!([]))
+                .write_json("202303.json", &json!([])),
+        )
+        .unwrap();
+    // Monday 2023-01-02, in 2023-W01
+    fixture
+        .commit(FixtureCommit::new("Add").at(1672617600).write_json(
+            "202301.json",
+            &json!([
+                { "crn": "1" },
+                // Listed early for the next season
+                { "crn": "2", "season_code": "202303" },
+            ]),
+        ))
+        .unwrap();
+    // Monday 2023-01-09, in 2023-W02
+    fixture
+        .commit(
+            FixtureCommit::new("Sync")
+                .at(1673222400)
+                .write_json(
+                    "202301.json",
+                    &json!([
+                        { "crn": "1", "seats": 1 },
+                        { "crn": "2", "season_code": "202303" },
+                    ]),
+                )
+                .write_json("202303.json", &json!([{ "crn": "3" }])),
+        )
+        .unwrap();
+    let summary_path = work_dir.join("summary.json");
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--season-field",
+            "/season_code",
+            "--season-summary",
+            summary_path.to_str().unwrap(),
+            "--quiet",
+        ],
+    );
+    output::write(&args, &extract::run(&args).unwrap());
+    let summary = fs::read_to_string(&summary_path).unwrap();
+    let summary = serde_json::from_str::<Value>(&summary).unwrap();
+
+    assert_eq!(
+        summary,
+        json!({
+            "202301": {
+                "2023-W01": { "added": 1, "removed": 0, "modified": 0 },
+                "2023-W02": { "added": 0, "removed": 0, "modified": 1 },
+            },
+            "202303": {
+                "2023-W01": { "added": 1, "removed": 0, "modified": 0 },
+                "2023-W02": { "added": 1, "removed": 0, "modified": 0 },
+            },
+        })
+    );
+}
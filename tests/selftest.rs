@@ -0,0 +1,12 @@
+mod common;
+
+use common::WorkDir;
+
+#[test]
+fn selftest_matches_golden_outputs() {
+    let work_dir = WorkDir::new("selftest");
+    let result = delorean::selftest::run(&work_dir);
+    if let Err(mismatches) = result {
+        panic!("{}", mismatches.join("\n"));
+    }
+}
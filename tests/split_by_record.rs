@@ -0,0 +1,58 @@
+mod common;
+
+use clap::Parser;
+use common::WorkDir;
+use delorean::extract::{self, ExtractArgs};
+use delorean::output;
+use delorean::output::record_file_path;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn records_are_split_into_sharded_files() {
+    let work_dir = WorkDir::new("split-by-record");
+    let fixture = work_dir.selftest_fixture();
+    let args_for = |output_dir: &Path, extra: &[&str]| {
+        let mut arguments = vec![
+            "delorean",
+            fixture.path().to_str().unwrap(),
+            output_dir.to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "parsed_courses/*.json",
+            "--quiet",
+        ];
+        arguments.extend(extra);
+        ExtractArgs::parse_from(arguments)
+    };
+    let split_dir = work_dir.join("split");
+    let args = args_for(&split_dir, &["--split-by", "record"]);
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    let plain_dir = work_dir.join("plain");
+    output::write(&args_for(&plain_dir, &[]), &extraction);
+    let read = |file: &Path| -> serde_json::Value {
+        serde_json::from_slice(&fs::read(file).unwrap()).unwrap()
+    };
+    let plain = read(&plain_dir.join("parsed_courses/202301.json"));
+    let records = plain
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(|key| {
+            let file = split_dir
+                .join("parsed_courses/202301")
+                .join(record_file_path(key));
+            (key.clone(), read(&file))
+        })
+        .collect::<serde_json::Map<_, _>>();
+
+    assert_eq!(serde_json::Value::Object(records), plain);
+    assert_eq!(
+        record_file_path("2"),
+        Path::new("d8/2.json"),
+        "the shard is the start of `git hash-object` of the key"
+    );
+    assert_eq!(record_file_path("a/b c"), Path::new("82/a%2Fb%20c.json"));
+}
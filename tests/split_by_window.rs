@@ -0,0 +1,111 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use delorean::output;
+use serde_json::json;
+use std::fs;
+
+#[test]
+fn changes_are_split_into_yearly_files() {
+    let work_dir = WorkDir::new("split-by-window");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Initial sync")
+                .at(1514764800) // 2018-01-01
+                .write_json("courses.json", &json!([])),
+        )
+        .unwrap();
+    let versions = [
+        // 2018-06-01
+        (1527811200, json!([{ "crn": "1", "title": "Intro" }])),
+        // 2019-03-01
+        (
+            1551398400,
+            json!([{ "crn": "1", "title": "Intro to CS" }, { "crn": "2" }]),
+        ),
+        // 2019-09-01
+        (1567296000, json!([{ "crn": "2" }])),
+    ];
+    let mut commits = vec![];
+    for (i, (time, version)) in versions.iter().enumerate() {
+        let commit = fixture
+            .commit(
+                FixtureCommit::new(format!("Sync {i}"))
+                    .at(*time)
+                    .write_json("courses.json", version),
+            )
+            .unwrap();
+        commits.push(commit.to_string());
+    }
+    let output_dir = work_dir.join("output");
+    let args = common::extract_args(
+        &fixture,
+        &[
+            output_dir.to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--split-by-window",
+            "year",
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    let read = |file: &str| -> serde_json::Value {
+        serde_json::from_slice(&fs::read(output_dir.join("courses.json").join(file)).unwrap())
+            .unwrap()
+    };
+    let (index, window_2018, window_2019) =
+        (read("index.json"), read("2018.json"), read("2019.json"));
+
+    assert_eq!(
+        index,
+        json!([
+            {
+                "window": "2018",
+                "file": "2018.json",
+                "records": 1,
+                "first_commit": commits[0],
+                "last_commit": commits[0],
+            },
+            {
+                "window": "2019",
+                "file": "2019.json",
+                "records": 2,
+                "first_commit": commits[1],
+                "last_commit": commits[2],
+            },
+        ])
+    );
+    let commits_of = |window: &serde_json::Value, key: &str, change_type: &str| {
+        window[key][change_type]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|change| change["commit"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        commits_of(&window_2018, "1", "added"),
+        [commits[0].as_str()]
+    );
+    assert!(window_2018.get("2").is_none());
+    assert_eq!(commits_of(&window_2019, "1", "added"), Vec::<String>::new());
+    assert_eq!(
+        commits_of(&window_2019, "1", "modified"),
+        [commits[1].as_str()]
+    );
+    assert_eq!(
+        commits_of(&window_2019, "1", "removed"),
+        [commits[2].as_str()]
+    );
+    assert_eq!(
+        commits_of(&window_2019, "2", "added"),
+        [commits[1].as_str()]
+    );
+}
@@ -0,0 +1,34 @@
+#![cfg(feature = "sqlite")]
+
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::output;
+
+#[test]
+fn sqlite_output_holds_every_change() {
+    let work_dir = WorkDir::new("sqlite");
+    let fixture = work_dir.selftest_fixture();
+    let database = work_dir.join("history.db");
+    let args = common::selftest_args(
+        &fixture,
+        &[database.to_str().unwrap(), "--output-format", "sqlite"],
+    );
+    output::write(&args, &extract::run(&args).unwrap());
+    let connection = rusqlite::Connection::open(&database).unwrap();
+    let change_types = connection
+        .prepare(
+            "SELECT change_type FROM changes JOIN records ON records.id = record_id \
+             JOIN files ON files.id = file_id \
+             WHERE path = 'parsed_courses/202301.json' AND primary_key = '2'",
+        )
+        .unwrap()
+        .query_map([], |row| row.get::<_, String>(0))
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    drop(connection);
+
+    assert_eq!(change_types, ["added", "removed"]);
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use clap::Parser;
+use common::WorkDir;
+use delorean::extract::{self, ExtractArgs};
+use delorean::output;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn dash_writes_the_merged_output_to_stdout() {
+    let work_dir = WorkDir::new("stdout");
+    let fixture = work_dir.selftest_fixture();
+    let merged_file = work_dir.join("changes.json");
+    let arguments = |output_path: &str| {
+        [
+            "delorean",
+            fixture.path().to_str().unwrap(),
+            output_path,
+            "--primary-key",
+            "crn",
+            "--include",
+            "parsed_courses/*.json",
+        ]
+        .map(String::from)
+    };
+    let args = ExtractArgs::parse_from(
+        arguments(merged_file.to_str().unwrap())
+            .into_iter()
+            .chain(["--merge-output".to_string(), "--quiet".to_string()]),
+    );
+    output::write(&args, &extract::run(&args).unwrap());
+    let run = Command::new(env!("CARGO_BIN_EXE_delorean"))
+        .args(&arguments("-")[1..])
+        .output()
+        .unwrap();
+    let merged = fs::read(&merged_file).unwrap();
+
+    assert!(run.status.success());
+    assert_eq!(
+        String::from_utf8(run.stdout).unwrap(),
+        String::from_utf8(merged).unwrap()
+    );
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::WorkDir;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use delorean::suggest;
+use serde_json::json;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn only_commits_without_data_changes_are_suggested() {
+    let work_dir = WorkDir::new("suggest");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json(
+            "courses.json",
+            &json!([{ "crn": "1", "seats": 1, "times": ["Mon", "Wed"] }]),
+        ))
+        .unwrap();
+    fixture
+        .commit(FixtureCommit::new("Modify").write_json(
+            "courses.json",
+            &json!([{ "crn": "1", "seats": 2, "times": ["Mon", "Wed"] }]),
+        ))
+        .unwrap();
+    let reorder = fixture
+        .commit(FixtureCommit::new("Reorder").write_json(
+            "courses.json",
+            &json!([{ "crn": "1", "seats": 2.0, "times": ["Wed", "Mon"] }]),
+        ))
+        .unwrap();
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--changes",
+            "added,removed",
+            "--where",
+            "/seats == 0",
+            "--quiet",
+        ],
+    );
+    let extraction = suggest::run(&args).unwrap();
+    let ignore_revs = work_dir.join("ignore-revs");
+    suggest::write_ignore_revs(&ignore_revs, &extraction).unwrap();
+    let ignore_revs = fs::read_to_string(&ignore_revs).unwrap();
+
+    assert_eq!(ignore_revs, format!("# 2023-01-03 Reorder\n{reorder}\n"),);
+}
+
+#[test]
+fn suggest_ignores_leaves_the_output_path_alone() {
+    let work_dir = WorkDir::new("suggest-output");
+    let fixture = work_dir.selftest_fixture();
+    let output_dir = work_dir.join("output");
+    let ignore_revs = work_dir.join("ignore-revs");
+    let run = Command::new(env!("CARGO_BIN_EXE_delorean"))
+        .args([
+            "suggest-ignores",
+            fixture.path().to_str().unwrap(),
+            output_dir.to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "parsed_courses/*.json",
+            "--quiet",
+            "--write",
+            ignore_revs.to_str().unwrap(),
+        ])
+        .output()
+        .unwrap();
+
+    assert!(run.status.success());
+    assert!(ignore_revs.exists());
+    assert!(!output_dir.exists());
+}
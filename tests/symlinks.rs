@@ -0,0 +1,65 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::os::unix::fs::symlink;
+use std::path::Path;
+
+#[test]
+fn symlinks_are_followed_within_the_repository() {
+    let work_dir = WorkDir::new("symlinks");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Sync").write_json(
+            "data/courses.json",
+            &json!([{ "crn": "1", "title": "Intro" }]),
+        ))
+        .unwrap();
+    for (link, target) in [
+        ("courses.json", "data/courses.json"),
+        ("link.json", "courses.json"),
+        ("outside.json", "../outside.json"),
+        ("dangling.json", "data/missing.json"),
+    ] {
+        symlink(target, fixture.path().join(link)).unwrap();
+        let mut index = fixture.repository().index().unwrap();
+        index.add_path(Path::new(link)).unwrap();
+        index.write().unwrap();
+    }
+    fixture.commit(FixtureCommit::new("Link")).unwrap();
+    let extraction = extract::run(&common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--quiet",
+        ],
+    ))
+    .unwrap();
+
+    assert!(extraction.errors.is_empty());
+    for path in ["courses.json", "link.json"] {
+        let record = &extraction.change_records[Path::new(path)]["1"];
+        assert_eq!(record.added.len(), 1, "{path}");
+    }
+    let warnings = extraction
+        .warnings
+        .iter()
+        .map(|warning| (warning.path.to_str().unwrap(), warning.message.as_str()))
+        .collect::<Vec<_>>();
+    assert!(warnings.contains(&(
+        "outside.json",
+        "Skipped file: Symlink outside.json points outside the repository"
+    )));
+    assert!(warnings.contains(&(
+        "dangling.json",
+        "Skipped file: data/missing.json does not exist"
+    )));
+}
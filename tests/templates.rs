@@ -0,0 +1,45 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::output;
+use std::fs;
+
+#[test]
+fn templates_name_and_shape_record_files() {
+    let work_dir = WorkDir::new("templates");
+    let fixture = work_dir.selftest_fixture();
+    let body_template = work_dir.join("body.hbs");
+    fs::write(
+        &body_template,
+        r#"{"course": "{{primary_key}}", "file": "{{path}}", "added": {{json record.added}}}"#,
+    )
+    .unwrap();
+    let output_dir = work_dir.join("output");
+    let args = common::selftest_args(
+        &fixture,
+        &[
+            output_dir.to_str().unwrap(),
+            "--split-by",
+            "record",
+            "--path-template",
+            "{{path}}/{{primary_key}}.json",
+            "--body-template",
+            body_template.to_str().unwrap(),
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    let body: serde_json::Value =
+        serde_json::from_slice(&fs::read(output_dir.join("parsed_courses/202301/2.json")).unwrap())
+            .unwrap();
+
+    let record =
+        &extraction.change_records[std::path::Path::new("parsed_courses/202301.json")]["2"];
+    assert_eq!(body["course"], "2");
+    assert_eq!(body["file"], "parsed_courses/202301.json");
+    assert_eq!(
+        body["added"],
+        serde_json::to_value(record).unwrap()["added"]
+    );
+}
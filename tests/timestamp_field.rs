@@ -0,0 +1,63 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use delorean::output;
+use serde_json::json;
+use std::path::Path;
+
+/// 2024-09-01T00:00:00Z
+const UPDATED: i64 = 1725148800;
+
+#[test]
+fn timestamps_in_the_data_time_changes_unless_out_of_range() {
+    let work_dir = WorkDir::new("timestamp-field");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("courses.json", &json!([])))
+        .unwrap();
+    fixture
+        .commit(FixtureCommit::new("Sync").write_json(
+            "courses.json",
+            &json!([
+                { "crn": "1", "updated": UPDATED },
+                { "crn": "2", "updated": "2024-09-01 00:00:00" },
+                { "crn": "3", "updated": UPDATED * 1_000_000 },
+                { "crn": "4", "updated": "yesterday" },
+            ]),
+        ))
+        .unwrap();
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--timestamp-field",
+            "/updated",
+            "--rollup",
+            "monthly",
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+
+    let records = &extraction.change_records[Path::new("courses.json")];
+    let added_at = |pk: &str| records[pk].added[0].instant.timestamp;
+    assert_eq!(added_at("1"), UPDATED);
+    assert_eq!(added_at("2"), UPDATED);
+    let commit_time = fixture
+        .repository()
+        .head()
+        .unwrap()
+        .peel_to_commit()
+        .unwrap()
+        .time()
+        .seconds();
+    assert_eq!(added_at("3"), commit_time);
+    assert_eq!(added_at("4"), commit_time);
+}
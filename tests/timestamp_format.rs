@@ -0,0 +1,39 @@
+mod common;
+
+use clap::Parser;
+use common::WorkDir;
+use delorean::extract::{self, ExtractArgs};
+use std::path::Path;
+
+#[test]
+fn timestamps_are_written_in_the_requested_format() {
+    let work_dir = WorkDir::new("timestamp-format");
+    let fixture = work_dir.selftest_fixture();
+    let added_timestamp = |format: &str| {
+        let args = common::selftest_args(
+            &fixture,
+            &[
+                work_dir.join("output").to_str().unwrap(),
+                "--timestamp-format",
+                format,
+            ],
+        );
+        let extraction = extract::run(&args).unwrap();
+        let record = &extraction.change_records[Path::new("parsed_courses/202301.json")]["2"];
+        serde_json::to_value(record).unwrap()["added"][0]["timestamp"].clone()
+    };
+    let rfc3339 = added_timestamp("rfc3339");
+    let unix = added_timestamp("unix");
+    let unix_millis = added_timestamp("unix-millis");
+    let date = added_timestamp("%Y-%m-%d");
+
+    assert_eq!(rfc3339, "2023-01-06T00:00:00+00:00");
+    assert_eq!(unix, 1_672_963_200);
+    assert_eq!(unix_millis, 1_672_963_200_000i64);
+    assert_eq!(date, "2023-01-06");
+    for invalid in ["unix_millis", "%Q"] {
+        assert!(
+            ExtractArgs::try_parse_from(["delorean", ".", "--timestamp-format", invalid]).is_err()
+        );
+    }
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn tombstoned_records_are_removed_and_re_added() {
+    let work_dir = WorkDir::new("tombstone");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("courses.json", &json!([])))
+        .unwrap();
+    for (message, status) in [
+        ("Open", "open"),
+        ("Cancel", "cancelled"),
+        ("Rename while cancelled", "cancelled"),
+        ("Reinstate", "open"),
+    ] {
+        let title = if message == "Open" {
+            "Intro"
+        } else {
+            "Intro to CS"
+        };
+        fixture
+            .commit(FixtureCommit::new(message).write_json(
+                "courses.json",
+                &json!([
+                    { "crn": "1", "title": title, "status": status },
+                    { "crn": "2", "title": "Always cancelled", "status": "cancelled" },
+                ]),
+            ))
+            .unwrap();
+    }
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--tombstone-field",
+            "status=cancelled",
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+
+    let records = &extraction.change_records[Path::new("courses.json")];
+    assert!(!records.contains_key("2"));
+    let record = &records["1"];
+    assert_eq!(record.added.len(), 2);
+    assert_eq!(record.removed.len(), 1);
+    assert!(record.modified.is_empty());
+    // Newest first: reinstated after being cancelled after being opened.
+    assert!(record.added[0].instant.timestamp > record.removed[0].instant.timestamp);
+    assert!(record.removed[0].instant.timestamp > record.added[1].instant.timestamp);
+}
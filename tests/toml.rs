@@ -0,0 +1,77 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use delorean::input::{self, InputFormat};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn arrays_of_tables_are_diffed_like_arrays() {
+    let work_dir = WorkDir::new("toml");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Initial sync")
+                .write("courses.toml", "course = []\n")
+                .write_json("courses.json", &json!([])),
+        )
+        .unwrap();
+    let versions = [
+        (
+            "[[course]]\ncrn = '1'\ntitle = 'Intro'\n\n[[course]]\ncrn = '2'\ntitle = 'Data'\n",
+            json!([{ "crn": "1", "title": "Intro" }, { "crn": "2", "title": "Data" }]),
+        ),
+        (
+            "[[course]]\ncrn = '1'\ntitle = 'Intro to CS'\n",
+            json!([{ "crn": "1", "title": "Intro to CS" }]),
+        ),
+    ];
+    for (i, (toml, json)) in versions.iter().enumerate() {
+        fixture
+            .commit(
+                FixtureCommit::new(format!("Sync {i}"))
+                    .write("courses.toml", *toml)
+                    .write_json("courses.json", json),
+            )
+            .unwrap();
+    }
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include-values",
+            "full",
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+
+    let history =
+        |path: &str| serde_json::to_value(&extraction.change_records[Path::new(path)]).unwrap();
+    assert_eq!(history("courses.toml"), history("courses.json"));
+    assert_eq!(
+        history("courses.toml")["1"]["modified"][0]["new"],
+        json!({ "crn": "1", "title": "Intro to CS" })
+    );
+}
+
+#[test]
+fn other_documents_are_tables() {
+    let document = "[1]\ntitle = 'Intro'\nupdated = 2024-09-02T02:30:00Z\n\n[2]\ntitle = 'Data'\n";
+    assert_eq!(
+        input::parse(document.as_bytes(), InputFormat::Toml).unwrap(),
+        json!({
+            "1": { "title": "Intro", "updated": "2024-09-02T02:30:00Z" },
+            "2": { "title": "Data" },
+        })
+    );
+    assert_eq!(
+        InputFormat::Auto.of(Path::new("courses.toml.gz")),
+        InputFormat::Toml
+    );
+    assert!(input::parse(b"title = ", InputFormat::Toml).is_err());
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use delorean::output;
+use serde_json::{json, Value};
+use std::fs;
+
+#[test]
+fn requested_trailers_annotate_the_changes_of_their_commit() {
+    let work_dir = WorkDir::new("trailers");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("courses.json", &json!([])))
+        .unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Sync\n\nSync-Batch: 42\nScraper: v2\n")
+                .write_json("courses.json", &json!([{ "crn": "1", "seats": 1 }])),
+        )
+        .unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Manual fix")
+                .write_json("courses.json", &json!([{ "crn": "1", "seats": 2 }])),
+        )
+        .unwrap();
+    let output_dir = work_dir.join("output");
+    let args = common::extract_args(
+        &fixture,
+        &[
+            output_dir.to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            // Matched case-insensitively
+            "--trailer",
+            "sync-batch",
+            "--trailer",
+            "Reviewed-By",
+            "--quiet",
+        ],
+    );
+    output::write(&args, &extract::run(&args).unwrap());
+    let written = fs::read_to_string(output_dir.join("courses.json")).unwrap();
+    let written = serde_json::from_str::<Value>(&written).unwrap();
+
+    assert_eq!(
+        written["1"]["added"][0]["trailers"],
+        json!({ "Sync-Batch": "42" })
+    );
+    // Commits without any requested trailer have no `trailers`
+    assert!(written["1"]["modified"][0].get("trailers").is_none());
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "tui")]
+
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::output;
+use delorean::record::ChangeType;
+use delorean::tui::Explorer;
+use ratatui::crossterm::event::KeyCode;
+
+#[test]
+fn explorer_searches_keys_and_shows_timelines() {
+    let work_dir = WorkDir::new("tui");
+    let fixture = work_dir.selftest_fixture();
+    let output_dir = work_dir.join("output");
+    let args = common::selftest_args(
+        &fixture,
+        &[
+            output_dir.to_str().unwrap(),
+            "--changed-fields",
+            "--write-meta",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+    output::write(&args, &extraction);
+    delorean::meta::write(&args, vec![]).unwrap();
+    let mut explorer = Explorer::open(&output_dir).unwrap();
+
+    let mut paths = extraction
+        .change_records
+        .keys()
+        .cloned()
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert_eq!(explorer.files(), paths);
+    let records = &extraction.change_records[explorer.selected_file().unwrap()];
+    assert_eq!(explorer.keys().len(), records.len());
+
+    let (key, record) = records
+        .iter()
+        .find(|(_, record)| !record.modified.is_empty())
+        .unwrap();
+    for code in [KeyCode::Char('/')]
+        .into_iter()
+        .chain(key.chars().map(KeyCode::Char))
+        .chain([KeyCode::Enter])
+    {
+        assert!(explorer.handle(code).unwrap());
+    }
+    assert!(explorer.keys().iter().all(|k| k.contains(key.as_str())));
+    while explorer.selected_key() != Some(key) {
+        explorer.handle(KeyCode::Down).unwrap();
+    }
+    let timeline = explorer.timeline();
+    let changes = record.added.len() + record.removed.len() + record.modified.len();
+    assert_eq!(timeline.len(), changes);
+    assert!(timeline
+        .windows(2)
+        .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+    let modification = timeline
+        .iter()
+        .find(|entry| entry.change_type == ChangeType::Modified)
+        .unwrap();
+    let change = record
+        .modified
+        .iter()
+        .find(|change| change.instant.commit == modification.commit)
+        .unwrap();
+    assert_eq!(Some(&modification.fields), change.changed_fields.as_ref());
+
+    assert!(explorer.handle(KeyCode::Tab).unwrap());
+    assert!(!explorer.handle(KeyCode::Char('q')).unwrap());
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use delorean::output;
+use serde_json::json;
+use std::fs;
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn unchanged_output_files_are_not_rewritten() {
+    let work_dir = WorkDir::new("unchanged-outputs");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Initial sync")
+                .write_json("courses.json", &json!([]))
+                .write_json("seasons.json", &json!([])),
+        )
+        .unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Sync")
+                .write_json("courses.json", &json!([{ "crn": "1" }]))
+                .write_json("seasons.json", &json!([{ "crn": "1" }])),
+        )
+        .unwrap();
+    let output_dir = work_dir.join("output");
+    let extract = |fixture: &FixtureRepo| {
+        let args = common::extract_args(
+            fixture,
+            &[
+                output_dir.to_str().unwrap(),
+                "--primary-key",
+                "crn",
+                "--include",
+                "*.json",
+                "--quiet",
+            ],
+        );
+        output::write(&args, &extract::run(&args).unwrap());
+    };
+    let long_ago = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    let backdate = |path: &str| {
+        fs::File::options()
+            .write(true)
+            .open(output_dir.join(path))
+            .unwrap()
+            .set_modified(long_ago)
+            .unwrap();
+    };
+    let modified = |path: &str| {
+        fs::metadata(output_dir.join(path))
+            .unwrap()
+            .modified()
+            .unwrap()
+    };
+    extract(&fixture);
+    backdate("courses.json");
+    backdate("seasons.json");
+    extract(&fixture);
+    let rerun = (modified("courses.json"), modified("seasons.json"));
+    fixture
+        .commit(
+            FixtureCommit::new("Add")
+                .write_json("courses.json", &json!([{ "crn": "1" }, { "crn": "2" }])),
+        )
+        .unwrap();
+    extract(&fixture);
+
+    assert_eq!(rerun, (long_ago, long_ago));
+    assert_ne!(modified("courses.json"), long_ago);
+    assert_eq!(modified("seasons.json"), long_ago);
+    assert!(fs::read_to_string(output_dir.join("courses.json"))
+        .unwrap()
+        .contains("\"2\""));
+}
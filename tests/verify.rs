@@ -0,0 +1,89 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::{output, verify};
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn verify_flags_instants_git_does_not_bear_out() {
+    let work_dir = WorkDir::new("verify");
+    let fixture = work_dir.selftest_fixture();
+    let output_dir = work_dir.join("output");
+    let args = common::selftest_args(&fixture, &[output_dir.to_str().unwrap()]);
+    output::write(&args, &extract::run(&args).unwrap());
+    let verification = verify::run(&args, 100).unwrap();
+    assert!(verification.checked > 0);
+    assert_eq!(verification.mismatches, Vec::<String>::new());
+
+    // Claim that record 3 was removed when it was added.
+    let path = output_dir.join("parsed_courses/202301.json");
+    let mut records: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+    let added = records["3"]["added"].clone();
+    records["3"]["removed"] = added;
+    fs::write(&path, records.to_string()).unwrap();
+    let verification = verify::run(&args, 100).unwrap();
+
+    assert_eq!(verification.mismatches.len(), 1);
+    assert!(verification.mismatches[0].contains(" 3: removed in "));
+}
+
+#[test]
+fn verify_runs_from_the_command_line() {
+    let work_dir = WorkDir::new("verify-cli");
+    let fixture = work_dir.selftest_fixture();
+    let output_dir = work_dir.join("output");
+    let args = common::selftest_args(&fixture, &[output_dir.to_str().unwrap()]);
+    output::write(&args, &extract::run(&args).unwrap());
+    let run = Command::new(env!("CARGO_BIN_EXE_delorean"))
+        .args([
+            "verify",
+            fixture.path().to_str().unwrap(),
+            output_dir.to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "parsed_courses/*.json",
+            "--replay",
+            "10",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(run.status.success());
+    assert!(String::from_utf8(run.stderr)
+        .unwrap()
+        .ends_with(" change instants, 0 inconsistent\n"));
+}
+
+#[test]
+fn compressed_output_is_rejected() {
+    let work_dir = WorkDir::new("verify-compressed");
+    let fixture = work_dir.selftest_fixture();
+    let output_dir = work_dir.join("output");
+    let args = common::selftest_args(
+        &fixture,
+        &[output_dir.to_str().unwrap(), "--compress", "gzip"],
+    );
+    output::write(&args, &extract::run(&args).unwrap());
+    let run = Command::new(env!("CARGO_BIN_EXE_delorean"))
+        .args([
+            "verify",
+            fixture.path().to_str().unwrap(),
+            output_dir.to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "parsed_courses/*.json",
+            "--compress",
+            "gzip",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(!run.status.success());
+    assert!(String::from_utf8(run.stderr)
+        .unwrap()
+        .contains("verify only reads uncompressed JSON output"));
+}
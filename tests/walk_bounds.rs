@@ -0,0 +1,174 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::collections::BTreeSet;
+
+/// A history with a merge: `side` and `main` both branch off the initial commit, and `merge`
+/// joins them.
+struct MergedHistory {
+    fixture: FixtureRepo,
+    side: String,
+    main: String,
+    merge: String,
+}
+
+impl MergedHistory {
+    fn build(work_dir: &WorkDir) -> Self {
+        let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+        let sync = |message: &str, crns: &[&str]| {
+            let records = crns.iter().map(|crn| json!({ "crn": crn })).collect();
+            FixtureCommit::new(message)
+                .write_json("courses.json", &serde_json::Value::Array(records))
+        };
+        fixture.commit(sync("Initial sync", &[])).unwrap();
+        let side = fixture.commit_aside(sync("Side", &["a"])).unwrap();
+        let main = fixture.commit(sync("Main", &["b"])).unwrap();
+        let merge = fixture
+            .commit(sync("Merge", &["a", "b"]).merge(side))
+            .unwrap();
+        MergedHistory {
+            fixture,
+            side: side.to_string(),
+            main: main.to_string(),
+            merge: merge.to_string(),
+        }
+    }
+
+    /// The commits of the changes extracted with `args`.
+    fn changed_commits(&self, work_dir: &WorkDir, args: &[&str]) -> BTreeSet<String> {
+        changed_commits(&self.fixture, work_dir, args)
+    }
+}
+
+/// The commits of the changes extracted from `fixture` with `args`.
+fn changed_commits(fixture: &FixtureRepo, work_dir: &WorkDir, args: &[&str]) -> BTreeSet<String> {
+    let mut arguments = vec![
+        work_dir.join("output").to_str().unwrap().to_string(),
+        "--primary-key".to_string(),
+        "crn".to_string(),
+        "--include".to_string(),
+        "*.json".to_string(),
+        "--quiet".to_string(),
+    ];
+    arguments.extend(args.iter().map(|arg| arg.to_string()));
+    let arguments = arguments.iter().map(String::as_str).collect::<Vec<_>>();
+    extract::run(&common::extract_args(fixture, &arguments))
+        .unwrap()
+        .change_records
+        .values()
+        .flat_map(|records| records.values())
+        .flat_map(|record| {
+            record
+                .added
+                .iter()
+                .chain(&record.removed)
+                .chain(&record.modified)
+        })
+        .map(|change| change.instant.commit.clone())
+        .collect()
+}
+
+fn commits(commits: &[&str]) -> BTreeSet<String> {
+    commits.iter().map(|commit| commit.to_string()).collect()
+}
+
+#[test]
+fn until_bounds_each_lineage() {
+    let work_dir = WorkDir::new("walk-bounds-until");
+    let history = MergedHistory::build(&work_dir);
+
+    assert_eq!(
+        history.changed_commits(&work_dir, &[]),
+        commits(&[&history.side, &history.main, &history.merge])
+    );
+    // The side branch is not an ancestor of `main`, so it is still walked
+    assert_eq!(
+        history.changed_commits(&work_dir, &["--until", &history.main]),
+        commits(&[&history.side, &history.merge])
+    );
+    assert_eq!(
+        history.changed_commits(
+            &work_dir,
+            &["--until", &history.main, "--until", &history.side]
+        ),
+        commits(&[&history.merge])
+    );
+}
+
+#[test]
+fn not_hides_revisions_and_ref_globs() {
+    let work_dir = WorkDir::new("walk-bounds-not");
+    let history = MergedHistory::build(&work_dir);
+    let repo = history.fixture.repository();
+    let side = repo.revparse_single(&history.side).unwrap();
+    repo.tag_lightweight("archived/side", &side, false).unwrap();
+
+    assert_eq!(
+        history.changed_commits(&work_dir, &["--not", &history.main]),
+        commits(&[&history.side, &history.merge])
+    );
+    assert_eq!(
+        history.changed_commits(&work_dir, &["--not", "refs/tags/archived/*"]),
+        commits(&[&history.main, &history.merge])
+    );
+    assert_eq!(
+        history.changed_commits(
+            &work_dir,
+            &["--not", "refs/tags/archived/*", "--until", &history.main]
+        ),
+        commits(&[&history.merge])
+    );
+}
+
+#[test]
+fn root_commits_end_only_their_own_lineage() {
+    let work_dir = WorkDir::new("walk-bounds-roots");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let sync = |message: &str, path: &str, crns: &[&str]| {
+        let records = crns.iter().map(|crn| json!({ "crn": crn })).collect();
+        FixtureCommit::new(message).write_json(path, &serde_json::Value::Array(records))
+    };
+    fixture
+        .commit(sync("Initial sync", "courses.json", &[]))
+        .unwrap();
+    let courses = fixture
+        .commit(sync("Courses", "courses.json", &["a"]))
+        .unwrap();
+    // A newer, unrelated history with its own root commit, e.g. another repository merged in.
+    let imported = {
+        let repo = fixture.repository();
+        let imported_commit = |day: i64, crns: &[&str], parents: &[&git2::Commit]| {
+            let records = crns.iter().map(|crn| json!({ "crn": crn })).collect();
+            let blob = repo
+                .blob(&serde_json::to_vec_pretty(&serde_json::Value::Array(records)).unwrap())
+                .unwrap();
+            let mut tree = repo.treebuilder(None).unwrap();
+            tree.insert("seminars.json", blob, 0o100644).unwrap();
+            let tree = repo.find_tree(tree.write().unwrap()).unwrap();
+            let time = git2::Time::new(1672531200 + day * 24 * 60 * 60, 0);
+            let signature =
+                git2::Signature::new("Imported", "imported@delorean.invalid", &time).unwrap();
+            let oid = repo
+                .commit(None, &signature, &signature, "Imported", &tree, parents)
+                .unwrap();
+            repo.find_commit(oid).unwrap()
+        };
+        let imported_root = imported_commit(2, &[], &[]);
+        imported_commit(3, &["s"], &[&imported_root]).id()
+    };
+    fixture
+        .commit(
+            sync("Merge", "seminars.json", &["s"])
+                .merge(imported)
+                .at(1672531200 + 4 * 24 * 60 * 60),
+        )
+        .unwrap();
+
+    // The imported root is walked before `courses`, which is still walked after it.
+    let changed = changed_commits(&fixture, &work_dir, &[]);
+    assert!(changed.contains(&imported.to_string()));
+    assert!(changed.contains(&courses.to_string()));
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::WorkDir;
+use delorean::errors;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn warnings_are_written_as_ndjson() {
+    let work_dir = WorkDir::new("warnings");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("courses.json", &json!([])))
+        .unwrap();
+    let commit = fixture
+        .commit(
+            FixtureCommit::new("Duplicate")
+                .write_json("courses.json", &json!([{ "crn": "1" }, { "crn": "1" }]))
+                .write_json("broken.json", &json!({ "crn": "2" })),
+        )
+        .unwrap();
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+    let warnings_path = work_dir.join("warnings.ndjson");
+    errors::write_ndjson(&warnings_path, &extraction.warnings, &extraction.errors).unwrap();
+    let lines = fs::read_to_string(&warnings_path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(lines.len(), 2);
+    for line in &lines {
+        assert_eq!(line["severity"], "warning");
+        assert_eq!(line["commit"], commit.to_string());
+    }
+    let mut paths = lines.iter().map(|line| &line["path"]).collect::<Vec<_>>();
+    paths.sort_by_key(|path| path.to_string());
+    assert_eq!(paths, ["broken.json", "courses.json"]);
+}
+
+#[test]
+fn warnings_are_written_when_the_run_aborts() {
+    let work_dir = WorkDir::new("warnings-abort");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(FixtureCommit::new("Initial sync").write_json("courses.json", &json!([])))
+        .unwrap();
+    fixture
+        .commit(FixtureCommit::new("Truncate").write("courses.json", "[{"))
+        .unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Duplicate")
+                .write_json("courses.json", &json!([{ "crn": "1" }, { "crn": "1" }])),
+        )
+        .unwrap();
+    let warnings_path = work_dir.join("warnings.ndjson");
+    let run = Command::new(env!("CARGO_BIN_EXE_delorean"))
+        .args([
+            fixture.path().to_str().unwrap(),
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--quiet",
+            "--max-errors",
+            "0",
+            "--warnings",
+            warnings_path.to_str().unwrap(),
+        ])
+        .output()
+        .unwrap();
+    let severities = fs::read_to_string(&warnings_path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["severity"].clone())
+        .collect::<Vec<_>>();
+
+    assert!(!run.status.success());
+    assert_eq!(severities, ["warning", "error"]);
+}
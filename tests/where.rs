@@ -0,0 +1,83 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::{json, Value};
+use std::path::Path;
+
+#[test]
+fn where_tracks_only_changes_to_matching_records() {
+    let work_dir = WorkDir::new("where");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    let mut sync = |message: &str, records: Value| {
+        fixture
+            .commit(FixtureCommit::new(message).write_json("courses.json", &records))
+            .unwrap();
+    };
+    sync("Initial sync", json!([]));
+    sync(
+        "Add",
+        json!([
+            { "crn": "1", "school": "YC", "seats": 1 },
+            { "crn": "2", "school": "SOM", "seats": 1 },
+            { "crn": "3", "school": "YC", "seats": 1 },
+        ]),
+    );
+    sync(
+        "Modify",
+        json!([
+            { "crn": "1", "school": "YC", "seats": 2 },
+            { "crn": "2", "school": "SOM", "seats": 2 },
+            // Moves out of YC; the modification is checked against the new version
+            { "crn": "3", "school": "SOM", "seats": 1 },
+        ]),
+    );
+    sync(
+        "Remove",
+        json!([{ "crn": "2", "school": "SOM", "seats": 2 }]),
+    );
+    let output_dir = work_dir.join("output");
+    let changes = |predicates: &[&str]| {
+        let mut arguments = vec![
+            output_dir.to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include",
+            "*.json",
+            "--quiet",
+        ];
+        for predicate in predicates {
+            arguments.extend(["--where", predicate]);
+        }
+        let extraction = extract::run(&common::extract_args(&fixture, &arguments)).unwrap();
+        let mut changes = extraction.change_records[Path::new("courses.json")]
+            .iter()
+            .map(|(key, record)| {
+                let counts = (
+                    record.added.len(),
+                    record.removed.len(),
+                    record.modified.len(),
+                );
+                (key.clone(), counts)
+            })
+            .filter(|(_, counts)| *counts != (0, 0, 0))
+            .collect::<Vec<_>>();
+        changes.sort();
+        changes
+    };
+
+    assert_eq!(
+        changes(&["/school == \"YC\""]),
+        [("1".to_string(), (1, 1, 1)), ("3".to_string(), (1, 0, 0)),]
+    );
+    assert_eq!(
+        changes(&["/school != \"YC\""]),
+        [("2".to_string(), (1, 0, 1)), ("3".to_string(), (0, 1, 1)),]
+    );
+    // All predicates must hold
+    assert_eq!(
+        changes(&["/school == \"YC\"", "/seats == 2"]),
+        [("1".to_string(), (0, 1, 1))]
+    );
+}
@@ -0,0 +1,42 @@
+use delorean::output;
+use std::path::{Path, PathBuf};
+
+#[test]
+fn windows_file_names_are_sanitized() {
+    for (name, sanitized) in [
+        ("202301.json", "202301.json"),
+        ("a:b?.json", "a%3Ab%3F.json"),
+        ("100%.json", "100%25.json"),
+        ("notes. ", "notes%2E%20"),
+        ("CON", "CO%4E"),
+        ("nul.json", "nu%6C.json"),
+        ("console.json", "console.json"),
+        ("tab\there", "tab%09here"),
+    ] {
+        assert_eq!(output::windows_file_name(name), sanitized);
+    }
+}
+
+#[test]
+fn output_paths_join_each_component() {
+    let output_path = output::output_file_path(Path::new("out"), Path::new("a/b/c.json.gz"));
+    let expected = if cfg!(windows) {
+        PathBuf::from(r"out\a\b\c.json")
+    } else {
+        PathBuf::from("out/a/b/c.json")
+    };
+    assert_eq!(output_path, expected);
+}
+
+#[cfg(windows)]
+#[test]
+fn local_paths_become_extended_length() {
+    assert_eq!(
+        output::extended_length(Path::new(r"C:\out")),
+        PathBuf::from(r"\\?\C:\out")
+    );
+    assert_eq!(
+        output::extended_length(Path::new(r"\\server\share\out")),
+        PathBuf::from(r"\\?\UNC\server\share\out")
+    );
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use common::WorkDir;
+use delorean::extract;
+use delorean::fixtures::{FixtureCommit, FixtureRepo};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn yaml_files_are_diffed_like_json() {
+    let work_dir = WorkDir::new("yaml");
+    let mut fixture = FixtureRepo::init(&work_dir.join("repo")).unwrap();
+    fixture
+        .commit(
+            FixtureCommit::new("Initial sync")
+                .write("courses.yaml", "[]\n")
+                .write_json("courses.json", &json!([])),
+        )
+        .unwrap();
+    let versions = [
+        (
+            "- crn: '1'\n  title: Intro\n- crn: '2'\n  title: Data\n",
+            json!([{ "crn": "1", "title": "Intro" }, { "crn": "2", "title": "Data" }]),
+        ),
+        (
+            "- crn: '1'\n  title: Intro to CS\n",
+            json!([{ "crn": "1", "title": "Intro to CS" }]),
+        ),
+    ];
+    for (i, (yaml, json)) in versions.iter().enumerate() {
+        fixture
+            .commit(
+                FixtureCommit::new(format!("Sync {i}"))
+                    .write("courses.yaml", *yaml)
+                    .write_json("courses.json", json),
+            )
+            .unwrap();
+    }
+    let args = common::extract_args(
+        &fixture,
+        &[
+            work_dir.join("output").to_str().unwrap(),
+            "--primary-key",
+            "crn",
+            "--include-values",
+            "full",
+            "--quiet",
+        ],
+    );
+    let extraction = extract::run(&args).unwrap();
+
+    let history =
+        |path: &str| serde_json::to_value(&extraction.change_records[Path::new(path)]).unwrap();
+    assert_eq!(history("courses.yaml"), history("courses.json"));
+    assert_eq!(
+        history("courses.yaml")["1"]["modified"][0]["new"],
+        json!({ "crn": "1", "title": "Intro to CS" })
+    );
+}